@@ -0,0 +1,30 @@
+use std::process::Command;
+
+/// Embeds the commit the binary is built from as `TWOTTY_GIT_SHA`, taken from
+/// `GIT_SHA` when it's set (as in the Docker build, which has no `.git`) or
+/// from git otherwise.
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+
+    let sha = match std::env::var("GIT_SHA") {
+        Ok(sha) => Some(sha),
+        Err(_) => {
+            if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+                println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+                println!("cargo:rerun-if-changed={}/logs/HEAD", git_dir);
+            }
+            git(&["rev-parse", "HEAD"])
+        }
+    };
+    if let Some(sha) = sha.filter(|sha| !sha.is_empty()) {
+        println!("cargo:rustc-env=TWOTTY_GIT_SHA={}", sha);
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
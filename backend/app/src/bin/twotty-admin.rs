@@ -0,0 +1,245 @@
+//! Manages the instance from the command line, with the same configuration as
+//! the server: `twotty-admin create-user alice --admin`, `twotty-admin stats`.
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use twotty::services::admin::AdminService;
+use twotty::utils::errors::AppError;
+use twotty::{db, routes, Config};
+
+#[derive(Parser)]
+#[command(name = "twotty-admin", version, about = "Manages a twotty instance")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the database migrations that haven't run yet
+    Migrate,
+    #[command(flatten)]
+    Manage(Manage),
+}
+
+/// Commands that go through the admin service
+#[derive(Subcommand)]
+enum Manage {
+    /// Creates a user, prompting for the password unless it's given
+    CreateUser {
+        username: String,
+        #[arg(long)]
+        password: Option<String>,
+        /// Lets the user manage the instance through the `/admin` endpoints
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Grants admin rights to a user, or takes them away with `--revoke`
+    SetAdmin {
+        username: String,
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Sets a new password and signs the user out everywhere
+    ResetPassword {
+        username: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Stops a user from signing in and signs them out everywhere
+    Suspend { username: String },
+    /// Lifts a suspension
+    Unsuspend { username: String },
+    /// Deletes a user and everything they own right away
+    DeleteUser {
+        username: String,
+        /// Confirms the deletion, it can't be undone
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Rebuilds the search indexes of posts and users
+    ReindexSearch,
+    /// Deletes trashed posts, accounts, data exports and webhook deliveries that are due
+    Purge {
+        /// Empties the whole trash instead of only the posts past the retention period
+        #[arg(long)]
+        all_trash: bool,
+    },
+    /// Prints counts of users, posts, media, jobs and webhooks
+    Stats,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let settings = match Config::load() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let filter = tracing_subscriber::filter::EnvFilter::default()
+        .add_directive(tracing::Level::WARN.into())
+        .add_directive("sqlx=error".parse().unwrap());
+    tracing_subscriber::fmt()
+        .compact()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+
+    match run(cli.command, &settings).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, settings: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Migrate => {
+            let pool = db::db_connection(settings).await?;
+            db::MIGRATOR.run(&pool).await?;
+            let latest = db::MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0);
+            println!("Database is migrated to version {}", latest);
+            Ok(())
+        }
+        Command::Manage(command) => {
+            let admin = routes::init_admin_service(settings).await?;
+            manage(command, &admin).await
+        }
+    }
+}
+
+async fn manage(command: Manage, admin: &AdminService) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Manage::CreateUser {
+            username,
+            password,
+            admin: is_admin,
+        } => {
+            let password = read_password(password)?;
+            let user = admin
+                .create_user(username, password, is_admin)
+                .await
+                .map_err(describe)?;
+            println!(
+                "Created {} `{}` with id {}",
+                if is_admin { "admin" } else { "user" },
+                user.username,
+                user.id
+            );
+        }
+        Manage::SetAdmin { username, revoke } => {
+            admin
+                .set_admin(&username, !revoke)
+                .await
+                .map_err(describe)?;
+            if revoke {
+                println!("`{}` is no longer an admin", username);
+            } else {
+                println!("`{}` is now an admin", username);
+            }
+        }
+        Manage::ResetPassword { username, password } => {
+            let password = read_password(password)?;
+            admin
+                .reset_password(&username, password)
+                .await
+                .map_err(describe)?;
+            println!("Reset the password of `{}` and signed them out", username);
+        }
+        Manage::Suspend { username } => {
+            if admin.suspend(&username).await.map_err(describe)? {
+                println!("Suspended `{}`", username);
+            } else {
+                println!("`{}` was suspended already", username);
+            }
+        }
+        Manage::Unsuspend { username } => {
+            if admin.unsuspend(&username).await.map_err(describe)? {
+                println!("Lifted the suspension of `{}`", username);
+            } else {
+                println!("`{}` isn't suspended", username);
+            }
+        }
+        Manage::DeleteUser { username, yes } => {
+            if !yes {
+                return Err(format!(
+                    "this deletes `{}` and everything they own for good, pass --yes to go ahead",
+                    username
+                )
+                .into());
+            }
+            let id = admin.delete_user(&username).await.map_err(describe)?;
+            println!("Deleted `{}` ({})", username, id);
+        }
+        Manage::ReindexSearch => {
+            admin.reindex_search().await.map_err(describe)?;
+            println!("Rebuilt the search indexes");
+        }
+        Manage::Purge { all_trash } => {
+            let report = admin.purge(all_trash).await.map_err(describe)?;
+            println!("Deleted {} posts from the trash", report.posts);
+            println!("Deleted {} accounts", report.accounts);
+            println!("Deleted {} expired data exports", report.data_exports);
+            println!(
+                "Deleted {} old webhook deliveries",
+                report.webhook_deliveries
+            );
+        }
+        Manage::Stats => {
+            let stats = admin.stats().await.map_err(describe)?;
+            let rows = [
+                ("users", stats.users),
+                ("admins", stats.admins),
+                ("suspended users", stats.suspended_users),
+                ("deletions scheduled", stats.deletions_scheduled),
+                ("posts", stats.posts),
+                ("scheduled posts", stats.scheduled_posts),
+                ("drafts", stats.drafts),
+                ("posts in trash", stats.posts_in_trash),
+                ("follows", stats.follows),
+                ("media", stats.media),
+                ("media bytes", stats.media_bytes),
+                ("pending jobs", stats.pending_jobs),
+                ("failed jobs", stats.failed_jobs),
+                ("webhooks", stats.webhooks),
+                ("disabled webhooks", stats.disabled_webhooks),
+            ];
+            for (name, count) in rows {
+                println!("{:<20} {}", name, count);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Uses the password given on the command line, or asks for it twice.
+fn read_password(password: Option<String>) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("Password: ")?;
+    if password != rpassword::prompt_password("Repeat password: ")? {
+        return Err("passwords don't match".into());
+    }
+    if password.is_empty() {
+        return Err("password can't be empty".into());
+    }
+    Ok(password)
+}
+
+/// Keeps the cause of database errors, which the API hides from users.
+fn describe(error: AppError) -> String {
+    match error {
+        AppError::Database(e) => format!("database error: {}", e),
+        AppError::DatabaseUnavailable(e) => format!("database unavailable: {}", e),
+        e => e.to_string(),
+    }
+}
@@ -1,21 +1,559 @@
-use serde::Deserialize;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+
+use axum::http::HeaderValue;
+use chrono::Duration;
+use serde::{Deserialize, Deserializer};
+use tracing_subscriber::EnvFilter;
+
+use crate::services::user::DeletedAccountContent;
+
+/// File read when `CONFIG_FILE` isn't set, with any extension the `config`
+/// crate knows (`twotty.toml`, `twotty.yaml`, ...). It's fine if it doesn't exist.
+const DEFAULT_CONFIG_FILE: &str = "twotty";
+
+const MINUTES_PER_YEAR: i64 = 365 * 24 * 60;
+/// Longest shutdown delay and timeout, the server waits for both added up
+const MAX_SHUTDOWN_SECONDS: u64 = 60 * 60;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Can't read configuration: {0}")]
+    Source(#[from] config::ConfigError),
+    #[error("Invalid configuration:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
+}
 
 #[derive(Deserialize, Clone, Debug, Default)]
 pub struct Config {
+    #[serde(default)]
     pub database_url: String,
+    #[serde(default)]
     pub jwt_secret: String,
-    pub frontend_origin: String,
+    /// Address the server listens on
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    /// How long running requests and jobs get to finish after SIGTERM or
+    /// Ctrl+C before the server exits anyway, in seconds
+    #[serde(default = "default_shutdown_timeout_seconds")]
+    pub shutdown_timeout_seconds: u64,
+    /// How long `/readyz` answers 503 before the server stops accepting
+    /// connections, so load balancers can take it out first, in seconds
+    #[serde(default)]
+    pub shutdown_delay_seconds: u64,
+    /// Origins allowed to call the API from a browser, comma separated in the
+    /// environment. `*` allows any origin.
+    #[serde(
+        default = "default_cors_origins",
+        deserialize_with = "deserialize_list"
+    )]
+    pub cors_origins: Vec<String>,
+    /// Extra allowed origin, kept for setups from before `CORS_ORIGINS`
+    pub frontend_origin: Option<String>,
+    /// How to write logs, `compact`, `pretty` or `json`
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
+    /// Which logs are written, in `RUST_LOG` syntax
+    #[serde(default = "default_log_filter")]
+    pub log_filter: String,
+    /// Most connections the pool opens to the database
+    #[serde(default = "default_database_max_connections")]
+    pub database_max_connections: u32,
+    /// Connections the pool keeps open even when idle
+    #[serde(default)]
+    pub database_min_connections: u32,
+    /// How long a query waits for a database connection before failing, in seconds
+    #[serde(default = "default_database_acquire_timeout_seconds")]
+    pub database_acquire_timeout_seconds: u64,
+    /// How long an unused connection is kept open, in seconds. 0 keeps it forever.
+    #[serde(default = "default_database_idle_timeout_seconds")]
+    pub database_idle_timeout_seconds: u64,
+    /// How long a connection is used before it's replaced, in seconds. 0 keeps it forever.
+    #[serde(default = "default_database_max_lifetime_seconds")]
+    pub database_max_lifetime_seconds: u64,
+    /// How long an access token is valid after login, in minutes
+    #[serde(default = "default_access_token_minutes")]
+    pub access_token_minutes: i64,
+    /// Most characters in the text of a post
+    #[serde(default = "default_max_post_length")]
+    pub max_post_length: usize,
+    /// How often trending hashtags are recomputed, in seconds
+    #[serde(default = "default_trending_refresh_seconds")]
+    pub trending_refresh_seconds: u64,
+    /// How long after creation a post can be edited, in minutes
+    #[serde(default = "default_post_edit_window_minutes")]
+    pub post_edit_window_minutes: i64,
+    /// How long deleted posts can be restored before they are purged, in days
+    #[serde(default = "default_post_trash_days")]
+    pub post_trash_days: i64,
+    /// How many posts a user can pin to their profile
+    #[serde(default = "default_max_pinned_posts")]
+    pub max_pinned_posts: i64,
+    /// How long a deleted account can be restored before it is purged, in days.
+    /// Accounts are deleted right away when this is 0.
+    #[serde(default = "default_account_deletion_grace_days")]
+    pub account_deletion_grace_days: i64,
+    /// What happens to the posts and messages of deleted accounts, `delete`
+    /// or `anonymize` to keep them under a placeholder user
+    #[serde(default = "default_deleted_account_content")]
+    pub deleted_account_content: String,
+    /// How long a finished data export can be downloaded before it's deleted, in hours
+    #[serde(default = "default_data_export_expiry_hours")]
+    pub data_export_expiry_hours: i64,
+    /// Largest archive and follows CSV that can be uploaded for an import, in bytes
+    #[serde(default = "default_import_max_bytes")]
+    pub import_max_bytes: usize,
+    /// How often due scheduled posts are published, in seconds
+    #[serde(default = "default_scheduler_interval_seconds")]
+    pub scheduler_interval_seconds: u64,
+    /// How many background jobs run at the same time
+    #[serde(default = "default_job_workers")]
+    pub job_workers: usize,
+    /// How many times a background job is tried before it's moved to the failed jobs
+    #[serde(default = "default_job_max_attempts")]
+    pub job_max_attempts: i32,
+    /// Wait before retrying a failed background job, doubled for every attempt, in seconds
+    #[serde(default = "default_job_retry_backoff_seconds")]
+    pub job_retry_backoff_seconds: i64,
+    /// Time limit for a webhook delivery attempt, in seconds
+    #[serde(default = "default_webhook_timeout_seconds")]
+    pub webhook_timeout_seconds: u64,
+    /// How many delivery attempts in a row can fail before a webhook is disabled
+    #[serde(default = "default_webhook_max_failures")]
+    pub webhook_max_failures: i32,
+    /// Lets webhooks be sent to loopback and private networks. Only meant
+    /// for tests against a local receiver.
+    #[serde(default)]
+    pub webhook_allow_private: bool,
+    /// Where uploaded media is kept, `local` or `s3`
+    #[serde(default = "default_media_store")]
+    pub media_store: String,
+    /// Directory for the `local` media store
+    #[serde(default = "default_media_dir")]
+    pub media_dir: String,
+    /// Largest accepted upload, in bytes
+    #[serde(default = "default_media_max_bytes")]
+    pub media_max_bytes: usize,
+    /// How many images can be processed at the same time
+    #[serde(default = "default_image_workers")]
+    pub image_workers: usize,
+    /// Time limit for fetching a linked page, in seconds
+    #[serde(default = "default_link_preview_timeout_seconds")]
+    pub link_preview_timeout_seconds: u64,
+    /// How much of a linked page is read, in bytes
+    #[serde(default = "default_link_preview_max_bytes")]
+    pub link_preview_max_bytes: usize,
+    #[serde(default = "default_link_preview_user_agent")]
+    pub link_preview_user_agent: String,
+    /// Lets link previews fetch from loopback and private networks. Only
+    /// meant for tests against a local server.
+    #[serde(default)]
+    pub link_preview_allow_private: bool,
+    #[serde(default)]
+    pub s3_bucket: String,
+    #[serde(default = "default_s3_region")]
+    pub s3_region: String,
+    /// Custom endpoint for S3-compatible stores such as MinIO
+    pub s3_endpoint: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<String>,
+}
+
+fn default_bind_address() -> String {
+    "0.0.0.0:8000".to_string()
+}
+
+fn default_shutdown_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_cors_origins() -> Vec<String> {
+    vec!["http://localhost:3000".to_string()]
+}
+
+fn default_log_format() -> String {
+    "compact".to_string()
+}
+
+fn default_log_filter() -> String {
+    "info,sqlx=error,tower_http=trace".to_string()
+}
+
+fn default_database_max_connections() -> u32 {
+    10
+}
+
+fn default_database_acquire_timeout_seconds() -> u64 {
+    5
+}
+
+fn default_database_idle_timeout_seconds() -> u64 {
+    10 * 60
+}
+
+fn default_database_max_lifetime_seconds() -> u64 {
+    30 * 60
+}
+
+fn default_access_token_minutes() -> i64 {
+    30
+}
+
+fn default_max_post_length() -> usize {
+    256
+}
+
+fn default_trending_refresh_seconds() -> u64 {
+    60
+}
+
+fn default_post_edit_window_minutes() -> i64 {
+    60
+}
+
+fn default_post_trash_days() -> i64 {
+    30
+}
+
+fn default_max_pinned_posts() -> i64 {
+    3
+}
+
+fn default_account_deletion_grace_days() -> i64 {
+    30
+}
+
+fn default_deleted_account_content() -> String {
+    "delete".to_string()
+}
+
+fn default_data_export_expiry_hours() -> i64 {
+    72
+}
+
+fn default_import_max_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_scheduler_interval_seconds() -> u64 {
+    10
+}
+
+fn default_job_workers() -> usize {
+    4
+}
+
+fn default_job_max_attempts() -> i32 {
+    5
+}
+
+fn default_job_retry_backoff_seconds() -> i64 {
+    30
+}
+
+fn default_webhook_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_webhook_max_failures() -> i32 {
+    10
+}
+
+fn default_media_store() -> String {
+    "local".to_string()
+}
+
+fn default_media_dir() -> String {
+    "media".to_string()
+}
+
+fn default_media_max_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_image_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn default_link_preview_timeout_seconds() -> u64 {
+    5
+}
+
+fn default_link_preview_max_bytes() -> usize {
+    512 * 1024
+}
+
+fn default_link_preview_user_agent() -> String {
+    format!("twotty/{} (link preview)", env!("CARGO_PKG_VERSION"))
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// Takes a list from a config file or a comma separated string from the environment.
+fn deserialize_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        Joined(String),
+        Items(Vec<String>),
+    }
+
+    Ok(match List::deserialize(deserializer)? {
+        List::Joined(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect(),
+        List::Items(items) => items,
+    })
+}
+
+/// Checks a setting that becomes a `chrono::Duration`. `Duration::days` and
+/// friends panic on values they can't represent, and long durations overflow
+/// once they are added to the current time, so the range is kept sane.
+fn check_duration(
+    errors: &mut Vec<String>,
+    name: &str,
+    value: i64,
+    range: RangeInclusive<i64>,
+    to_duration: fn(i64) -> Option<Duration>,
+) {
+    if !range.contains(&value) || to_duration(value).is_none() {
+        errors.push(format!(
+            "{} must be between {} and {}",
+            name,
+            range.start(),
+            range.end()
+        ));
+    }
 }
 
 impl Config {
-    pub fn from_env() -> Config {
+    /// Reads the config file named by `CONFIG_FILE`, then the environment
+    /// and `.env`, which override the file. Fails when a setting is invalid.
+    pub fn load() -> Result<Config, ConfigError> {
         dotenvy::dotenv().ok();
 
-        config::Config::builder()
+        let file = match std::env::var("CONFIG_FILE") {
+            Ok(path) => config::File::with_name(&path),
+            Err(_) => config::File::with_name(DEFAULT_CONFIG_FILE).required(false),
+        };
+        let settings: Config = config::Config::builder()
+            .add_source(file)
             .add_source(config::Environment::default())
-            .build()
-            .unwrap()
-            .try_deserialize()
-            .unwrap()
+            .build()?
+            .try_deserialize()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Origins allowed by CORS, including `frontend_origin`
+    pub fn allowed_origins(&self) -> Vec<String> {
+        let mut origins = self.cors_origins.clone();
+        origins.extend(
+            self.frontend_origin
+                .clone()
+                .filter(|origin| !origin.is_empty()),
+        );
+        origins
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        if self.database_url.is_empty() {
+            errors.push("DATABASE_URL must be set".to_string());
+        }
+        if self.jwt_secret.is_empty() {
+            errors.push("JWT_SECRET must be set".to_string());
+        }
+        if self.bind_address.parse::<SocketAddr>().is_err() {
+            errors.push(format!(
+                "BIND_ADDRESS `{}` isn't an address such as 0.0.0.0:8000",
+                self.bind_address
+            ));
+        }
+        let origins = self.allowed_origins();
+        if origins.len() > 1 && origins.iter().any(|origin| origin == "*") {
+            errors.push("CORS_ORIGINS can't mix `*` with other origins".to_string());
+        }
+        for origin in &origins {
+            if origin != "*" && origin.parse::<HeaderValue>().is_err() {
+                errors.push(format!("CORS origin `{}` isn't valid", origin));
+            }
+        }
+        if !["compact", "pretty", "json"].contains(&self.log_format.as_str()) {
+            errors.push(format!(
+                "LOG_FORMAT must be `compact`, `pretty` or `json`, not `{}`",
+                self.log_format
+            ));
+        }
+        if let Err(e) = EnvFilter::try_new(&self.log_filter) {
+            errors.push(format!("LOG_FILTER isn't valid: {}", e));
+        }
+        if self.database_max_connections == 0 {
+            errors.push("DATABASE_MAX_CONNECTIONS must be at least 1".to_string());
+        }
+        // A zero timeout fails every acquire, fetch and delivery
+        if self.database_acquire_timeout_seconds == 0 {
+            errors.push("DATABASE_ACQUIRE_TIMEOUT_SECONDS must be at least 1".to_string());
+        }
+        if self.webhook_timeout_seconds == 0 {
+            errors.push("WEBHOOK_TIMEOUT_SECONDS must be at least 1".to_string());
+        }
+        if self.link_preview_timeout_seconds == 0 {
+            errors.push("LINK_PREVIEW_TIMEOUT_SECONDS must be at least 1".to_string());
+        }
+        if self.shutdown_timeout_seconds > MAX_SHUTDOWN_SECONDS {
+            errors.push(format!(
+                "SHUTDOWN_TIMEOUT_SECONDS must be at most {}",
+                MAX_SHUTDOWN_SECONDS
+            ));
+        }
+        if self.shutdown_delay_seconds > MAX_SHUTDOWN_SECONDS {
+            errors.push(format!(
+                "SHUTDOWN_DELAY_SECONDS must be at most {}",
+                MAX_SHUTDOWN_SECONDS
+            ));
+        }
+        if self.database_min_connections > self.database_max_connections {
+            errors.push(
+                "DATABASE_MIN_CONNECTIONS can't be more than DATABASE_MAX_CONNECTIONS".to_string(),
+            );
+        }
+        check_duration(
+            &mut errors,
+            "ACCESS_TOKEN_MINUTES",
+            self.access_token_minutes,
+            1..=MINUTES_PER_YEAR,
+            Duration::try_minutes,
+        );
+        if self.max_post_length == 0 {
+            errors.push("MAX_POST_LENGTH must be at least 1".to_string());
+        }
+        // `tokio::time::interval` panics on a zero period
+        if self.trending_refresh_seconds == 0 {
+            errors.push("TRENDING_REFRESH_SECONDS must be at least 1".to_string());
+        }
+        if self.scheduler_interval_seconds == 0 {
+            errors.push("SCHEDULER_INTERVAL_SECONDS must be at least 1".to_string());
+        }
+        check_duration(
+            &mut errors,
+            "POST_EDIT_WINDOW_MINUTES",
+            self.post_edit_window_minutes,
+            0..=MINUTES_PER_YEAR,
+            Duration::try_minutes,
+        );
+        check_duration(
+            &mut errors,
+            "POST_TRASH_DAYS",
+            self.post_trash_days,
+            0..=10 * 365,
+            Duration::try_days,
+        );
+        if self.max_pinned_posts < 0 {
+            errors.push("MAX_PINNED_POSTS can't be negative".to_string());
+        }
+        check_duration(
+            &mut errors,
+            "ACCOUNT_DELETION_GRACE_DAYS",
+            self.account_deletion_grace_days,
+            0..=10 * 365,
+            Duration::try_days,
+        );
+        check_duration(
+            &mut errors,
+            "DATA_EXPORT_EXPIRY_HOURS",
+            self.data_export_expiry_hours,
+            1..=365 * 24,
+            Duration::try_hours,
+        );
+        // Doubled for every attempt, but never waits longer than an hour
+        check_duration(
+            &mut errors,
+            "JOB_RETRY_BACKOFF_SECONDS",
+            self.job_retry_backoff_seconds,
+            0..=60 * 60,
+            Duration::try_seconds,
+        );
+        if self.media_max_bytes == 0 {
+            errors.push("MEDIA_MAX_BYTES must be at least 1".to_string());
+        }
+        if self.import_max_bytes == 0 {
+            errors.push("IMPORT_MAX_BYTES must be at least 1".to_string());
+        }
+        if let Err(e) = self
+            .deleted_account_content
+            .parse::<DeletedAccountContent>()
+        {
+            errors.push(format!("DELETED_ACCOUNT_CONTENT: {}", e));
+        }
+        match self.media_store.as_str() {
+            "local" => {}
+            "s3" if self.s3_bucket.is_empty() => {
+                errors.push("S3_BUCKET must be set for the s3 media store".to_string())
+            }
+            "s3" => {}
+            other => errors.push(format!(
+                "MEDIA_STORE must be `local` or `s3`, not `{}`",
+                other
+            )),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors_of(config: Config) -> Vec<String> {
+        match config.validate() {
+            Err(ConfigError::Invalid(errors)) => errors,
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn huge_durations_are_reported() {
+        let errors = errors_of(Config {
+            account_deletion_grace_days: i64::MAX,
+            access_token_minutes: i64::MAX,
+            shutdown_timeout_seconds: u64::MAX,
+            ..Default::default()
+        });
+        assert!(
+            errors.contains(&"ACCOUNT_DELETION_GRACE_DAYS must be between 0 and 3650".to_string())
+        );
+        assert!(errors.contains(&"ACCESS_TOKEN_MINUTES must be between 1 and 525600".to_string()));
+        assert!(errors.contains(&"SHUTDOWN_TIMEOUT_SECONDS must be at most 3600".to_string()));
+    }
+
+    #[test]
+    fn zero_timeouts_are_reported() {
+        let errors = errors_of(Config {
+            database_acquire_timeout_seconds: 0,
+            webhook_timeout_seconds: 0,
+            link_preview_timeout_seconds: 0,
+            ..Default::default()
+        });
+        for name in [
+            "DATABASE_ACQUIRE_TIMEOUT_SECONDS",
+            "WEBHOOK_TIMEOUT_SECONDS",
+            "LINK_PREVIEW_TIMEOUT_SECONDS",
+        ] {
+            assert!(errors.contains(&format!("{} must be at least 1", name)));
+        }
     }
 }
@@ -1,10 +1,28 @@
+use std::time::Duration;
+
 use crate::Config;
+use sqlx::migrate::Migrator;
 use sqlx::postgres::{PgPool, PgPoolOptions};
 
+/// Migrations built into the binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
 pub async fn db_connection(settings: &Config) -> Result<PgPool, sqlx::Error> {
+    // Requests fail with 503 instead of queueing for long while the database is down
     let pull = PgPoolOptions::new()
+        .max_connections(settings.database_max_connections)
+        .min_connections(settings.database_min_connections)
+        .acquire_timeout(Duration::from_secs(
+            settings.database_acquire_timeout_seconds,
+        ))
+        .idle_timeout(seconds_or_never(settings.database_idle_timeout_seconds))
+        .max_lifetime(seconds_or_never(settings.database_max_lifetime_seconds))
         .connect(settings.database_url.as_str())
         .await?;
 
     Ok(pull)
 }
+
+fn seconds_or_never(seconds: u64) -> Option<Duration> {
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
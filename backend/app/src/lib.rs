@@ -6,6 +6,7 @@ pub mod routes;
 pub mod schemas;
 pub mod services;
 pub mod state;
+pub mod storage;
 pub mod utils;
 
 pub use config::Config;
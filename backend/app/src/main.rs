@@ -6,33 +6,146 @@ pub mod routes;
 pub mod schemas;
 pub mod services;
 pub mod state;
+pub mod storage;
 pub mod utils;
 
 pub use config::Config;
+use std::future::IntoFuture;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing_subscriber::EnvFilter;
+use utils::errors::StartupError;
 
 #[tokio::main]
-async fn main() {
-    let settings = Config::from_env();
-
-    let filter = tracing_subscriber::filter::EnvFilter::default()
-        .add_directive(tracing::Level::INFO.into())
-        .add_directive("sqlx=error".parse().unwrap())
-        .add_directive("tower_http=trace".parse().unwrap());
-
-    let subscriber = tracing_subscriber::fmt()
-        .compact()
-        .with_file(true)
-        .with_line_number(true)
-        .with_env_filter(filter)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).unwrap();
-
-    let db = db::db_connection(&settings).await.unwrap();
-    sqlx::migrate!("../migrations").run(&db).await.unwrap();
-
-    let app = routes::init_routers(&settings).await;
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await.unwrap();
-    tracing::info!("listening on http://{}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+async fn main() -> ExitCode {
+    let settings = match Config::load() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    init_tracing(&settings);
+
+    match run(settings).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(settings: Config) -> Result<(), StartupError> {
+    let db = db::db_connection(&settings).await?;
+    db::MIGRATOR.run(&db).await?;
+
+    let (shutdown, shutdown_receiver) = watch::channel(false);
+    let state = routes::init_state(&settings, db, shutdown_receiver.clone())?;
+    let mut tasks = state
+        .job_service
+        .spawn_workers(settings.job_workers, shutdown_receiver.clone());
+    tasks.push(state.explore_service.clone().spawn_trending_refresh(
+        Duration::from_secs(settings.trending_refresh_seconds),
+        shutdown_receiver.clone(),
+    ));
+    state.job_service.spawn_scheduler(shutdown_receiver.clone());
+    let job_service = state.job_service.clone();
+    let app = routes::init_routers(state);
+
+    let listener = tokio::net::TcpListener::bind(&settings.bind_address)
+        .await
+        .map_err(|e| StartupError::Bind(settings.bind_address.clone(), e))?;
+    tracing::info!("listening on http://{}", settings.bind_address);
+    let mut server = tokio::spawn(
+        axum::serve(listener, app)
+            .with_graceful_shutdown(stopped(
+                shutdown_receiver,
+                Duration::from_secs(settings.shutdown_delay_seconds),
+            ))
+            .into_future(),
+    );
+    let server_result = tokio::select! {
+        _ = shutdown_signal() => None,
+        result = &mut server => Some(result),
+    };
+
+    // Readiness turns off and the workers stop claiming jobs right away, the
+    // server stops accepting connections after the delay. What's running
+    // already gets until the timeout to finish
+    tracing::info!("Shutting down, waiting for running requests and jobs");
+    shutdown.send_replace(true);
+    let drain_timeout =
+        Duration::from_secs(settings.shutdown_delay_seconds + settings.shutdown_timeout_seconds);
+    let drained = tokio::time::timeout(drain_timeout, async {
+        let result = match server_result {
+            Some(result) => result,
+            None => server.await,
+        };
+        match result {
+            Ok(Err(e)) => tracing::error!("Server error: {}", e),
+            Err(e) => tracing::error!("Server task failed: {}", e),
+            Ok(Ok(())) => {}
+        }
+        for task in tasks {
+            let _ = task.await;
+        }
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "Requests or jobs still running after {} seconds, stopping anyway",
+            drain_timeout.as_secs()
+        );
+        match job_service.release_running().await {
+            Ok(0) => {}
+            Ok(released) => tracing::info!("Put {} running jobs back in the queue", released),
+            Err(e) => tracing::error!("Can't put running jobs back in the queue: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Resolves `delay` after the shutdown has started.
+async fn stopped(mut shutdown: watch::Receiver<bool>, delay: Duration) {
+    let _ = shutdown.wait_for(|stop| *stop).await;
+    tokio::time::sleep(delay).await;
+}
+
+/// Writes logs to stdout in the configured `LOG_FORMAT`. The filter was
+/// checked when the config was loaded.
+fn init_tracing(settings: &Config) {
+    let filter = EnvFilter::new(&settings.log_filter);
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match settings.log_format.as_str() {
+        "json" => builder.json().init(),
+        "pretty" => builder.pretty().init(),
+        _ => builder
+            .compact()
+            .with_file(true)
+            .with_line_number(true)
+            .init(),
+    }
+}
+
+/// Resolves on Ctrl+C or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.unwrap();
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .unwrap()
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConversationMember {
+    pub conversation_id: Uuid,
+    pub user_id: Uuid,
+    pub joined_at: NaiveDateTime,
+    pub last_read_at: Option<NaiveDateTime>,
+}
+
+/// Conversation as seen by one of its members, with the latest message inlined.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConversationPreview {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub member_ids: Vec<Uuid>,
+    pub last_read_at: Option<NaiveDateTime>,
+    pub unread_count: i64,
+    pub last_message_id: Option<Uuid>,
+    pub last_message_sender_id: Option<Uuid>,
+    pub last_message_text: Option<String>,
+    pub last_message_created_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Archive of everything a user has stored, built in the background.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// `pending`, `running`, `ready`, `failed` or `expired`
+    pub status: String,
+    /// Key of the archive in the media store, set once it's ready
+    pub storage_key: Option<String>,
+    pub size: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    /// Refreshed by the worker while it builds the export
+    pub locked_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    /// When the archive gets deleted
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl DataExport {
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+
+    /// Whether the export is still waiting for or being built by the worker
+    pub fn is_in_progress(&self) -> bool {
+        self.status == "pending" || self.status == "running"
+    }
+}
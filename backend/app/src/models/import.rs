@@ -0,0 +1,37 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Posts and follows brought over from another network, run in the background.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Import {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// `twitter` or `mastodon`, `None` when only follows are imported
+    pub source: Option<String>,
+    /// `pending`, `running`, `done` or `failed`
+    pub status: String,
+    pub archive_key: Option<String>,
+    pub follows_key: Option<String>,
+    /// Number of posts and follows found in the uploaded files
+    pub total: i32,
+    pub processed: i32,
+    pub posts_created: i32,
+    /// Posts that were empty, too long or imported before
+    pub posts_skipped: i32,
+    pub follows_created: i32,
+    /// Why the import failed
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    /// Refreshed by the worker while it runs the import
+    pub locked_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+impl Import {
+    /// Whether the import is still waiting for or being run by the worker
+    pub fn is_in_progress(&self) -> bool {
+        self.status == "pending" || self.status == "running"
+    }
+}
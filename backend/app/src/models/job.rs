@@ -0,0 +1,69 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Work done in the background by the job workers. Stored as JSON, the
+/// variant name becomes the `kind` of the job.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobPayload {
+    /// Fetches and caches the preview of a link in a post
+    FetchLinkPreview {
+        url: String,
+    },
+    PublishScheduledPosts,
+    ClosePolls,
+    PurgeTrash,
+    PurgeDeletedAccounts,
+    BuildDataExports,
+    PurgeDataExports,
+    RunImports,
+    /// Makes an attempt at sending an event to a webhook
+    DeliverWebhook {
+        delivery_id: Uuid,
+    },
+    PurgeWebhookDeliveries,
+}
+
+impl JobPayload {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::FetchLinkPreview { .. } => "fetch_link_preview",
+            Self::PublishScheduledPosts => "publish_scheduled_posts",
+            Self::ClosePolls => "close_polls",
+            Self::PurgeTrash => "purge_trash",
+            Self::PurgeDeletedAccounts => "purge_deleted_accounts",
+            Self::BuildDataExports => "build_data_exports",
+            Self::PurgeDataExports => "purge_data_exports",
+            Self::RunImports => "run_imports",
+            Self::DeliverWebhook { .. } => "deliver_webhook",
+            Self::PurgeWebhookDeliveries => "purge_webhook_deliveries",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    /// Serialized [`JobPayload`]
+    pub payload: serde_json::Value,
+    /// `pending`, `running` or `failed` once it ran out of attempts
+    pub status: String,
+    pub attempts: i32,
+    /// When the job can run, pushed back after a failed attempt
+    pub run_at: NaiveDateTime,
+    /// When a worker took the job, refreshed while it runs
+    pub locked_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub failed_at: Option<NaiveDateTime>,
+    /// Set by the worker that claimed the job, changes when it's taken over
+    pub lease_id: Option<Uuid>,
+}
+
+impl Job {
+    pub fn is_failed(&self) -> bool {
+        self.status == "failed"
+    }
+}
@@ -0,0 +1,15 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Metadata of a linked page. Pages that couldn't be fetched are stored with
+/// empty fields so they aren't fetched again right away.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LinkPreview {
+    /// Normalized url of the page
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+    pub fetched_at: NaiveDateTime,
+}
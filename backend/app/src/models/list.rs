@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct List {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub name: String,
+    /// Private lists are only visible to their owner
+    pub private: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListMemberCount {
+    pub list_id: Uuid,
+    pub count: i64,
+}
@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Media {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    /// Post the file is attached to, `None` until the post is created
+    pub post_id: Option<Uuid>,
+    pub position: i16,
+    pub storage_key: String,
+    pub mime: String,
+    pub size: i64,
+    pub created_at: NaiveDateTime,
+    /// Image size in pixels, `None` for videos
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    /// Keys of the resized copies, `None` when the file is served as uploaded
+    pub small_key: Option<String>,
+    pub thumbnail_key: Option<String>,
+}
@@ -1,3 +1,16 @@
+pub mod bookmark;
+pub mod conversation;
+pub mod data_export;
 pub mod following;
+pub mod import;
+pub mod job;
+pub mod link_preview;
+pub mod list;
+pub mod media;
+pub mod message;
+pub mod poll;
 pub mod post;
+pub mod search;
+pub mod stats;
 pub mod user;
+pub mod webhook;
@@ -0,0 +1,44 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Poll {
+    pub post_id: Uuid,
+    /// Whether voters can pick more than one option
+    pub multiple: bool,
+    pub expires_at: NaiveDateTime,
+    /// When the author was notified that the poll closed
+    pub closed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PollOption {
+    pub post_id: Uuid,
+    pub position: i16,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PollVote {
+    pub post_id: Uuid,
+    pub user_id: Uuid,
+    /// Positions of the chosen options
+    pub options: Vec<i16>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Number of votes for one option of a poll
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PollOptionCount {
+    pub post_id: Uuid,
+    pub position: i16,
+    pub count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PollVoterCount {
+    pub post_id: Uuid,
+    pub count: i64,
+}
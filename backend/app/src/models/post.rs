@@ -11,6 +11,22 @@ pub struct Post {
     pub author_id: Uuid,
     pub created_at: NaiveDateTime,
     pub edited: bool,
+    pub edited_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+    /// Drafts are only visible to their author until published
+    pub draft: bool,
+    /// When a scheduled post will be published, `None` once it is
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// When the author pinned the post to their profile
+    pub pinned_at: Option<NaiveDateTime>,
+    /// Post this one replies to
+    pub reply_to_id: Option<Uuid>,
+    /// Id of the post on the network it was imported from, `twitter:<id>` or `mastodon:<uri>`
+    pub source_id: Option<String>,
+    /// Whether the user the post was loaded for bookmarked it. Only queries
+    /// that load posts for display set it.
+    #[serde(skip)]
+    pub bookmarked_by_me: bool,
 }
 
 impl Post {
@@ -21,18 +37,73 @@ impl Post {
             author_id,
             created_at: Utc::now().naive_utc(),
             edited: false,
+            edited_at: None,
+            deleted_at: None,
+            draft: false,
+            scheduled_at: None,
+            pinned_at: None,
+            reply_to_id: None,
+            source_id: None,
+            bookmarked_by_me: false,
         }
     }
+
+    pub fn is_published(&self) -> bool {
+        !self.draft && self.scheduled_at.is_none()
+    }
 }
 
-impl Into<PostSchema> for Post {
-    fn into(self) -> PostSchema {
+impl From<Post> for PostSchema {
+    fn from(value: Post) -> Self {
         PostSchema {
-            id: self.id,
-            author_id: self.author_id,
-            text: self.text,
-            created_at: self.created_at,
-            edited: self.edited,
+            id: value.id,
+            author_id: value.author_id,
+            text: value.text,
+            created_at: value.created_at,
+            edited: value.edited,
+            edited_at: value.edited_at,
+            revision_count: 0,
+            deleted_at: value.deleted_at,
+            draft: value.draft,
+            scheduled_at: value.scheduled_at,
+            pinned: value.pinned_at.is_some(),
+            reply_to_id: value.reply_to_id,
+            reply_to_deleted: false,
+            entities: Vec::new(),
+            media: Vec::new(),
+            card: None,
+            poll: None,
+            bookmarked_by_me: value.bookmarked_by_me,
         }
     }
 }
+
+/// Resolved `@mention` of a post, joined with the mentioned user's current username.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostMention {
+    pub post_id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+}
+
+/// Single use of a hashtag, timestamped with the post creation time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagUse {
+    pub tag: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Earlier version of a post text, `created_at` is when that version was written.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostRevision {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub text: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevisionCount {
+    pub post_id: Uuid,
+    pub count: i64,
+}
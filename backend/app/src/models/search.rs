@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Id of an entity matching a search query, with its rank and a highlighted snippet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: Uuid,
+    pub rank: f32,
+    pub snippet: String,
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Counts of what the instance holds, for operators
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceStats {
+    pub users: i64,
+    pub admins: i64,
+    pub suspended_users: i64,
+    /// Accounts waiting for their grace period to end
+    pub deletions_scheduled: i64,
+    /// Published posts, the trash left out
+    pub posts: i64,
+    pub scheduled_posts: i64,
+    pub drafts: i64,
+    pub posts_in_trash: i64,
+    pub follows: i64,
+    pub media: i64,
+    /// Size of the uploaded originals, in bytes
+    pub media_bytes: i64,
+    pub pending_jobs: i64,
+    pub failed_jobs: i64,
+    pub webhooks: i64,
+    pub disabled_webhooks: i64,
+}
@@ -1,12 +1,38 @@
+use chrono::NaiveDateTime;
 use uuid::Uuid;
 
 use serde::{Deserialize, Serialize};
 
+/// Placeholder author of the anonymized posts and messages of deleted accounts
+pub const DELETED_USER_ID: Uuid = Uuid::nil();
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
     pub password: String,
-    pub avatar: Option<String>,
     pub about: String,
+    pub dms_from_followers_only: bool,
+    /// Hotlinked profile picture from before uploads, used until the user
+    /// uploads a picture or resets it
+    pub avatar: Option<String>,
+    /// Uploaded media shown as the profile picture
+    pub avatar_media_id: Option<Uuid>,
+    pub banner_media_id: Option<Uuid>,
+    /// When the account gets purged, unless the deletion is cancelled first
+    pub deletion_scheduled_at: Option<NaiveDateTime>,
+    /// Tokens issued before this are rejected
+    pub tokens_valid_after: NaiveDateTime,
+    /// Can manage the instance through the `/admin` endpoints
+    pub is_admin: bool,
+    /// Suspended accounts can't sign in and their tokens are rejected
+    pub suspended_at: Option<NaiveDateTime>,
+}
+
+impl User {
+    /// Whether a token issued at `iat`, in seconds since the epoch, is still
+    /// accepted. Suspended accounts accept none.
+    pub fn accepts_token(&self, iat: usize) -> bool {
+        self.suspended_at.is_none() && iat as i64 >= self.tokens_valid_after.and_utc().timestamp()
+    }
 }
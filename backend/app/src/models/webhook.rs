@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Events a webhook can subscribe to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEvent {
+    PostCreated,
+    PostDeleted,
+    UserFollowed,
+    Mention,
+    PollClosed,
+    /// Sent from the "send test event" action only, it can't be subscribed to
+    Test,
+}
+
+impl WebhookEvent {
+    /// Events that can be subscribed to
+    pub const SUBSCRIBABLE: [Self; 5] = [
+        Self::PostCreated,
+        Self::PostDeleted,
+        Self::UserFollowed,
+        Self::Mention,
+        Self::PollClosed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PostCreated => "post.created",
+            Self::PostDeleted => "post.deleted",
+            Self::UserFollowed => "user.followed",
+            Self::Mention => "mention",
+            Self::PollClosed => "poll.closed",
+            Self::Test => "test",
+        }
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::SUBSCRIBABLE
+            .into_iter()
+            .find(|event| event.as_str() == s)
+            .ok_or_else(|| format!("unknown webhook event `{s}`"))
+    }
+}
+
+/// Endpoint that is sent the events of its owner, or of every user when an
+/// admin set `all_users`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub url: String,
+    /// Key of the HMAC-SHA256 signature sent with every delivery
+    pub secret: String,
+    pub events: Vec<String>,
+    pub all_users: bool,
+    pub enabled: bool,
+    /// Failed attempts in a row
+    pub failure_count: i32,
+    /// When it was disabled for failing
+    pub disabled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// An event sent to a webhook. Every attempt sends the same body with the
+/// delivery id, so receivers can tell retries apart from new events.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// `pending`, `delivered` or `failed` once it ran out of attempts
+    pub status: String,
+    pub attempts: i32,
+    /// Status code of the last response, `None` when no response came
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+impl WebhookDelivery {
+    pub fn is_pending(&self) -> bool {
+        self.status == "pending"
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository};
+use crate::models::bookmark::Bookmark;
+
+pub struct CreateBookmarkDTO {
+    pub user_id: Uuid,
+    pub post_id: Uuid,
+}
+
+pub struct UpdateBookmarkDTO {
+    pub folder_id: Option<Uuid>,
+}
+
+pub struct BookmarkFindAllParams {
+    pub user_id: Uuid,
+    /// Only bookmarks in this folder
+    pub folder_id: Option<Uuid>,
+    /// Only bookmarks created strictly before this moment
+    pub before: Option<NaiveDateTime>,
+    pub limit: i64,
+}
+
+#[derive(Clone)]
+pub struct BookmarkRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for BookmarkRepository {
+    type Model = Bookmark;
+    type Id = Uuid;
+    type CreateDTO = CreateBookmarkDTO;
+    type UpdateDTO = UpdateBookmarkDTO;
+    type FindAllParams = BookmarkFindAllParams;
+
+    /// Bookmarks the post, or returns the existing bookmark.
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            Bookmark,
+            r#"INSERT INTO "bookmark" (id, user_id, post_id) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, post_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            RETURNING *"#,
+            Uuid::new_v4(),
+            data.user_id,
+            data.post_id
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Bookmark, r#"SELECT * FROM "bookmark" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Newest bookmarks first. Bookmarks of posts that are no longer visible are skipped.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Bookmark,
+            r#"SELECT b.* FROM "bookmark" b
+            JOIN "post" p ON p.id = b.post_id
+            WHERE b.user_id = $1
+            AND ($2::uuid IS NULL OR b.folder_id = $2)
+            AND ($3::timestamp IS NULL OR b.created_at < $3)
+            AND p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL
+            ORDER BY b.created_at DESC
+            LIMIT $4"#,
+            params.user_id,
+            params.folder_id,
+            params.before,
+            params.limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "bookmark" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves the bookmark to another folder, `None` takes it out of its folder.
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "bookmark" SET folder_id = $2 WHERE id = $1"#,
+            id,
+            data.folder_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl BookmarkRepository {
+    pub async fn find_one_by_post(
+        &self,
+        user_id: &Uuid,
+        post_id: &Uuid,
+    ) -> Result<Option<Bookmark>, RepoError> {
+        Ok(sqlx::query_as!(
+            Bookmark,
+            r#"SELECT * FROM "bookmark" WHERE user_id = $1 AND post_id = $2"#,
+            user_id,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Every bookmark of the user, including those of posts that are no longer visible, oldest first.
+    pub async fn find_all_of_user(&self, user_id: &Uuid) -> Result<Vec<Bookmark>, RepoError> {
+        Ok(sqlx::query_as!(
+            Bookmark,
+            r#"SELECT * FROM "bookmark" WHERE user_id = $1 ORDER BY created_at"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
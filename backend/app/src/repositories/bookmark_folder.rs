@@ -0,0 +1,100 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository};
+use crate::models::bookmark::BookmarkFolder;
+
+pub struct CreateBookmarkFolderDTO {
+    pub user_id: Uuid,
+    pub name: String,
+}
+
+pub struct UpdateBookmarkFolderDTO {
+    pub name: String,
+}
+
+pub struct BookmarkFolderFindAllParams {
+    pub user_id: Uuid,
+}
+
+#[derive(Clone)]
+pub struct BookmarkFolderRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for BookmarkFolderRepository {
+    type Model = BookmarkFolder;
+    type Id = Uuid;
+    type CreateDTO = CreateBookmarkFolderDTO;
+    type UpdateDTO = UpdateBookmarkFolderDTO;
+    type FindAllParams = BookmarkFolderFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            BookmarkFolder,
+            r#"INSERT INTO "bookmark_folder" (id, user_id, name) VALUES ($1, $2, $3) RETURNING *"#,
+            Uuid::new_v4(),
+            data.user_id,
+            data.name
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            BookmarkFolder,
+            r#"SELECT * FROM "bookmark_folder" WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            BookmarkFolder,
+            r#"SELECT * FROM "bookmark_folder" WHERE user_id = $1 ORDER BY name"#,
+            params.user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Bookmarks in the folder are kept and lose their folder.
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "bookmark_folder" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "bookmark_folder" SET name = $2 WHERE id = $1"#,
+            id,
+            data.name
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl BookmarkFolderRepository {
+    pub async fn find_one_by_name(
+        &self,
+        user_id: &Uuid,
+        name: &str,
+    ) -> Result<Option<BookmarkFolder>, RepoError> {
+        Ok(sqlx::query_as!(
+            BookmarkFolder,
+            r#"SELECT * FROM "bookmark_folder" WHERE user_id = $1 AND name = $2"#,
+            user_id,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+}
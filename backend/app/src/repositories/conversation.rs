@@ -0,0 +1,233 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::conversation::{Conversation, ConversationMember, ConversationPreview};
+
+pub struct CreateConversationDTO {
+    pub member_ids: Vec<Uuid>,
+}
+
+pub struct ConversationFindAllParams {
+    pub user_id: Uuid,
+}
+
+#[derive(Clone)]
+pub struct ConversationRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for ConversationRepository {
+    type Model = Conversation;
+    type Id = Uuid;
+    type CreateDTO = CreateConversationDTO;
+    type UpdateDTO = ();
+    type FindAllParams = ConversationFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        let conversation = self.create_in(&mut uow, data).await?;
+        uow.commit().await?;
+        Ok(conversation)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Conversation,
+            r#"SELECT id, created_at FROM "conversation" WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Conversation,
+            r#"SELECT c.id, c.created_at FROM "conversation" c
+            JOIN "conversation_member" m ON m.conversation_id = c.id
+            WHERE m.user_id = $1 ORDER BY c.created_at DESC"#,
+            params.user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "conversation" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+impl ConversationRepository {
+    /// Creates the conversation with its members. Two members make a
+    /// one-to-one conversation, and if the pair already has one that one is
+    /// returned instead.
+    pub async fn create_in(
+        &self,
+        uow: &mut UnitOfWork,
+        data: CreateConversationDTO,
+    ) -> Result<Conversation, RepoError> {
+        let id = Uuid::new_v4();
+        let direct = match data.member_ids.as_slice() {
+            [first, second] => Some((*first.min(second), *first.max(second))),
+            _ => None,
+        };
+        // The no-op update makes a conflicting insert wait for the other one
+        // and return its row
+        let conversation = sqlx::query_as!(
+            Conversation,
+            r#"INSERT INTO "conversation" (id, direct_first_id, direct_second_id) VALUES ($1, $2, $3)
+            ON CONFLICT (direct_first_id, direct_second_id)
+            DO UPDATE SET direct_first_id = EXCLUDED.direct_first_id
+            RETURNING id, created_at"#,
+            id,
+            direct.map(|(first, _)| first),
+            direct.map(|(_, second)| second)
+        )
+        .fetch_one(uow.conn())
+        .await?;
+        if conversation.id != id {
+            return Ok(conversation);
+        }
+
+        sqlx::query!(
+            r#"INSERT INTO "conversation_member" (conversation_id, user_id) SELECT $1, unnest($2::uuid[])"#,
+            id,
+            &data.member_ids
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(conversation)
+    }
+
+    /// Removes the user from all of their conversations and deletes the ones
+    /// nobody is left in
+    pub async fn leave_all(&self, uow: &mut UnitOfWork, user_id: &Uuid) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"DELETE FROM "conversation_member" WHERE user_id = $1"#,
+            user_id
+        )
+        .execute(uow.conn())
+        .await?;
+        sqlx::query!(
+            r#"DELETE FROM "conversation" c
+            WHERE NOT EXISTS (SELECT 1 FROM "conversation_member" WHERE conversation_id = c.id)"#
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_members(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<ConversationMember>, RepoError> {
+        Ok(sqlx::query_as!(
+            ConversationMember,
+            r#"SELECT * FROM "conversation_member" WHERE conversation_id = $1 ORDER BY joined_at"#,
+            conversation_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<ConversationMember>, RepoError> {
+        Ok(sqlx::query_as!(
+            ConversationMember,
+            r#"SELECT * FROM "conversation_member" WHERE conversation_id = $1 AND user_id = $2"#,
+            conversation_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Finds the one-to-one conversation between two users, if it exists.
+    pub async fn find_one_direct(
+        &self,
+        first_id: &Uuid,
+        second_id: &Uuid,
+    ) -> Result<Option<Conversation>, RepoError> {
+        Ok(sqlx::query_as!(
+            Conversation,
+            r#"SELECT id, created_at FROM "conversation"
+            WHERE direct_first_id = LEAST($1::uuid, $2::uuid)
+            AND direct_second_id = GREATEST($1::uuid, $2::uuid)"#,
+            first_id,
+            second_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Lists the user's conversations, newest activity first. Pass `conversation_id`
+    /// to narrow the result down to a single conversation.
+    pub async fn find_previews(
+        &self,
+        user_id: &Uuid,
+        conversation_id: Option<&Uuid>,
+    ) -> Result<Vec<ConversationPreview>, RepoError> {
+        Ok(sqlx::query_as!(
+            ConversationPreview,
+            r#"SELECT
+                c.id,
+                c.created_at,
+                ARRAY(
+                    SELECT user_id FROM "conversation_member"
+                    WHERE conversation_id = c.id ORDER BY joined_at
+                ) AS "member_ids!",
+                m.last_read_at,
+                (
+                    SELECT COUNT(*) FROM "message"
+                    WHERE conversation_id = c.id AND sender_id <> $1
+                    AND (m.last_read_at IS NULL OR created_at > m.last_read_at)
+                ) AS "unread_count!",
+                lm.id AS "last_message_id?",
+                lm.sender_id AS "last_message_sender_id?",
+                lm.text AS "last_message_text?",
+                lm.created_at AS "last_message_created_at?"
+            FROM "conversation_member" m
+            JOIN "conversation" c ON c.id = m.conversation_id
+            LEFT JOIN LATERAL (
+                SELECT * FROM "message" WHERE conversation_id = c.id
+                ORDER BY created_at DESC LIMIT 1
+            ) lm ON TRUE
+            WHERE m.user_id = $1 AND ($2::uuid IS NULL OR c.id = $2)
+            ORDER BY COALESCE(lm.created_at, c.created_at) DESC"#,
+            user_id,
+            conversation_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn mark_read(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        at: NaiveDateTime,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "conversation_member" SET last_read_at = $3 WHERE conversation_id = $1 AND user_id = $2"#,
+            conversation_id,
+            user_id,
+            at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
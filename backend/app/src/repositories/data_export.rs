@@ -0,0 +1,184 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::data_export::DataExport;
+
+pub struct CreateDataExportDTO {
+    pub user_id: Uuid,
+}
+
+pub struct DataExportFindAllParams {
+    pub user_id: Uuid,
+}
+
+#[derive(Clone)]
+pub struct DataExportRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for DataExportRepository {
+    type Model = DataExport;
+    type Id = Uuid;
+    type CreateDTO = CreateDataExportDTO;
+    type UpdateDTO = ();
+    type FindAllParams = DataExportFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            DataExport,
+            r#"INSERT INTO "data_export" (id, user_id) VALUES ($1, $2) RETURNING *"#,
+            Uuid::new_v4(),
+            data.user_id
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            DataExport,
+            r#"SELECT * FROM "data_export" WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Exports of the user, newest first.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            DataExport,
+            r#"SELECT * FROM "data_export" WHERE user_id = $1 ORDER BY created_at DESC"#,
+            params.user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "data_export" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+impl DataExportRepository {
+    /// Takes the oldest pending export for building. Running exports whose
+    /// lock wasn't refreshed since `stale_before` are taken again, their
+    /// worker is assumed to have died.
+    pub async fn claim_next(
+        &self,
+        now: NaiveDateTime,
+        stale_before: NaiveDateTime,
+    ) -> Result<Option<DataExport>, RepoError> {
+        Ok(sqlx::query_as!(
+            DataExport,
+            r#"UPDATE "data_export" SET status = 'running', started_at = $1, locked_at = $1
+            WHERE id = (
+                SELECT id FROM "data_export"
+                WHERE status = 'pending' OR (status = 'running' AND locked_at < $2)
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *"#,
+            now,
+            stale_before
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Tells other workers the export is still being built
+    pub async fn heartbeat(&self, id: &Uuid, now: NaiveDateTime) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "data_export" SET locked_at = $2 WHERE id = $1 AND status = 'running'"#,
+            id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mark_ready(
+        &self,
+        id: &Uuid,
+        storage_key: &str,
+        size: i64,
+        now: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "data_export"
+            SET status = 'ready', storage_key = $2, size = $3, finished_at = $4, expires_at = $5
+            WHERE id = $1"#,
+            id,
+            storage_key,
+            size,
+            now,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mark_failed(&self, id: &Uuid, now: NaiveDateTime) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "data_export" SET status = 'failed', finished_at = $2 WHERE id = $1"#,
+            id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Ready exports that are past their expiry, whose archives are due for deletion
+    pub async fn find_expired(&self, now: NaiveDateTime) -> Result<Vec<DataExport>, RepoError> {
+        Ok(sqlx::query_as!(
+            DataExport,
+            r#"SELECT * FROM "data_export" WHERE status = 'ready' AND expires_at <= $1"#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Marks a ready export as expired once its archive is deleted. Returns
+    /// `false` when it isn't ready any more.
+    pub async fn expire(&self, id: &Uuid) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "data_export" SET status = 'expired', storage_key = NULL
+            WHERE id = $1 AND status = 'ready'"#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Keys of the user's stored archives
+    pub async fn find_storage_keys(
+        &self,
+        uow: &mut UnitOfWork,
+        user_id: &Uuid,
+    ) -> Result<Vec<String>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT storage_key AS "storage_key!" FROM "data_export"
+            WHERE user_id = $1 AND storage_key IS NOT NULL"#,
+            user_id
+        )
+        .fetch_all(uow.conn())
+        .await?)
+    }
+}
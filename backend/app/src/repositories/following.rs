@@ -3,7 +3,7 @@ use uuid::Uuid;
 
 use crate::models::following::Following;
 
-use super::Repository;
+use super::{RepoError, Repository, UnitOfWork};
 
 #[derive(Clone)]
 pub struct FollowingRepository {
@@ -27,7 +27,7 @@ impl Repository for FollowingRepository {
     type UpdateDTO = ();
     type FindAllParams = FindAllFollowingsParams;
 
-    async fn create(&self, data: Self::CreateDTO) -> Self::Model {
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
         let id = Uuid::new_v4();
         sqlx::query!(
             r#"INSERT INTO "following" (id, from_id, to_id) VALUES ($1, $2, $3)"#,
@@ -36,86 +36,125 @@ impl Repository for FollowingRepository {
             data.to_id
         )
         .execute(&self.pool)
-        .await
-        .unwrap();
+        .await?;
 
-        Following {
+        Ok(Following {
             id,
             from_id: data.from_id,
             to_id: data.to_id,
-        }
+        })
     }
 
-    async fn find_one(&self, id: &Self::Id) -> Option<Self::Model> {
-        sqlx::query_as!(Following, r#"SELECT * FROM "following" WHERE id = $1"#, id)
-            .fetch_optional(&self.pool)
-            .await
-            .unwrap()
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Following, r#"SELECT * FROM "following" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
     }
 
-    async fn find_all(&self, params: Self::FindAllParams) -> Vec<Self::Model> {
-        sqlx::query_as!(
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
             Following,
             r#"SELECT * FROM "following" WHERE from_id = $1"#,
             params.from_id
         )
         .fetch_all(&self.pool)
-        .await
-        .unwrap()
+        .await?)
     }
 
-    async fn delete(&self, id: &Self::Id) {
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
         sqlx::query!(r#"DELETE FROM "following" WHERE id = $1"#, id)
             .execute(&self.pool)
-            .await
-            .unwrap();
+            .await?;
+        Ok(())
     }
 
-    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) {}
+    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
 }
 
 impl FollowingRepository {
+    /// Returns `false` when `from_id` already follows `to_id`
+    pub async fn follow(&self, from_id: &Uuid, to_id: &Uuid) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"INSERT INTO "following" (id, from_id, to_id) VALUES ($1, $2, $3)
+            ON CONFLICT (from_id, to_id) DO NOTHING"#,
+            Uuid::new_v4(),
+            from_id,
+            to_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns `false` when `from_id` wasn't following `to_id`
+    pub async fn unfollow(&self, from_id: &Uuid, to_id: &Uuid) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"DELETE FROM "following" WHERE from_id = $1 AND to_id = $2"#,
+            from_id,
+            to_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes the user's followings and followers
+    pub async fn delete_all_of_user(
+        &self,
+        uow: &mut UnitOfWork,
+        user_id: &Uuid,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"DELETE FROM "following" WHERE from_id = $1 OR to_id = $1"#,
+            user_id
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
     pub async fn find_one_by_from_and_to_ids(
         &self,
         from_id: &Uuid,
         to_id: &Uuid,
-    ) -> Option<<FollowingRepository as Repository>::Model> {
-        sqlx::query_as!(
+    ) -> Result<Option<<FollowingRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
             Following,
             r#"SELECT * FROM "following" WHERE from_id = $1 AND to_id = $2"#,
             from_id,
             to_id
         )
         .fetch_optional(&self.pool)
-        .await
-        .unwrap()
+        .await?)
     }
 
     pub async fn find_all_by_from_id(
         &self,
         from_id: &Uuid,
-    ) -> Vec<<FollowingRepository as Repository>::Model> {
-        sqlx::query_as!(
+    ) -> Result<Vec<<FollowingRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
             Following,
             r#"SELECT * FROM "following" WHERE from_id = $1"#,
             from_id
         )
         .fetch_all(&self.pool)
-        .await
-        .unwrap()
+        .await?)
     }
 
     pub async fn find_all_by_to_id(
         &self,
         to_id: &Uuid,
-    ) -> Vec<<FollowingRepository as Repository>::Model> {
-        sqlx::query_as!(
+    ) -> Result<Vec<<FollowingRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
             Following,
             r#"SELECT * FROM "following" WHERE to_id = $1"#,
             to_id
         )
         .fetch_all(&self.pool)
-        .await
-        .unwrap()
+        .await?)
     }
 }
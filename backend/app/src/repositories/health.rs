@@ -0,0 +1,19 @@
+use sqlx::PgPool;
+
+use super::RepoError;
+
+#[derive(Clone)]
+pub struct HealthRepository {
+    pub pool: PgPool,
+}
+
+impl HealthRepository {
+    /// Versions of the migrations that ran successfully
+    pub async fn applied_migrations(&self) -> Result<Vec<i64>, RepoError> {
+        Ok(
+            sqlx::query_scalar!("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.pool)
+                .await?,
+        )
+    }
+}
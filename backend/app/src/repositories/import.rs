@@ -0,0 +1,180 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::import::Import;
+
+pub struct CreateImportDTO {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub source: Option<String>,
+    pub archive_key: Option<String>,
+    pub follows_key: Option<String>,
+}
+
+pub struct ImportFindAllParams {
+    pub user_id: Uuid,
+}
+
+/// Counters of a running import
+#[derive(Clone)]
+pub struct ImportProgressDTO {
+    pub total: i32,
+    pub processed: i32,
+    pub posts_created: i32,
+    pub posts_skipped: i32,
+    pub follows_created: i32,
+}
+
+#[derive(Clone)]
+pub struct ImportRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for ImportRepository {
+    type Model = Import;
+    type Id = Uuid;
+    type CreateDTO = CreateImportDTO;
+    type UpdateDTO = ImportProgressDTO;
+    type FindAllParams = ImportFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            Import,
+            r#"INSERT INTO "import" (id, user_id, source, archive_key, follows_key)
+            VALUES ($1, $2, $3, $4, $5) RETURNING *"#,
+            data.id,
+            data.user_id,
+            data.source,
+            data.archive_key,
+            data.follows_key
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Import, r#"SELECT * FROM "import" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Imports of the user, newest first.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Import,
+            r#"SELECT * FROM "import" WHERE user_id = $1 ORDER BY created_at DESC"#,
+            params.user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "import" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Records the progress of a running import.
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "import"
+            SET total = $2, processed = $3, posts_created = $4, posts_skipped = $5, follows_created = $6
+            WHERE id = $1"#,
+            id,
+            data.total,
+            data.processed,
+            data.posts_created,
+            data.posts_skipped,
+            data.follows_created
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl ImportRepository {
+    /// Takes the oldest pending import for running. Running imports whose
+    /// lock wasn't refreshed since `stale_before` are taken again, their
+    /// worker is assumed to have died. Their counters start over, the posts and follows
+    /// imported before are skipped.
+    pub async fn claim_next(
+        &self,
+        now: NaiveDateTime,
+        stale_before: NaiveDateTime,
+    ) -> Result<Option<Import>, RepoError> {
+        Ok(sqlx::query_as!(
+            Import,
+            r#"UPDATE "import" SET status = 'running', started_at = $1, locked_at = $1,
+                total = 0, processed = 0, posts_created = 0, posts_skipped = 0, follows_created = 0
+            WHERE id = (
+                SELECT id FROM "import"
+                WHERE status = 'pending' OR (status = 'running' AND locked_at < $2)
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *"#,
+            now,
+            stale_before
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Tells other workers the import is still running
+    pub async fn heartbeat(&self, id: &Uuid, now: NaiveDateTime) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "import" SET locked_at = $2 WHERE id = $1 AND status = 'running'"#,
+            id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Marks the import as done, or failed when there is an `error`. The
+    /// uploaded files are forgotten, they are deleted from the store.
+    pub async fn finish(
+        &self,
+        id: &Uuid,
+        error: Option<&str>,
+        now: NaiveDateTime,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "import"
+            SET status = CASE WHEN $2::text IS NULL THEN 'done' ELSE 'failed' END,
+                error = $2, finished_at = $3, archive_key = NULL, follows_key = NULL
+            WHERE id = $1"#,
+            id,
+            error,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Keys of the user's uploaded files that haven't been imported yet
+    pub async fn find_storage_keys(
+        &self,
+        uow: &mut UnitOfWork,
+        user_id: &Uuid,
+    ) -> Result<Vec<String>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT key AS "key!" FROM "import", unnest(ARRAY[archive_key, follows_key]) key
+            WHERE user_id = $1 AND key IS NOT NULL"#,
+            user_id
+        )
+        .fetch_all(uow.conn())
+        .await?)
+    }
+}
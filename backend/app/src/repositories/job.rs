@@ -0,0 +1,245 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository};
+use crate::models::job::{Job, JobPayload};
+
+pub struct CreateJobDTO {
+    pub payload: JobPayload,
+    pub run_at: NaiveDateTime,
+}
+
+pub struct JobFindAllParams {
+    /// Only jobs with this status, all of them when `None`
+    pub status: Option<String>,
+    pub limit: i64,
+}
+
+#[derive(Clone)]
+pub struct JobRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for JobRepository {
+    type Model = Job;
+    type Id = Uuid;
+    type CreateDTO = CreateJobDTO;
+    type UpdateDTO = ();
+    type FindAllParams = JobFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            Job,
+            r#"INSERT INTO "job" (id, kind, payload, run_at) VALUES ($1, $2, $3, $4) RETURNING *"#,
+            Uuid::new_v4(),
+            data.payload.kind(),
+            encode(&data.payload)?,
+            data.run_at
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Job, r#"SELECT * FROM "job" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Newest jobs first.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Job,
+            r#"SELECT * FROM "job" WHERE $1::varchar IS NULL OR status = $1
+            ORDER BY created_at DESC LIMIT $2"#,
+            params.status,
+            params.limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "job" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+impl JobRepository {
+    /// Queues a job unless the same one is already waiting to run. Returns
+    /// `false` when it was waiting already.
+    pub async fn enqueue(
+        &self,
+        payload: &JobPayload,
+        run_at: NaiveDateTime,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"INSERT INTO "job" (id, kind, payload, run_at)
+            SELECT $1, $2::varchar, $3::jsonb, $4
+            WHERE NOT EXISTS (
+                SELECT 1 FROM "job" WHERE kind = $2 AND payload = $3 AND status = 'pending'
+            )"#,
+            Uuid::new_v4(),
+            payload.kind(),
+            encode(payload)?,
+            run_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Takes the next due job under `lease_id` and counts the attempt. Running
+    /// jobs whose lock wasn't refreshed since `stale_before` are taken again,
+    /// their worker is assumed to have died.
+    ///
+    /// The methods that change a running job only do so while it's still
+    /// held under the same lease, and return `false` once it was lost.
+    pub async fn claim_next(
+        &self,
+        now: NaiveDateTime,
+        stale_before: NaiveDateTime,
+        lease_id: &Uuid,
+    ) -> Result<Option<Job>, RepoError> {
+        Ok(sqlx::query_as!(
+            Job,
+            r#"UPDATE "job" SET status = 'running', locked_at = $1, attempts = attempts + 1,
+                lease_id = $3
+            WHERE id = (
+                SELECT id FROM "job"
+                WHERE (status = 'pending' AND run_at <= $1)
+                    OR (status = 'running' AND locked_at < $2)
+                ORDER BY run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *"#,
+            now,
+            stale_before,
+            lease_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Tells other workers the job is still running
+    pub async fn heartbeat(
+        &self,
+        id: &Uuid,
+        lease_id: &Uuid,
+        now: NaiveDateTime,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "job" SET locked_at = $3
+            WHERE id = $1 AND lease_id = $2 AND status = 'running'"#,
+            id,
+            lease_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes a job that ran successfully.
+    pub async fn complete(&self, id: &Uuid, lease_id: &Uuid) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"DELETE FROM "job" WHERE id = $1 AND lease_id = $2 AND status = 'running'"#,
+            id,
+            lease_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Puts a job that failed back in the queue to run again at `run_at`.
+    pub async fn reschedule(
+        &self,
+        id: &Uuid,
+        lease_id: &Uuid,
+        error: &str,
+        run_at: NaiveDateTime,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "job" SET status = 'pending', locked_at = NULL, lease_id = NULL,
+                last_error = $3, run_at = $4
+            WHERE id = $1 AND lease_id = $2 AND status = 'running'"#,
+            id,
+            lease_id,
+            error,
+            run_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Puts jobs that were interrupted while running back in the queue to run
+    /// right away. The interrupted attempt isn't counted. Jobs are given by
+    /// the leases they were claimed under.
+    pub async fn release(&self, lease_ids: &[Uuid], now: NaiveDateTime) -> Result<u64, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "job" SET status = 'pending', locked_at = NULL, lease_id = NULL, run_at = $2,
+                attempts = GREATEST(attempts - 1, 0)
+            WHERE lease_id = ANY($1) AND status = 'running'"#,
+            lease_ids,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Moves a job that ran out of attempts to the failed jobs.
+    pub async fn mark_failed(
+        &self,
+        id: &Uuid,
+        lease_id: &Uuid,
+        error: &str,
+        now: NaiveDateTime,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "job" SET status = 'failed', locked_at = NULL, lease_id = NULL,
+                last_error = $3, failed_at = $4
+            WHERE id = $1 AND lease_id = $2 AND status = 'running'"#,
+            id,
+            lease_id,
+            error,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queues a failed job again with fresh attempts. Returns `None` if
+    /// there's no failed job with this id.
+    pub async fn retry(&self, id: &Uuid, now: NaiveDateTime) -> Result<Option<Job>, RepoError> {
+        Ok(sqlx::query_as!(
+            Job,
+            r#"UPDATE "job" SET status = 'pending', attempts = 0, run_at = $2, failed_at = NULL
+            WHERE id = $1 AND status = 'failed'
+            RETURNING *"#,
+            id,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+}
+
+/// Serializes a payload for the `payload` column
+fn encode(payload: &JobPayload) -> Result<serde_json::Value, RepoError> {
+    serde_json::to_value(payload).map_err(RepoError::Encoding)
+}
@@ -0,0 +1,83 @@
+use sqlx::PgPool;
+
+use super::{RepoError, Repository};
+use crate::models::link_preview::LinkPreview;
+
+pub struct CreateLinkPreviewDTO {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+}
+
+pub struct LinkPreviewFindAllParams {
+    pub urls: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct LinkPreviewRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for LinkPreviewRepository {
+    type Model = LinkPreview;
+    type Id = String;
+    type CreateDTO = CreateLinkPreviewDTO;
+    type UpdateDTO = ();
+    type FindAllParams = LinkPreviewFindAllParams;
+
+    /// Stores the preview, replacing an earlier fetch of the same url.
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            LinkPreview,
+            r#"INSERT INTO "link_preview" (url, title, description, image, site_name) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (url) DO UPDATE SET
+                title = EXCLUDED.title,
+                description = EXCLUDED.description,
+                image = EXCLUDED.image,
+                site_name = EXCLUDED.site_name,
+                fetched_at = EXCLUDED.fetched_at
+            RETURNING *"#,
+            data.url,
+            data.title,
+            data.description,
+            data.image,
+            data.site_name
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, url: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            LinkPreview,
+            r#"SELECT * FROM "link_preview" WHERE url = $1"#,
+            url
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            LinkPreview,
+            r#"SELECT * FROM "link_preview" WHERE url = ANY($1)"#,
+            &params.urls
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, url: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "link_preview" WHERE url = $1"#, url)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _url: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::list::{List, ListMemberCount};
+use crate::models::post::Post;
+
+pub struct CreateListDTO {
+    pub owner_id: Uuid,
+    pub name: String,
+    pub private: bool,
+}
+
+pub struct UpdateListDTO {
+    pub name: Option<String>,
+    pub private: Option<bool>,
+}
+
+/// Outcome of `ListRepository::add_member`
+#[derive(Debug, PartialEq, Eq)]
+pub enum AddMemberOutcome {
+    Added,
+    AlreadyMember,
+    Full,
+}
+
+pub struct ListFindAllParams {
+    pub owner_id: Uuid,
+    /// Leave out private lists
+    pub public_only: bool,
+}
+
+#[derive(Clone)]
+pub struct ListRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for ListRepository {
+    type Model = List;
+    type Id = Uuid;
+    type CreateDTO = CreateListDTO;
+    type UpdateDTO = UpdateListDTO;
+    type FindAllParams = ListFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            List,
+            r#"INSERT INTO "list" (id, owner_id, name, private) VALUES ($1, $2, $3, $4) RETURNING *"#,
+            Uuid::new_v4(),
+            data.owner_id,
+            data.name,
+            data.private
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(List, r#"SELECT * FROM "list" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            List,
+            r#"SELECT * FROM "list" WHERE owner_id = $1 AND (NOT $2 OR NOT private)
+            ORDER BY created_at DESC"#,
+            params.owner_id,
+            params.public_only
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "list" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "list" SET name = COALESCE($2, name), private = COALESCE($3, private) WHERE id = $1"#,
+            id,
+            data.name,
+            data.private
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl ListRepository {
+    /// Ids of the list members, in the order they were added.
+    pub async fn find_member_ids(&self, list_id: &Uuid) -> Result<Vec<Uuid>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT user_id FROM "list_member" WHERE list_id = $1 ORDER BY created_at"#,
+            list_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn count_members(
+        &self,
+        list_ids: &[Uuid],
+    ) -> Result<Vec<ListMemberCount>, RepoError> {
+        Ok(sqlx::query_as!(
+            ListMemberCount,
+            r#"SELECT list_id, count(*) AS "count!" FROM "list_member" WHERE list_id = ANY($1) GROUP BY list_id"#,
+            list_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Adds the user unless the list already has `max` members.
+    pub async fn add_member(
+        &self,
+        list_id: &Uuid,
+        user_id: &Uuid,
+        max: i64,
+    ) -> Result<AddMemberOutcome, RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        // Members are counted with the list row locked, otherwise two
+        // concurrent adds could both see the last free slot
+        sqlx::query!(
+            r#"SELECT id FROM "list" WHERE id = $1 FOR NO KEY UPDATE"#,
+            list_id
+        )
+        .fetch_optional(uow.conn())
+        .await?;
+        let added = sqlx::query!(
+            r#"INSERT INTO "list_member" (list_id, user_id)
+            SELECT $1, $2
+            WHERE (SELECT count(*) FROM "list_member" WHERE list_id = $1) < $3
+            ON CONFLICT DO NOTHING"#,
+            list_id,
+            user_id,
+            max
+        )
+        .execute(uow.conn())
+        .await?
+        .rows_affected()
+            > 0;
+        let outcome = if added {
+            AddMemberOutcome::Added
+        } else if sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM "list_member" WHERE list_id = $1 AND user_id = $2) AS "exists!""#,
+            list_id,
+            user_id
+        )
+        .fetch_one(uow.conn())
+        .await?
+        {
+            AddMemberOutcome::AlreadyMember
+        } else {
+            AddMemberOutcome::Full
+        };
+        uow.commit().await?;
+        Ok(outcome)
+    }
+
+    /// Returns `false` if the user wasn't a member.
+    pub async fn remove_member(&self, list_id: &Uuid, user_id: &Uuid) -> Result<bool, RepoError> {
+        Ok(sqlx::query!(
+            r#"DELETE FROM "list_member" WHERE list_id = $1 AND user_id = $2"#,
+            list_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// Lists the user subscribed to that are still public, newest subscription first.
+    pub async fn find_subscribed(&self, user_id: &Uuid) -> Result<Vec<List>, RepoError> {
+        Ok(sqlx::query_as!(
+            List,
+            r#"SELECT l.* FROM "list" l
+            JOIN "list_subscription" s ON s.list_id = l.id
+            WHERE s.user_id = $1 AND NOT l.private
+            ORDER BY s.created_at DESC"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Returns `false` if the user is already subscribed.
+    pub async fn subscribe(&self, list_id: &Uuid, user_id: &Uuid) -> Result<bool, RepoError> {
+        Ok(sqlx::query!(
+            r#"INSERT INTO "list_subscription" (list_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+            list_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// Returns `false` if the user wasn't subscribed.
+    pub async fn unsubscribe(&self, list_id: &Uuid, user_id: &Uuid) -> Result<bool, RepoError> {
+        Ok(sqlx::query!(
+            r#"DELETE FROM "list_subscription" WHERE list_id = $1 AND user_id = $2"#,
+            list_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// Published posts of the list members, newest first.
+    pub async fn find_timeline(
+        &self,
+        list_id: &Uuid,
+        viewer_id: &Uuid,
+        before: Option<NaiveDateTime>,
+        limit: i64,
+    ) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT p.id, p.text, p.author_id, p.created_at, p.edited, p.edited_at, p.deleted_at,
+                p.draft, p.scheduled_at, p.pinned_at, p.reply_to_id, p.source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = p.id AND b.user_id = $4
+                ) AS "bookmarked_by_me!"
+            FROM "post" p
+            JOIN "list_member" m ON m.user_id = p.author_id
+            WHERE m.list_id = $1
+            AND ($2::timestamp IS NULL OR p.created_at < $2)
+            AND p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL
+            ORDER BY p.created_at DESC, p.id DESC
+            LIMIT $3"#,
+            list_id,
+            before,
+            limit,
+            viewer_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
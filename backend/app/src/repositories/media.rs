@@ -0,0 +1,199 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::media::Media;
+
+pub struct CreateMediaDTO {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub storage_key: String,
+    pub mime: String,
+    pub size: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    pub small_key: Option<String>,
+    pub thumbnail_key: Option<String>,
+}
+
+pub struct MediaFindAllParams {
+    pub post_ids: Vec<Uuid>,
+}
+
+#[derive(Clone)]
+pub struct MediaRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for MediaRepository {
+    type Model = Media;
+    type Id = Uuid;
+    type CreateDTO = CreateMediaDTO;
+    type UpdateDTO = ();
+    type FindAllParams = MediaFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            Media,
+            r#"INSERT INTO "media" (id, owner_id, storage_key, mime, size, width, height, blurhash, small_key, thumbnail_key)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"#,
+            data.id,
+            data.owner_id,
+            data.storage_key,
+            data.mime,
+            data.size,
+            data.width,
+            data.height,
+            data.blurhash,
+            data.small_key,
+            data.thumbnail_key
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Media, r#"SELECT * FROM "media" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Media,
+            r#"SELECT * FROM "media" WHERE post_id = ANY($1) ORDER BY position"#,
+            &params.post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "media" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+impl MediaRepository {
+    /// Hands the media attached to the owner's posts over to `to_id`
+    pub async fn reassign_attached(
+        &self,
+        uow: &mut UnitOfWork,
+        from_id: &Uuid,
+        to_id: &Uuid,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "media" m SET owner_id = $2
+            FROM "post" p WHERE p.id = m.post_id AND m.owner_id = $1 AND p.author_id = $2"#,
+            from_id,
+            to_id
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_all_by_owner(&self, owner_id: &Uuid) -> Result<Vec<Media>, RepoError> {
+        Ok(sqlx::query_as!(
+            Media,
+            r#"SELECT * FROM "media" WHERE owner_id = $1"#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_all_by_owner_in(
+        &self,
+        uow: &mut UnitOfWork,
+        owner_id: &Uuid,
+    ) -> Result<Vec<Media>, RepoError> {
+        Ok(sqlx::query_as!(
+            Media,
+            r#"SELECT * FROM "media" WHERE owner_id = $1"#,
+            owner_id
+        )
+        .fetch_all(uow.conn())
+        .await?)
+    }
+
+    /// Deletes the media attached to the posts and returns it, so its files
+    /// can be deleted once the unit of work is committed
+    pub async fn delete_all_by_posts(
+        &self,
+        uow: &mut UnitOfWork,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<Media>, RepoError> {
+        Ok(sqlx::query_as!(
+            Media,
+            r#"DELETE FROM "media" WHERE post_id = ANY($1) RETURNING *"#,
+            post_ids
+        )
+        .fetch_all(uow.conn())
+        .await?)
+    }
+
+    pub async fn find_all_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Media>, RepoError> {
+        Ok(
+            sqlx::query_as!(Media, r#"SELECT * FROM "media" WHERE id = ANY($1)"#, ids)
+                .fetch_all(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Finds media the viewer can see. Files that aren't attached to a post,
+    /// such as avatars, are public. Attached ones are public once the post is
+    /// published and stay visible to its author in drafts and the trash.
+    pub async fn find_one_visible(
+        &self,
+        id: &Uuid,
+        viewer_id: Option<&Uuid>,
+    ) -> Result<Option<Media>, RepoError> {
+        Ok(sqlx::query_as!(
+            Media,
+            r#"SELECT * FROM "media" WHERE id = $1 AND (
+                post_id IS NULL OR EXISTS (
+                    SELECT 1 FROM "post" p WHERE p.id = "media".post_id AND (
+                        p.author_id = $2
+                        OR (p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL)
+                    )
+                )
+            )"#,
+            id,
+            viewer_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Attaches media to a post, keeping the order of `ids`. Only unattached
+    /// media of `owner_id` is taken, the ids of what was attached are returned.
+    pub async fn attach_to_post(
+        &self,
+        uow: &mut UnitOfWork,
+        post_id: &Uuid,
+        owner_id: &Uuid,
+        ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"UPDATE "media" SET post_id = $1, position = array_position($3::uuid[], id) - 1
+            WHERE id = ANY($3) AND owner_id = $2 AND post_id IS NULL
+            RETURNING id"#,
+            post_id,
+            owner_id,
+            ids
+        )
+        .fetch_all(uow.conn())
+        .await?)
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::message::Message;
+
+pub struct CreateMessageDTO {
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub text: String,
+}
+
+pub struct MessageFindAllParams {
+    pub conversation_id: Uuid,
+    /// Only return messages sent strictly before this moment
+    pub before: Option<NaiveDateTime>,
+    pub limit: i64,
+}
+
+#[derive(Clone)]
+pub struct MessageRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for MessageRepository {
+    type Model = Message;
+    type Id = Uuid;
+    type CreateDTO = CreateMessageDTO;
+    type UpdateDTO = ();
+    type FindAllParams = MessageFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        let id = Uuid::new_v4();
+        let response = sqlx::query!(
+            r#"INSERT INTO "message" (id, conversation_id, sender_id, text) VALUES ($1, $2, $3, $4) RETURNING created_at"#,
+            id,
+            data.conversation_id,
+            data.sender_id,
+            data.text
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Message {
+            id,
+            conversation_id: data.conversation_id,
+            sender_id: data.sender_id,
+            text: data.text,
+            created_at: response.created_at,
+        })
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Message, r#"SELECT * FROM "message" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Message,
+            r#"SELECT * FROM "message"
+            WHERE conversation_id = $1 AND ($2::timestamp IS NULL OR created_at < $2)
+            ORDER BY created_at DESC LIMIT $3"#,
+            params.conversation_id,
+            params.before,
+            params.limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "message" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+impl MessageRepository {
+    pub async fn reassign_sender(
+        &self,
+        uow: &mut UnitOfWork,
+        from_id: &Uuid,
+        to_id: &Uuid,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "message" SET sender_id = $2 WHERE sender_id = $1"#,
+            from_id,
+            to_id
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_all_by_sender(
+        &self,
+        uow: &mut UnitOfWork,
+        sender_id: &Uuid,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "message" WHERE sender_id = $1"#, sender_id)
+            .execute(uow.conn())
+            .await?;
+        Ok(())
+    }
+}
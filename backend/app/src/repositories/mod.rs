@@ -1,8 +1,101 @@
 use serde::Serialize;
+use sqlx::error::ErrorKind;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
 
+pub mod bookmark;
+pub mod bookmark_folder;
+pub mod conversation;
+pub mod data_export;
 pub mod following;
+pub mod health;
+pub mod import;
+pub mod job;
+pub mod link_preview;
+pub mod list;
+pub mod media;
+pub mod message;
+pub mod poll;
 pub mod post;
+pub mod stats;
 pub mod user;
+pub mod webhook;
+pub mod webhook_delivery;
+
+/// Database failure of a repository call, sorted by what the caller can do about it.
+#[derive(Debug, thiserror::Error)]
+pub enum RepoError {
+    /// A unique or primary key constraint was violated, usually by a concurrent insert
+    #[error("Unique constraint `{0}` violated")]
+    UniqueViolation(String),
+    /// A referenced row doesn't exist, usually because it was deleted concurrently
+    #[error("Foreign key constraint `{0}` violated")]
+    ForeignKeyViolation(String),
+    /// The transaction lost a serialization conflict or a deadlock and can be retried
+    #[error("Serialization failure: {0}")]
+    SerializationFailure(String),
+    /// The database can't be reached or has no free connections
+    #[error("Database unavailable: {0}")]
+    Unavailable(#[source] sqlx::Error),
+    /// A value couldn't be serialized for the query
+    #[error("Can't encode value: {0}")]
+    Encoding(#[source] serde_json::Error),
+    #[error(transparent)]
+    Other(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RepoError {
+    fn from(error: sqlx::Error) -> Self {
+        let (kind, code, constraint, message) = match &error {
+            sqlx::Error::Database(e) => (
+                e.kind(),
+                e.code().map(|code| code.into_owned()),
+                e.constraint().unwrap_or_default().to_string(),
+                e.message().to_string(),
+            ),
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => return Self::Unavailable(error),
+            _ => return Self::Other(error),
+        };
+        match (kind, code.as_deref()) {
+            (ErrorKind::UniqueViolation, _) => Self::UniqueViolation(constraint),
+            (ErrorKind::ForeignKeyViolation, _) => Self::ForeignKeyViolation(constraint),
+            (_, Some("40001" | "40P01")) => Self::SerializationFailure(message),
+            // Connection exceptions, server shutdown and too many connections
+            (_, Some(code))
+                if code.starts_with("08") || code.starts_with("57P") || code == "53300" =>
+            {
+                Self::Unavailable(error)
+            }
+            _ => Self::Other(error),
+        }
+    }
+}
+
+/// Repository calls that succeed or fail together, on one transaction.
+/// Dropping it without `commit` rolls everything back.
+pub struct UnitOfWork {
+    tx: Transaction<'static, Postgres>,
+}
+
+impl UnitOfWork {
+    pub async fn begin(pool: &PgPool) -> Result<Self, RepoError> {
+        Ok(Self {
+            tx: pool.begin().await?,
+        })
+    }
+
+    pub async fn commit(self) -> Result<(), RepoError> {
+        Ok(self.tx.commit().await?)
+    }
+
+    /// Connection the transaction runs on, for repositories to query through
+    fn conn(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+}
 
 #[async_trait::async_trait]
 pub trait Repository: Send + Sync {
@@ -12,9 +105,9 @@ pub trait Repository: Send + Sync {
     type UpdateDTO;
     type FindAllParams;
 
-    async fn create(&self, data: Self::CreateDTO) -> Self::Model;
-    async fn find_one(&self, id: &Self::Id) -> Option<Self::Model>;
-    async fn find_all(&self, params: Self::FindAllParams) -> Vec<Self::Model>;
-    async fn delete(&self, id: &Self::Id);
-    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO);
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError>;
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError>;
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError>;
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError>;
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError>;
 }
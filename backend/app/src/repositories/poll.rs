@@ -0,0 +1,186 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::poll::{Poll, PollOption, PollOptionCount, PollVote, PollVoterCount};
+
+pub struct CreatePollDTO {
+    pub post_id: Uuid,
+    pub multiple: bool,
+    pub expires_at: NaiveDateTime,
+    /// Option texts in display order
+    pub options: Vec<String>,
+}
+
+pub struct PollFindAllParams {
+    pub post_ids: Vec<Uuid>,
+}
+
+#[derive(Clone)]
+pub struct PollRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for PollRepository {
+    type Model = Poll;
+    type Id = Uuid;
+    type CreateDTO = CreatePollDTO;
+    type UpdateDTO = ();
+    type FindAllParams = PollFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        let poll = self.create_in(&mut uow, data).await?;
+        uow.commit().await?;
+        Ok(poll)
+    }
+
+    async fn find_one(&self, post_id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Poll, r#"SELECT * FROM "poll" WHERE post_id = $1"#, post_id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Poll,
+            r#"SELECT * FROM "poll" WHERE post_id = ANY($1)"#,
+            &params.post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, post_id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "poll" WHERE post_id = $1"#, post_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, _post_id: &Self::Id, _data: Self::UpdateDTO) -> Result<(), RepoError> {
+        Ok(())
+    }
+}
+
+impl PollRepository {
+    pub async fn create_in(
+        &self,
+        uow: &mut UnitOfWork,
+        data: CreatePollDTO,
+    ) -> Result<Poll, RepoError> {
+        let poll = sqlx::query_as!(
+            Poll,
+            r#"INSERT INTO "poll" (post_id, multiple, expires_at) VALUES ($1, $2, $3) RETURNING *"#,
+            data.post_id,
+            data.multiple,
+            data.expires_at
+        )
+        .fetch_one(uow.conn())
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO "poll_option" (post_id, position, text)
+            SELECT $1, (ordinality - 1)::smallint, text FROM unnest($2::varchar[]) WITH ORDINALITY AS t(text, ordinality)"#,
+            data.post_id,
+            &data.options
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(poll)
+    }
+
+    pub async fn find_options(&self, post_ids: &[Uuid]) -> Result<Vec<PollOption>, RepoError> {
+        Ok(sqlx::query_as!(
+            PollOption,
+            r#"SELECT * FROM "poll_option" WHERE post_id = ANY($1) ORDER BY post_id, position"#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn count_votes(&self, post_ids: &[Uuid]) -> Result<Vec<PollOptionCount>, RepoError> {
+        Ok(sqlx::query_as!(
+            PollOptionCount,
+            r#"SELECT post_id, option AS "position!", COUNT(*) AS "count!"
+            FROM "poll_vote", unnest(options) AS option
+            WHERE post_id = ANY($1)
+            GROUP BY post_id, option"#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn count_voters(&self, post_ids: &[Uuid]) -> Result<Vec<PollVoterCount>, RepoError> {
+        Ok(sqlx::query_as!(
+            PollVoterCount,
+            r#"SELECT post_id, COUNT(*) AS "count!" FROM "poll_vote" WHERE post_id = ANY($1) GROUP BY post_id"#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_votes_by_user(
+        &self,
+        post_ids: &[Uuid],
+        user_id: &Uuid,
+    ) -> Result<Vec<PollVote>, RepoError> {
+        Ok(sqlx::query_as!(
+            PollVote,
+            r#"SELECT * FROM "poll_vote" WHERE post_id = ANY($1) AND user_id = $2"#,
+            post_ids,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Marks up to `limit` polls of published posts that expired by `now` as
+    /// closed and returns them. Rows are claimed with `SKIP LOCKED`, so each
+    /// poll is returned once even with several instances running.
+    pub async fn close_due(&self, now: NaiveDateTime, limit: i64) -> Result<Vec<Poll>, RepoError> {
+        Ok(sqlx::query_as!(
+            Poll,
+            r#"UPDATE "poll" SET closed_at = $1
+            WHERE post_id IN (
+                SELECT p.post_id FROM "poll" p JOIN "post" ON "post".id = p.post_id
+                WHERE p.closed_at IS NULL AND p.expires_at <= $1
+                    AND "post".deleted_at IS NULL AND NOT "post".draft AND "post".scheduled_at IS NULL
+                ORDER BY p.expires_at
+                LIMIT $2
+                FOR UPDATE OF p SKIP LOCKED
+            )
+            RETURNING *"#,
+            now,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Stores the vote. Returns `false` when the user has voted already.
+    pub async fn vote(
+        &self,
+        post_id: &Uuid,
+        user_id: &Uuid,
+        options: &[i16],
+    ) -> Result<bool, RepoError> {
+        Ok(sqlx::query!(
+            r#"INSERT INTO "poll_vote" (post_id, user_id, options) VALUES ($1, $2, $3)
+            ON CONFLICT (post_id, user_id) DO NOTHING"#,
+            post_id,
+            user_id,
+            options
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            == 1)
+    }
+}
@@ -1,12 +1,25 @@
+use chrono::NaiveDateTime;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use super::Repository;
-use crate::models::post::Post;
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::post::{Post, PostMention, PostRevision, RevisionCount, TagUse};
+use crate::models::search::SearchHit;
 
 pub struct CreatePostDTO {
     pub text: String,
     pub author_id: Uuid,
+    pub draft: bool,
+    pub scheduled_at: Option<NaiveDateTime>,
+}
+
+/// Post brought over from another network, published at its original time
+pub struct CreateImportedPostDTO {
+    pub text: String,
+    pub author_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub reply_to_id: Option<Uuid>,
+    pub source_id: String,
 }
 
 pub struct UpdatePostDTO {
@@ -15,6 +28,8 @@ pub struct UpdatePostDTO {
 
 pub struct PostFindAllParams {
     pub author_id: Uuid,
+    /// User the posts are shown to
+    pub viewer_id: Uuid,
 }
 
 #[derive(Clone)]
@@ -30,66 +45,826 @@ impl Repository for PostRepository {
     type UpdateDTO = UpdatePostDTO;
     type FindAllParams = PostFindAllParams;
 
-    async fn create(&self, data: Self::CreateDTO) -> Self::Model {
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        let post = self.create_in(&mut uow, data).await?;
+        uow.commit().await?;
+        Ok(post)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                FALSE AS "bookmarked_by_me!"
+            FROM "post" WHERE id = $1 AND deleted_at IS NULL AND NOT draft AND scheduled_at IS NULL"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Pinned posts first, most recently pinned on top, then the newest posts.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = $2
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE author_id = $1 AND deleted_at IS NULL AND NOT draft AND scheduled_at IS NULL
+            ORDER BY pinned_at DESC NULLS LAST, created_at DESC, id DESC"#,
+            params.author_id,
+            params.viewer_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Moves the post to the trash and unpins it.
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "post" SET deleted_at = (now() AT TIME ZONE 'utc'), pinned_at = NULL
+            WHERE id = $1 AND deleted_at IS NULL"#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        self.update_in(&mut uow, id, data).await?;
+        uow.commit().await
+    }
+}
+
+impl PostRepository {
+    /// Finds a published post as shown to `viewer_id`
+    pub async fn find_one_for(
+        &self,
+        id: &Uuid,
+        viewer_id: &Uuid,
+    ) -> Result<Option<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = $2
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE id = $1 AND deleted_at IS NULL AND NOT draft AND scheduled_at IS NULL"#,
+            id,
+            viewer_id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    pub async fn create_in(
+        &self,
+        uow: &mut UnitOfWork,
+        data: CreatePostDTO,
+    ) -> Result<Post, RepoError> {
         let id = Uuid::new_v4();
         let response = sqlx::query!(
-            r#"INSERT INTO "post" (id, text, author_id) VALUES ($1, $2, $3) RETURNING created_at, edited"#,
+            r#"INSERT INTO "post" (id, text, author_id, draft, scheduled_at) VALUES ($1, $2, $3, $4, $5)
+            RETURNING created_at, edited, edited_at"#,
             id,
             data.text,
-            data.author_id
+            data.author_id,
+            data.draft,
+            data.scheduled_at
         )
-        .fetch_one(&self.pool)
-        .await
-        .unwrap();
-        Post {
+        .fetch_one(uow.conn())
+        .await?;
+        Ok(Post {
             id,
             text: data.text,
             author_id: data.author_id,
             created_at: response.created_at,
             edited: response.edited,
+            edited_at: response.edited_at,
+            deleted_at: None,
+            draft: data.draft,
+            scheduled_at: data.scheduled_at,
+            pinned_at: None,
+            reply_to_id: None,
+            source_id: None,
+            bookmarked_by_me: false,
+        })
+    }
+
+    /// Finds a post that isn't in the trash and locks the row until the unit
+    /// of work ends
+    pub async fn lock(&self, uow: &mut UnitOfWork, id: &Uuid) -> Result<Option<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                FALSE AS "bookmarked_by_me!"
+            FROM "post" WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+            id
+        )
+        .fetch_optional(uow.conn())
+        .await?)
+    }
+
+    /// Changes the text of a post. The previous text of a published post is
+    /// kept as a revision, and the row stays locked in between so concurrent
+    /// edits can't lose one.
+    pub async fn update_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        data: UpdatePostDTO,
+    ) -> Result<(), RepoError> {
+        let Some(post) = self.lock(uow, id).await? else {
+            return Ok(());
+        };
+        let text = match data.text {
+            Some(text) if text != post.text => text,
+            _ => return Ok(()),
+        };
+
+        // Nobody has seen an unpublished text, so it isn't kept as a revision
+        if !post.is_published() {
+            sqlx::query!(r#"UPDATE "post" SET text = $1 WHERE id = $2"#, text, id)
+                .execute(uow.conn())
+                .await?;
+            return Ok(());
         }
+
+        sqlx::query!(
+            r#"INSERT INTO "post_revision" (id, post_id, text, created_at) VALUES ($1, $2, $3, $4)"#,
+            Uuid::new_v4(),
+            id,
+            post.text,
+            post.edited_at.unwrap_or(post.created_at)
+        )
+        .execute(uow.conn())
+        .await?;
+
+        sqlx::query!(
+            r#"UPDATE "post" SET text = $1, edited = true, edited_at = (now() AT TIME ZONE 'utc') WHERE id = $2"#,
+            text,
+            id
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
     }
 
-    async fn find_one(&self, id: &Self::Id) -> Option<Self::Model> {
-        sqlx::query_as!(Post, r#"SELECT * FROM "post" WHERE id = $1"#, id)
-            .fetch_optional(&self.pool)
-            .await
-            .unwrap()
+    /// Hands the author's published posts over to `to_id`, unpinned. Their
+    /// import source is dropped, the same archive can be imported into
+    /// another account and end up under `to_id` again.
+    pub async fn reassign_published(
+        &self,
+        uow: &mut UnitOfWork,
+        from_id: &Uuid,
+        to_id: &Uuid,
+        now: NaiveDateTime,
+    ) -> Result<u64, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "post" SET author_id = $2, pinned_at = NULL, source_id = NULL
+            WHERE author_id = $1 AND deleted_at IS NULL AND NOT draft
+            AND (scheduled_at IS NULL OR scheduled_at <= $3)"#,
+            from_id,
+            to_id,
+            now
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(result.rows_affected())
     }
 
-    async fn find_all(&self, params: Self::FindAllParams) -> Vec<Self::Model> {
-        sqlx::query_as!(
+    /// Deletes every post of the author, including drafts and the trash
+    pub async fn delete_all_by_author(
+        &self,
+        uow: &mut UnitOfWork,
+        author_id: &Uuid,
+    ) -> Result<u64, RepoError> {
+        let result = sqlx::query!(r#"DELETE FROM "post" WHERE author_id = $1"#, author_id)
+            .execute(uow.conn())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Replaces the users mentioned in the post.
+    pub async fn set_mentions(
+        &self,
+        uow: &mut UnitOfWork,
+        post_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "post_mention" WHERE post_id = $1"#, post_id)
+            .execute(uow.conn())
+            .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO "post_mention" (post_id, user_id) SELECT $1, unnest($2::uuid[]) ON CONFLICT DO NOTHING"#,
+            post_id,
+            user_ids
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Replaces the hashtags of the post. Tags are expected to be normalized.
+    pub async fn set_tags(
+        &self,
+        uow: &mut UnitOfWork,
+        post_id: &Uuid,
+        tags: &[String],
+    ) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "post_tag" WHERE post_id = $1"#, post_id)
+            .execute(uow.conn())
+            .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO "post_tag" (post_id, tag) SELECT $1, unnest($2::varchar[]) ON CONFLICT DO NOTHING"#,
+            post_id,
+            tags
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_mentions(&self, post_ids: &[Uuid]) -> Result<Vec<PostMention>, RepoError> {
+        Ok(sqlx::query_as!(
+            PostMention,
+            r#"SELECT m.post_id, m.user_id, u.username FROM "post_mention" m
+            JOIN "user" u ON u.id = m.user_id
+            WHERE m.post_id = ANY($1)"#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_all_by_tag(
+        &self,
+        tag: &str,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
             Post,
-            r#"SELECT * FROM "post" WHERE author_id = $1 ORDER BY created_at DESC"#,
-            params.author_id
+            r#"SELECT p.id, p.text, p.author_id, p.created_at, p.edited, p.edited_at, p.deleted_at,
+                p.draft, p.scheduled_at, p.pinned_at, p.reply_to_id, p.source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = p.id AND b.user_id = $2
+                ) AS "bookmarked_by_me!"
+            FROM "post" p
+            JOIN "post_tag" t ON t.post_id = p.id
+            WHERE t.tag = $1 AND p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL
+            ORDER BY p.created_at DESC"#,
+            tag,
+            viewer_id
         )
         .fetch_all(&self.pool)
-        .await
-        .unwrap()
+        .await?)
+    }
+
+    pub async fn find_tag_uses_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> Result<Vec<TagUse>, RepoError> {
+        Ok(sqlx::query_as!(
+            TagUse,
+            r#"SELECT t.tag, p.created_at FROM "post_tag" t
+            JOIN "post" p ON p.id = t.post_id
+            WHERE p.created_at >= $1 AND p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL"#,
+            since
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Posts created since `since` by anyone but the user and the people they
+    /// follow, with the highest engagement velocity at `now` first: replies,
+    /// bookmarks and poll votes from other people, plus one, divided by the age
+    /// in hours plus two raised to `gravity`. Ties go to newer posts, then to
+    /// smaller ids.
+    pub async fn find_explore(
+        &self,
+        user_id: &Uuid,
+        since: NaiveDateTime,
+        now: NaiveDateTime,
+        gravity: f64,
+        limit: i64,
+    ) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT p.id, p.text, p.author_id, p.created_at, p.edited, p.edited_at, p.deleted_at,
+                p.draft, p.scheduled_at, p.pinned_at, p.reply_to_id, p.source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = p.id AND b.user_id = $1
+                ) AS "bookmarked_by_me!"
+            FROM "post" p
+            CROSS JOIN LATERAL (
+                SELECT (
+                    SELECT COUNT(*) FROM "post" r
+                    WHERE r.reply_to_id = p.id AND r.author_id <> p.author_id
+                    AND r.deleted_at IS NULL AND NOT r.draft AND r.scheduled_at IS NULL
+                ) + (
+                    SELECT COUNT(*) FROM "bookmark" b
+                    WHERE b.post_id = p.id AND b.user_id <> p.author_id
+                ) + (
+                    SELECT COUNT(DISTINCT v.user_id) FROM "poll_vote" v
+                    WHERE v.post_id = p.id AND v.user_id <> p.author_id
+                ) AS engagement
+            ) e
+            WHERE p.created_at >= $2 AND p.author_id <> $1
+            AND p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL
+            AND NOT EXISTS (
+                SELECT 1 FROM "following" f WHERE f.from_id = $1 AND f.to_id = p.author_id
+            )
+            ORDER BY
+                (e.engagement + 1) / power(
+                    GREATEST(EXTRACT(EPOCH FROM $3 - p.created_at)::float8, 0) / 3600 + 2,
+                    $4::float8
+                ) DESC,
+                p.created_at DESC,
+                p.id
+            LIMIT $5"#,
+            user_id,
+            since,
+            now,
+            gravity,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_all_by_ids(
+        &self,
+        ids: &[Uuid],
+        viewer_id: &Uuid,
+    ) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = $2
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE id = ANY($1) AND deleted_at IS NULL AND NOT draft AND scheduled_at IS NULL"#,
+            ids,
+            viewer_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Full-text search over post texts, best matches first. Posts of
+    /// suspended users and of accounts pending deletion are left out, like the
+    /// users themselves. The text is HTML-escaped before highlighting, so
+    /// `<mark>` is the only markup in snippets.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchHit>, RepoError> {
+        Ok(sqlx::query_as!(
+            SearchHit,
+            r#"SELECT
+                p.id,
+                ts_rank(p.search_vector, q) AS "rank!",
+                ts_headline(
+                    'simple',
+                    replace(replace(replace(p.text, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                    q,
+                    'StartSel=<mark>, StopSel=</mark>, MaxFragments=2'
+                ) AS "snippet!"
+            FROM "post" p
+            JOIN "user" u ON u.id = p.author_id,
+            websearch_to_tsquery('simple', $1) q
+            WHERE p.search_vector @@ q
+            AND p.deleted_at IS NULL AND NOT p.draft AND p.scheduled_at IS NULL
+            AND u.suspended_at IS NULL AND u.deletion_scheduled_at IS NULL
+            ORDER BY 2 DESC, p.created_at DESC
+            LIMIT $2 OFFSET $3"#,
+            query,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_revisions(&self, post_id: &Uuid) -> Result<Vec<PostRevision>, RepoError> {
+        Ok(sqlx::query_as!(
+            PostRevision,
+            r#"SELECT * FROM "post_revision" WHERE post_id = $1 ORDER BY created_at DESC"#,
+            post_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Earlier versions of all the posts, oldest first.
+    pub async fn find_revisions_of_posts(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<PostRevision>, RepoError> {
+        Ok(sqlx::query_as!(
+            PostRevision,
+            r#"SELECT * FROM "post_revision" WHERE post_id = ANY($1) ORDER BY created_at"#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn count_revisions(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<RevisionCount>, RepoError> {
+        Ok(sqlx::query_as!(
+            RevisionCount,
+            r#"SELECT post_id, COUNT(*) AS "count!" FROM "post_revision"
+            WHERE post_id = ANY($1) GROUP BY post_id"#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Every post of the author, including drafts, scheduled posts and the trash, oldest first.
+    pub async fn find_all_of_author(&self, author_id: &Uuid) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                FALSE AS "bookmarked_by_me!"
+            FROM "post" WHERE author_id = $1 ORDER BY created_at, id"#,
+            author_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Creates an imported post. Returns `None` if the author already has a
+    /// post with the same `source_id`, so importing again doesn't duplicate posts.
+    pub async fn create_imported(
+        &self,
+        uow: &mut UnitOfWork,
+        data: CreateImportedPostDTO,
+    ) -> Result<Option<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"INSERT INTO "post" (id, text, author_id, created_at, reply_to_id, source_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (author_id, source_id) WHERE source_id IS NOT NULL DO NOTHING
+            RETURNING id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                FALSE AS "bookmarked_by_me!""#,
+            Uuid::new_v4(),
+            data.text,
+            data.author_id,
+            data.created_at,
+            data.reply_to_id,
+            data.source_id
+        )
+        .fetch_optional(uow.conn())
+        .await?)
+    }
+
+    /// Posts of the author imported with one of the `source_ids`, including the trash.
+    pub async fn find_all_by_source_ids(
+        &self,
+        author_id: &Uuid,
+        source_ids: &[String],
+    ) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                FALSE AS "bookmarked_by_me!"
+            FROM "post" WHERE author_id = $1 AND source_id = ANY($2)"#,
+            author_id,
+            source_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Finds a post in the trash.
+    pub async fn find_one_deleted(&self, id: &Uuid) -> Result<Option<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = "post".author_id
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE id = $1 AND deleted_at IS NOT NULL"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_all_deleted(&self, author_id: &Uuid) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = "post".author_id
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE author_id = $1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+            author_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Returns `false` when the post isn't in the trash, for example because
+    /// it was purged in the meantime
+    pub async fn restore(&self, id: &Uuid) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "post" SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL"#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
     }
 
-    async fn delete(&self, id: &Self::Id) {
-        sqlx::query!(r#"DELETE FROM "post" WHERE id = $1"#, id)
+    /// Locks the posts that have been in the trash since before `before`, so
+    /// they can't be restored while they are purged
+    pub async fn lock_deleted_before(
+        &self,
+        uow: &mut UnitOfWork,
+        before: NaiveDateTime,
+    ) -> Result<Vec<Uuid>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT id FROM "post" WHERE deleted_at IS NOT NULL AND deleted_at < $1 FOR UPDATE"#,
+            before
+        )
+        .fetch_all(uow.conn())
+        .await?)
+    }
+
+    pub async fn delete_all_by_ids(
+        &self,
+        uow: &mut UnitOfWork,
+        ids: &[Uuid],
+    ) -> Result<u64, RepoError> {
+        let result = sqlx::query!(r#"DELETE FROM "post" WHERE id = ANY($1)"#, ids)
+            .execute(uow.conn())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Which of the posts are still there and not in the trash.
+    pub async fn find_existing(&self, ids: &[Uuid]) -> Result<Vec<Uuid>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT id FROM "post" WHERE id = ANY($1) AND deleted_at IS NULL"#,
+            ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Rebuilds the full-text index of post texts
+    pub async fn reindex_search(&self) -> Result<(), RepoError> {
+        sqlx::query!(r#"REINDEX INDEX CONCURRENTLY post_search_idx"#)
             .execute(&self.pool)
-            .await
-            .unwrap();
+            .await?;
+        Ok(())
     }
 
-    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) {
-        let post = self.find_one(id).await;
+    /// Finds a post whether it is published or not. Only for the author's eyes.
+    pub async fn find_one_unpublished(&self, id: &Uuid) -> Result<Option<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                FALSE AS "bookmarked_by_me!"
+            FROM "post" WHERE id = $1 AND deleted_at IS NULL"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
 
-        let mut post = post.unwrap();
-        if let Some(text) = data.text {
-            post.text = text
-        }
+    pub async fn find_all_scheduled(&self, author_id: &Uuid) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = "post".author_id
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE author_id = $1 AND deleted_at IS NULL AND scheduled_at IS NOT NULL
+            ORDER BY scheduled_at"#,
+            author_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn find_all_drafts(&self, author_id: &Uuid) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"SELECT id, text, author_id, created_at, edited, edited_at, deleted_at, draft,
+                scheduled_at, pinned_at, reply_to_id, source_id,
+                EXISTS (
+                    SELECT 1 FROM "bookmark" b WHERE b.post_id = "post".id AND b.user_id = "post".author_id
+                ) AS "bookmarked_by_me!"
+            FROM "post" WHERE author_id = $1 AND deleted_at IS NULL AND draft
+            ORDER BY created_at DESC"#,
+            author_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
 
+    /// Schedules an unpublished post, or moves it to the drafts when `scheduled_at` is `None`.
+    pub async fn schedule(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        scheduled_at: Option<NaiveDateTime>,
+    ) -> Result<(), RepoError> {
         sqlx::query!(
-            r#"UPDATE "post" SET text = $1, edited =  true WHERE id = $2"#,
-            post.text,
-            id
+            r#"UPDATE "post" SET scheduled_at = $2, draft = $2::timestamp IS NULL WHERE id = $1"#,
+            id,
+            scheduled_at
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Publishes up to `limit` scheduled posts that are due at `now`. Rows are
+    /// claimed with `SKIP LOCKED`, so several instances can run this at once
+    /// without publishing a post twice.
+    ///
+    /// Published posts take `now` as their creation time and their polls are
+    /// shifted to keep the duration they were created with.
+    pub async fn publish_due(
+        &self,
+        now: NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<Post>, RepoError> {
+        Ok(sqlx::query_as!(
+            Post,
+            r#"WITH due AS (
+                SELECT id, created_at FROM "post"
+                WHERE scheduled_at <= $1 AND deleted_at IS NULL
+                ORDER BY scheduled_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            ), polls AS (
+                UPDATE "poll" SET expires_at = expires_at + ($1 - due.created_at)
+                FROM due WHERE "poll".post_id = due.id
+            )
+            UPDATE "post" p SET created_at = $1, scheduled_at = NULL
+            FROM due WHERE p.id = due.id
+            RETURNING p.id, p.text, p.author_id, p.created_at, p.edited, p.edited_at, p.deleted_at,
+                p.draft, p.scheduled_at, p.pinned_at, p.reply_to_id, p.source_id,
+                FALSE AS "bookmarked_by_me!""#,
+            now,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Publishes a draft or scheduled post right away. Returns `false` when
+    /// the post was already published, e.g. by `publish_due` in the meantime.
+    pub async fn publish(&self, id: &Uuid, now: NaiveDateTime) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"WITH target AS (
+                SELECT id, created_at FROM "post"
+                WHERE id = $1 AND deleted_at IS NULL AND (draft OR scheduled_at IS NOT NULL)
+                FOR UPDATE
+            ), polls AS (
+                UPDATE "poll" SET expires_at = expires_at + ($2 - target.created_at)
+                FROM target WHERE "poll".post_id = target.id
+            )
+            UPDATE "post" p SET created_at = $2, scheduled_at = NULL, draft = FALSE
+            FROM target WHERE p.id = target.id"#,
+            id,
+            now
         )
         .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Pins the post unless its author already has `max` pinned posts.
+    /// Returns whether the post is pinned afterwards.
+    pub async fn pin(
+        &self,
+        id: &Uuid,
+        author_id: &Uuid,
+        now: NaiveDateTime,
+        max: i64,
+    ) -> Result<bool, RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        // Pins of one author are counted with their user row locked, otherwise
+        // two concurrent pins could both see the last free slot
+        sqlx::query!(
+            r#"SELECT id FROM "user" WHERE id = $1 FOR NO KEY UPDATE"#,
+            author_id
+        )
+        .fetch_optional(uow.conn())
+        .await?;
+        let pinned = sqlx::query!(
+            r#"UPDATE "post" p SET pinned_at = $2
+            WHERE p.id = $1 AND p.pinned_at IS NULL
+            AND (SELECT count(*) FROM "post" WHERE author_id = p.author_id AND pinned_at IS NOT NULL) < $3"#,
+            id,
+            now,
+            max
+        )
+        .execute(uow.conn())
+        .await?
+        .rows_affected()
+            > 0;
+        uow.commit().await?;
+        Ok(pinned)
+    }
+
+    pub async fn unpin(&self, id: &Uuid) -> Result<(), RepoError> {
+        sqlx::query!(r#"UPDATE "post" SET pinned_at = NULL WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_user(pool: &PgPool, username: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"INSERT INTO "user" (id, username, password) VALUES ($1, $2, '')"#,
+            id,
+            username
+        )
+        .execute(pool)
         .await
         .unwrap();
+        id
+    }
+
+    async fn insert_post(pool: &PgPool, author_id: &Uuid, text: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"INSERT INTO "post" (id, text, author_id) VALUES ($1, $2, $3)"#,
+            id,
+            text,
+            author_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn search_leaves_out_posts_of_hidden_authors(pool: PgPool) {
+        let active = insert_user(&pool, "active").await;
+        let suspended = insert_user(&pool, "suspended").await;
+        let leaving = insert_user(&pool, "leaving").await;
+        sqlx::query!(
+            r#"UPDATE "user" SET suspended_at = now() WHERE id = $1"#,
+            suspended
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"UPDATE "user" SET deletion_scheduled_at = now() WHERE id = $1"#,
+            leaving
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let visible = insert_post(&pool, &active, "walrus sighting").await;
+        insert_post(&pool, &suspended, "walrus spam").await;
+        insert_post(&pool, &leaving, "walrus farewell").await;
+
+        let hits = PostRepository { pool }
+            .search("walrus", 20, 0)
+            .await
+            .unwrap();
+
+        let ids: Vec<Uuid> = hits.iter().map(|hit| hit.id).collect();
+        assert_eq!(ids, vec![visible]);
     }
 }
@@ -0,0 +1,41 @@
+use sqlx::PgPool;
+
+use super::RepoError;
+use crate::models::stats::InstanceStats;
+use crate::models::user::DELETED_USER_ID;
+
+#[derive(Clone)]
+pub struct StatsRepository {
+    pub pool: PgPool,
+}
+
+impl StatsRepository {
+    /// Counts everything at once. The placeholder author of anonymized
+    /// content isn't counted as a user.
+    pub async fn fetch(&self) -> Result<InstanceStats, RepoError> {
+        Ok(sqlx::query_as!(
+            InstanceStats,
+            r#"SELECT
+                (SELECT COUNT(*) FROM "user" WHERE id <> $1) AS "users!",
+                (SELECT COUNT(*) FROM "user" WHERE is_admin) AS "admins!",
+                (SELECT COUNT(*) FROM "user" WHERE suspended_at IS NOT NULL) AS "suspended_users!",
+                (SELECT COUNT(*) FROM "user" WHERE deletion_scheduled_at IS NOT NULL) AS "deletions_scheduled!",
+                (SELECT COUNT(*) FROM "post"
+                    WHERE deleted_at IS NULL AND NOT draft AND scheduled_at IS NULL) AS "posts!",
+                (SELECT COUNT(*) FROM "post"
+                    WHERE deleted_at IS NULL AND scheduled_at IS NOT NULL) AS "scheduled_posts!",
+                (SELECT COUNT(*) FROM "post" WHERE deleted_at IS NULL AND draft) AS "drafts!",
+                (SELECT COUNT(*) FROM "post" WHERE deleted_at IS NOT NULL) AS "posts_in_trash!",
+                (SELECT COUNT(*) FROM "following") AS "follows!",
+                (SELECT COUNT(*) FROM "media") AS "media!",
+                (SELECT COALESCE(SUM(size), 0)::bigint FROM "media") AS "media_bytes!",
+                (SELECT COUNT(*) FROM "job" WHERE status = 'pending') AS "pending_jobs!",
+                (SELECT COUNT(*) FROM "job" WHERE status = 'failed') AS "failed_jobs!",
+                (SELECT COUNT(*) FROM "webhook") AS "webhooks!",
+                (SELECT COUNT(*) FROM "webhook" WHERE NOT enabled) AS "disabled_webhooks!""#,
+            DELETED_USER_ID
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+}
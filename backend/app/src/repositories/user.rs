@@ -1,20 +1,21 @@
-use super::Repository;
-use crate::models::user::User;
+use super::{RepoError, Repository, UnitOfWork};
+use crate::models::search::SearchHit;
+use crate::models::user::{User, DELETED_USER_ID};
+use chrono::NaiveDateTime;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 pub struct CreateUserDTO {
     pub username: String,
     pub password: String,
-    pub avatar: Option<String>,
     pub about: String,
 }
 
 pub struct UpdateUserDTO {
     pub username: Option<String>,
     pub password: Option<String>,
-    pub avatar: Option<String>,
     pub about: Option<String>,
+    pub dms_from_followers_only: Option<bool>,
 }
 
 #[derive(Clone)]
@@ -30,53 +31,91 @@ impl Repository for UserRepository {
     type UpdateDTO = UpdateUserDTO;
     type FindAllParams = ();
 
-    async fn create(&self, data: Self::CreateDTO) -> Self::Model {
-        let id = Uuid::new_v4();
-        sqlx::query!(
-            r#"INSERT INTO "user" (id, username, password, avatar, about) VALUES ($1, $2, $3, $4, $5)"#,
-            id,
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            User,
+            r#"INSERT INTO "user" (id, username, password, about) VALUES ($1, $2, $3, $4)
+            RETURNING id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at"#,
+            Uuid::new_v4(),
             data.username,
             data.password,
-            data.avatar,
             data.about
         )
-        .execute(&self.pool)
-        .await
-        .unwrap();
-        User {
-            id,
-            username: data.username,
-            password: data.password,
-            avatar: data.avatar,
-            about: data.about,
-        }
+        .fetch_one(&self.pool)
+        .await?)
     }
 
-    async fn find_one(&self, id: &Self::Id) -> Option<Self::Model> {
-        sqlx::query_as!(User, r#"SELECT * FROM "user" WHERE id = $1"#, id)
-            .fetch_optional(&self.pool)
-            .await
-            .unwrap()
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            User,
+            r#"SELECT id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at
+            FROM "user" WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
     }
 
-    async fn find_all(&self, _params: Self::FindAllParams) -> Vec<Self::Model> {
-        sqlx::query_as!(User, r#"SELECT * FROM "user""#)
-            .fetch_all(&self.pool)
-            .await
-            .unwrap()
+    /// Leaves out suspended users and the placeholder for deleted users
+    async fn find_all(&self, _params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            User,
+            r#"SELECT id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at
+            FROM "user" WHERE suspended_at IS NULL AND id <> $1"#,
+            DELETED_USER_ID
+        )
+        .fetch_all(&self.pool)
+        .await?)
     }
 
-    async fn delete(&self, id: &Self::Id) {
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
         sqlx::query!(r#"DELETE FROM "user" WHERE id = $1"#, id)
             .execute(&self.pool)
-            .await
-            .unwrap();
+            .await?;
+        Ok(())
     }
 
-    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) {
-        let user = self.find_one(id).await;
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        self.update_in(&mut uow, id, data).await?;
+        uow.commit().await
+    }
+}
 
-        let mut user = user.unwrap();
+impl UserRepository {
+    /// Finds the user and locks the row until the unit of work ends
+    pub async fn lock(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+    ) -> Result<Option<<UserRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            User,
+            r#"SELECT id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at
+            FROM "user" WHERE id = $1 FOR UPDATE"#,
+            id
+        )
+        .fetch_optional(uow.conn())
+        .await?)
+    }
+
+    pub async fn update_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        data: UpdateUserDTO,
+    ) -> Result<(), RepoError> {
+        let Some(mut user) = self.lock(uow, id).await? else {
+            return Ok(());
+        };
         if let Some(username) = data.username {
             user.username = username;
         }
@@ -84,40 +123,265 @@ impl Repository for UserRepository {
             user.password = password;
         }
 
-        if let Some(avatar) = data.avatar {
-            user.avatar = Some(avatar);
-        }
-
         if let Some(about) = data.about {
             user.about = about;
         }
 
+        if let Some(dms_from_followers_only) = data.dms_from_followers_only {
+            user.dms_from_followers_only = dms_from_followers_only;
+        }
+
         sqlx::query!(
-            r#"UPDATE "user" SET username = $2, password = $3, avatar = $4, about = $5 WHERE id = $1"#,
+            r#"UPDATE "user" SET username = $2, password = $3, about = $4, dms_from_followers_only = $5 WHERE id = $1"#,
             user.id,
             user.username,
             user.password,
-            user.avatar,
-            user.about
+            user.about,
+            user.dms_from_followers_only
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_in(&self, uow: &mut UnitOfWork, id: &Uuid) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "user" WHERE id = $1"#, id)
+            .execute(uow.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// Sets or clears the time the account gets purged
+    pub async fn schedule_deletion(
+        &self,
+        id: &Uuid,
+        at: Option<NaiveDateTime>,
+    ) -> Result<(), RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        self.schedule_deletion_in(&mut uow, id, at).await?;
+        uow.commit().await
+    }
+
+    pub async fn schedule_deletion_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        at: Option<NaiveDateTime>,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "user" SET deletion_scheduled_at = $2 WHERE id = $1"#,
+            id,
+            at
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Signs the user out everywhere by rejecting tokens issued before `now`
+    pub async fn revoke_tokens_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        now: NaiveDateTime,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "user" SET tokens_valid_after = $2 WHERE id = $1"#,
+            id,
+            now
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Grants or takes away access to the `/admin` endpoints
+    pub async fn set_admin(&self, id: &Uuid, is_admin: bool) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "user" SET is_admin = $2 WHERE id = $1"#,
+            id,
+            is_admin
         )
         .execute(&self.pool)
-        .await
-        .unwrap();
+        .await?;
+        Ok(())
+    }
+
+    /// Suspends the account at `at`, or lifts the suspension when it's `None`.
+    /// Returns `false` when the account already was in that state.
+    pub async fn set_suspended(
+        &self,
+        id: &Uuid,
+        at: Option<NaiveDateTime>,
+    ) -> Result<bool, RepoError> {
+        let mut uow = UnitOfWork::begin(&self.pool).await?;
+        let changed = self.set_suspended_in(&mut uow, id, at).await?;
+        uow.commit().await?;
+        Ok(changed)
+    }
+
+    pub async fn set_suspended_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        at: Option<NaiveDateTime>,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"UPDATE "user" SET suspended_at = $2
+            WHERE id = $1 AND (suspended_at IS NULL) <> ($2::timestamp IS NULL)"#,
+            id,
+            at
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Rebuilds the full-text index of usernames and bios
+    pub async fn reindex_search(&self) -> Result<(), RepoError> {
+        sqlx::query!(r#"REINDEX INDEX CONCURRENTLY user_search_idx"#)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query!(r#"REINDEX INDEX CONCURRENTLY user_username_prefix_idx"#)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_due_deletions(&self, now: NaiveDateTime) -> Result<Vec<Uuid>, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT id FROM "user" WHERE deletion_scheduled_at <= $1"#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn set_avatar_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        media_id: Option<Uuid>,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "user" SET avatar_media_id = $2, avatar = NULL WHERE id = $1"#,
+            id,
+            media_id
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_banner_in(
+        &self,
+        uow: &mut UnitOfWork,
+        id: &Uuid,
+        media_id: Option<Uuid>,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "user" SET banner_media_id = $2 WHERE id = $1"#,
+            id,
+            media_id
+        )
+        .execute(uow.conn())
+        .await?;
+        Ok(())
     }
-}
 
-impl UserRepository {
     pub async fn find_one_by_username(
         &self,
-        username: &String,
-    ) -> Option<<UserRepository as Repository>::Model> {
-        sqlx::query_as!(
+        username: &str,
+    ) -> Result<Option<<UserRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
             User,
-            r#"SELECT * FROM "user" WHERE lower(username) = LOWER($1)"#,
+            r#"SELECT id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at
+            FROM "user" WHERE lower(username) = LOWER($1)"#,
             username
         )
         .fetch_optional(&self.pool)
-        .await
-        .unwrap()
+        .await?)
+    }
+
+    pub async fn find_one_by_username_in(
+        &self,
+        uow: &mut UnitOfWork,
+        username: &str,
+    ) -> Result<Option<<UserRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            User,
+            r#"SELECT id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at
+            FROM "user" WHERE lower(username) = LOWER($1)"#,
+            username
+        )
+        .fetch_optional(uow.conn())
+        .await?)
+    }
+
+    pub async fn find_all_by_ids(
+        &self,
+        ids: &[Uuid],
+    ) -> Result<Vec<<UserRepository as Repository>::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            User,
+            r#"SELECT id, username, password, about, dms_from_followers_only, avatar,
+                avatar_media_id, banner_media_id, deletion_scheduled_at, tokens_valid_after,
+                is_admin, suspended_at
+            FROM "user" WHERE id = ANY($1)"#,
+            ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Searches usernames and bios. Usernames starting with the query rank above
+    /// full-text matches. Suspended users, accounts pending deletion and the
+    /// placeholder for deleted users are left out, and bios are HTML-escaped
+    /// before highlighting.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchHit>, RepoError> {
+        let prefix = format!(
+            "{}%",
+            query
+                .to_lowercase()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        Ok(sqlx::query_as!(
+            SearchHit,
+            r#"SELECT
+                id,
+                (
+                    CASE WHEN lower(username) LIKE $2 THEN 1 ELSE 0 END
+                    + ts_rank(search_vector, q)
+                )::real AS "rank!",
+                ts_headline(
+                    'simple',
+                    replace(replace(replace(about, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                    q,
+                    'StartSel=<mark>, StopSel=</mark>'
+                ) AS "snippet!"
+            FROM "user", websearch_to_tsquery('simple', $1) q
+            WHERE (lower(username) LIKE $2 OR search_vector @@ q)
+            AND suspended_at IS NULL AND deletion_scheduled_at IS NULL AND id <> $5
+            ORDER BY 2 DESC, username
+            LIMIT $3 OFFSET $4"#,
+            query,
+            prefix,
+            limit,
+            offset,
+            DELETED_USER_ID
+        )
+        .fetch_all(&self.pool)
+        .await?)
     }
 }
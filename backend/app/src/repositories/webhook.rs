@@ -0,0 +1,164 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository};
+use crate::models::webhook::Webhook;
+
+pub struct CreateWebhookDTO {
+    pub owner_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub all_users: bool,
+}
+
+pub struct WebhookFindAllParams {
+    pub owner_id: Uuid,
+}
+
+/// Fields left `None` are kept. Enabling a webhook clears its failures.
+pub struct UpdateWebhookDTO {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Clone)]
+pub struct WebhookRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for WebhookRepository {
+    type Model = Webhook;
+    type Id = Uuid;
+    type CreateDTO = CreateWebhookDTO;
+    type UpdateDTO = UpdateWebhookDTO;
+    type FindAllParams = WebhookFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            Webhook,
+            r#"INSERT INTO "webhook" (id, owner_id, url, secret, events, all_users)
+            VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"#,
+            Uuid::new_v4(),
+            data.owner_id,
+            data.url,
+            data.secret,
+            &data.events,
+            data.all_users
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(
+            sqlx::query_as!(Webhook, r#"SELECT * FROM "webhook" WHERE id = $1"#, id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Webhooks of the owner, newest first.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            Webhook,
+            r#"SELECT * FROM "webhook" WHERE owner_id = $1 ORDER BY created_at DESC"#,
+            params.owner_id
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "webhook" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "webhook" SET
+                url = COALESCE($2, url),
+                events = COALESCE($3, events),
+                enabled = COALESCE($4, enabled),
+                failure_count = CASE WHEN $4 THEN 0 ELSE failure_count END,
+                disabled_at = CASE WHEN $4 THEN NULL ELSE disabled_at END
+            WHERE id = $1"#,
+            id,
+            data.url,
+            data.events.as_deref(),
+            data.enabled
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl WebhookRepository {
+    pub async fn count_by_owner(&self, owner_id: &Uuid) -> Result<i64, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM "webhook" WHERE owner_id = $1"#,
+            owner_id
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Enabled webhooks subscribed to `event` that are owned by one of
+    /// `user_ids` or receive the events of all users.
+    pub async fn find_subscribed(
+        &self,
+        event: &str,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<Webhook>, RepoError> {
+        Ok(sqlx::query_as!(
+            Webhook,
+            r#"SELECT * FROM "webhook"
+            WHERE enabled AND $1 = ANY(events) AND (all_users OR owner_id = ANY($2))"#,
+            event,
+            user_ids
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Clears the failures in a row after a successful delivery.
+    pub async fn record_success(&self, id: &Uuid) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "webhook" SET failure_count = 0 WHERE id = $1 AND failure_count > 0"#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Counts a failed attempt and disables the webhook once `max_failures`
+    /// attempts in a row failed. Returns whether it's still enabled.
+    pub async fn record_failure(
+        &self,
+        id: &Uuid,
+        max_failures: i32,
+        now: NaiveDateTime,
+    ) -> Result<bool, RepoError> {
+        Ok(sqlx::query_scalar!(
+            r#"UPDATE "webhook" SET
+                failure_count = failure_count + 1,
+                enabled = enabled AND failure_count + 1 < $2,
+                disabled_at = CASE WHEN enabled AND failure_count + 1 >= $2 THEN $3 ELSE disabled_at END
+            WHERE id = $1
+            RETURNING enabled"#,
+            id,
+            max_failures,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or(false))
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{RepoError, Repository};
+use crate::models::webhook::WebhookDelivery;
+
+pub struct CreateWebhookDeliveryDTO {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+pub struct WebhookDeliveryFindAllParams {
+    pub webhook_id: Uuid,
+    /// Only deliveries with this status, all of them when `None`
+    pub status: Option<String>,
+    pub limit: i64,
+}
+
+/// Outcome of an attempt, counted on top of the earlier ones
+pub struct WebhookAttemptDTO {
+    /// `pending` when it's tried again, `delivered` or `failed`
+    pub status: String,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone)]
+pub struct WebhookDeliveryRepository {
+    pub pool: PgPool,
+}
+
+#[async_trait::async_trait]
+impl Repository for WebhookDeliveryRepository {
+    type Model = WebhookDelivery;
+    type Id = Uuid;
+    type CreateDTO = CreateWebhookDeliveryDTO;
+    type UpdateDTO = WebhookAttemptDTO;
+    type FindAllParams = WebhookDeliveryFindAllParams;
+
+    async fn create(&self, data: Self::CreateDTO) -> Result<Self::Model, RepoError> {
+        Ok(sqlx::query_as!(
+            WebhookDelivery,
+            r#"INSERT INTO "webhook_delivery" (id, webhook_id, event, payload)
+            VALUES ($1, $2, $3, $4) RETURNING *"#,
+            data.id,
+            data.webhook_id,
+            data.event,
+            data.payload
+        )
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn find_one(&self, id: &Self::Id) -> Result<Option<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT * FROM "webhook_delivery" WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Deliveries of the webhook, newest first.
+    async fn find_all(&self, params: Self::FindAllParams) -> Result<Vec<Self::Model>, RepoError> {
+        Ok(sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT * FROM "webhook_delivery"
+            WHERE webhook_id = $1 AND ($2::varchar IS NULL OR status = $2)
+            ORDER BY created_at DESC LIMIT $3"#,
+            params.webhook_id,
+            params.status,
+            params.limit
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<(), RepoError> {
+        sqlx::query!(r#"DELETE FROM "webhook_delivery" WHERE id = $1"#, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Records an attempt.
+    async fn update(&self, id: &Self::Id, data: Self::UpdateDTO) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"UPDATE "webhook_delivery" SET
+                attempts = attempts + 1, status = $2, response_status = $3, last_error = $4,
+                delivered_at = $5
+            WHERE id = $1"#,
+            id,
+            data.status,
+            data.response_status,
+            data.error,
+            data.delivered_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl WebhookDeliveryRepository {
+    /// Gives up on the pending deliveries of a webhook, such as when it's disabled.
+    pub async fn fail_pending(&self, webhook_id: &Uuid, error: &str) -> Result<u64, RepoError> {
+        Ok(sqlx::query!(
+            r#"UPDATE "webhook_delivery" SET status = 'failed', last_error = $2
+            WHERE webhook_id = $1 AND status = 'pending'"#,
+            webhook_id,
+            error
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected())
+    }
+
+    /// Deletes finished deliveries created before `before`.
+    pub async fn purge(&self, before: NaiveDateTime) -> Result<u64, RepoError> {
+        Ok(sqlx::query!(
+            r#"DELETE FROM "webhook_delivery" WHERE status <> 'pending' AND created_at < $1"#,
+            before
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected())
+    }
+}
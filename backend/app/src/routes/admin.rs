@@ -0,0 +1,102 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use uuid::Uuid;
+
+use crate::routes::{admin_middleware, auth_middleware};
+use crate::schemas::job::{JobSchema, JobsQuery};
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_jobs, get_job, retry_job),
+    components(schemas(JobSchema)),
+    tags(
+        (name = "admin", description = "Instance administration api, for admins only")
+    )
+)]
+pub(super) struct AdminDoc;
+
+pub fn init_admin_router(state: AppState) -> Router<AppState> {
+    let admin_middleware = axum::middleware::from_fn_with_state(state.clone(), admin_middleware);
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/jobs", get(get_jobs))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/retry", post(retry_job))
+        .layer(admin_middleware)
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    tag = "admin",
+    params(JobsQuery),
+    responses(
+        (status = 200, description = "Background jobs, newest first", body = Vec<JobSchema>),
+        (status = 403, description = "Not an admin")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<JobsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let jobs = state.job_service.find_jobs(query).await?;
+    Ok(Json(jobs))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Job with its last error", body = JobSchema),
+        (status = 403, description = "Not an admin"),
+        (status = 404, description = "Job not found, finished jobs are deleted")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let job = state.job_service.find_job(&id).await?;
+    Ok(Json(job))
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs/{id}/retry",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Failed job queued again with fresh attempts", body = JobSchema),
+        (status = 403, description = "Not an admin"),
+        (status = 404, description = "Job not found"),
+        (status = 409, description = "Job hasn't failed")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn retry_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let job = state.job_service.retry_job(&id).await?;
+    tracing::info!("Queued failed job `{}` again", id);
+    Ok(Json(job))
+}
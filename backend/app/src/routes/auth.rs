@@ -1,7 +1,8 @@
-use axum::routing::{delete, get, patch, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Body,
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::{header, StatusCode},
     response::{IntoResponse, Json},
     Extension, Router,
 };
@@ -9,13 +10,20 @@ use serde_json::json;
 use uuid::Uuid;
 
 use super::auth_middleware;
+use super::media::read_file;
 use crate::{
     schemas::{
         auth::{AuthBody, AuthPayload},
-        user::{CreateUserSchema, UpdateUserSchema, UserSchema},
+        data_export::DataExportSchema,
+        import::{ImportSchema, UploadImportSchema},
+        media::{MediaVariantsSchema, UploadMediaSchema},
+        user::{
+            AccountDeletionSchema, CreateUserSchema, DeleteAccountSchema, MeSchema,
+            UpdateUserSchema, UserSchema,
+        },
     },
     state::AppState,
-    utils::{errors::AppError, validator::ValidatedJson},
+    utils::{errors::AppError, images::ProfileImage, validator::ValidatedJson},
 };
 
 #[derive(utoipa::OpenApi)]
@@ -24,13 +32,32 @@ use crate::{
         login,
         register_user,
         delete_user,
+        request_deletion,
+        cancel_deletion,
+        request_export,
+        get_export,
+        download_export,
+        request_import,
+        get_import,
         update_user,
         get_me,
+        upload_avatar,
+        delete_avatar,
+        upload_banner,
+        delete_banner,
     ),
     components(schemas(
         UserSchema,
+        MeSchema,
+        MediaVariantsSchema,
+        UploadMediaSchema,
         CreateUserSchema,
         UpdateUserSchema,
+        DeleteAccountSchema,
+        AccountDeletionSchema,
+        DataExportSchema,
+        ImportSchema,
+        UploadImportSchema,
         AuthBody,
         AuthPayload,
     )),
@@ -41,13 +68,60 @@ use crate::{
 pub(super) struct AuthDoc;
 
 pub(super) fn init_auth_router(state: AppState) -> Router<AppState> {
+    // Leave room for the multipart boundaries around the file
+    let body_limit = DefaultBodyLimit::max(state.config.media_max_bytes + 64 * 1024);
+    let import_body_limit = DefaultBodyLimit::max(state.config.import_max_bytes + 64 * 1024);
     let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
     Router::new()
         .route("/", patch(update_user).layer(auth_middleware.clone()))
         .route("/:id", delete(delete_user).layer(auth_middleware.clone()))
+        .route(
+            "/delete",
+            post(request_deletion).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/delete/cancel",
+            post(cancel_deletion).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/export",
+            post(request_export).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/export/:id",
+            get(get_export).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/export/:id/download",
+            get(download_export).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/import",
+            post(request_import)
+                .layer(import_body_limit)
+                .layer(auth_middleware.clone()),
+        )
+        .route(
+            "/import/:id",
+            get(get_import).layer(auth_middleware.clone()),
+        )
         .route("/login", post(login))
         .route("/register", post(register_user))
         .route("/me", get(get_me).layer(auth_middleware.clone()))
+        .route(
+            "/avatar",
+            put(upload_avatar)
+                .delete(delete_avatar)
+                .layer(body_limit.clone())
+                .layer(auth_middleware.clone()),
+        )
+        .route(
+            "/banner",
+            put(upload_banner)
+                .delete(delete_banner)
+                .layer(body_limit)
+                .layer(auth_middleware.clone()),
+        )
 }
 
 #[utoipa::path(
@@ -90,7 +164,8 @@ pub async fn register_user(
     path = "/{id}",
     tag = "auth",
     responses(
-        (status = 200, description = "User deleted successfully")
+        (status = 200, description = "User deleted successfully, without a grace period"),
+        (status = 403, description = "Only your own account can be deleted")
     ),
     params(
         ("id" = Uuid, Path, description = "User id from database")
@@ -101,12 +176,201 @@ pub async fn register_user(
 )]
 pub async fn delete_user(
     State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
+    if id != user.id {
+        return Err(AppError::CantDoThis);
+    }
     state.user_service.delete_user(&id).await?;
     Ok(Json(json!({"message": "User deleted"})))
 }
 
+#[utoipa::path(
+    post,
+    path = "/delete",
+    tag = "auth",
+    request_body = DeleteAccountSchema,
+    responses(
+        (status = 200, description = "Account scheduled for deletion and signed out everywhere, or deleted right away when there is no grace period", body = AccountDeletionSchema),
+        (status = 401, description = "Wrong password"),
+        (status = 409, description = "Deletion is already scheduled")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn request_deletion(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Json(body): Json<DeleteAccountSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let deletion = state
+        .user_service
+        .request_deletion(&user.id, body.password)
+        .await?;
+    Ok(Json(deletion))
+}
+
+#[utoipa::path(
+    post,
+    path = "/delete/cancel",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Scheduled deletion cancelled"),
+        (status = 400, description = "Deletion isn't scheduled")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn cancel_deletion(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    state.user_service.cancel_deletion(&user.id).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/export",
+    tag = "auth",
+    responses(
+        (status = 202, description = "Export of your data queued, poll it until it's ready", body = DataExportSchema),
+        (status = 409, description = "An export is already in progress")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn request_export(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let export = state.data_export_service.request_export(&user.id).await?;
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/{id}",
+    tag = "auth",
+    params(
+        ("id" = Uuid, Path, description = "Export id")
+    ),
+    responses(
+        (status = 200, description = "Export status, with a `download_url` once it's ready", body = DataExportSchema),
+        (status = 404, description = "Export not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_export(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let export = state.data_export_service.find_export(&id, &user.id).await?;
+    Ok(Json(export))
+}
+
+#[utoipa::path(
+    get,
+    path = "/export/{id}/download",
+    tag = "auth",
+    params(
+        ("id" = Uuid, Path, description = "Export id")
+    ),
+    responses(
+        (status = 200, description = "ZIP archive with JSON files, media and an `index.html`", content_type = "application/zip"),
+        (status = 404, description = "Export not found"),
+        (status = 409, description = "Export isn't ready or has expired")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn download_export(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let archive = state.data_export_service.download(&id, &user.id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"twotty-{}.zip\"", user.username),
+            ),
+        ],
+        Body::from_stream(archive),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "auth",
+    request_body(content = UploadImportSchema, content_type = "multipart/form-data"),
+    responses(
+        (status = 202, description = "Import queued, poll it for progress", body = ImportSchema),
+        (status = 409, description = "An import is already in progress"),
+        (status = 413, description = "Upload too large"),
+        (status = 422, description = "Not a Twitter archive or Mastodon outbox")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn request_import(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let mut archive = None;
+    let mut follows = None;
+    while let Some(field) = multipart.next_field().await? {
+        match field.name() {
+            Some("file") => archive = Some(field.bytes().await?),
+            Some("follows") => follows = Some(field.bytes().await?),
+            _ => {}
+        }
+    }
+    let import = state
+        .import_service
+        .request_import(&user.id, archive.as_deref(), follows.as_deref())
+        .await?;
+    tracing::info!("Queued import `{}` of user `{}`", import.id, user.id);
+    Ok((StatusCode::ACCEPTED, Json(import)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/import/{id}",
+    tag = "auth",
+    params(
+        ("id" = Uuid, Path, description = "Import id")
+    ),
+    responses(
+        (status = 200, description = "Import status and progress", body = ImportSchema),
+        (status = 404, description = "Import not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_import(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let import = state.import_service.find_import(&id, &user.id).await?;
+    Ok(Json(import))
+}
+
 #[utoipa::path(
     patch,
     path = "",
@@ -134,12 +398,114 @@ pub async fn update_user(
     path = "/me",
     tag = "auth",
     responses(
-        (status = 200, description = "Current user", body = UserSchema)
+        (status = 200, description = "Current user", body = MeSchema)
     ),
     security(
         ("http" = [])
     )
 )]
-pub async fn get_me(Extension(me): Extension<UserSchema>) -> Result<impl IntoResponse, AppError> {
+pub async fn get_me(
+    State(state): State<AppState>,
+    Extension(me): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let me = state.user_service.find_me(&me.id).await?;
     Ok(Json(me))
 }
+
+#[utoipa::path(
+    put,
+    path = "/avatar",
+    tag = "auth",
+    request_body(content = UploadMediaSchema, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Avatar replaced, the image is cropped to a square", body = UserSchema),
+        (status = 413, description = "File is too large"),
+        (status = 415, description = "Unsupported file type")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let data = read_file(&mut multipart).await?;
+    let user = state
+        .user_service
+        .set_profile_image(&user.id, ProfileImage::Avatar, Some(&data))
+        .await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/avatar",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Avatar reset to the default", body = UserSchema)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = state
+        .user_service
+        .set_profile_image(&user.id, ProfileImage::Avatar, None)
+        .await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    put,
+    path = "/banner",
+    tag = "auth",
+    request_body(content = UploadMediaSchema, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Banner replaced, the image is cropped to 3:1", body = UserSchema),
+        (status = 413, description = "File is too large"),
+        (status = 415, description = "Unsupported file type")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn upload_banner(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let data = read_file(&mut multipart).await?;
+    let user = state
+        .user_service
+        .set_profile_image(&user.id, ProfileImage::Banner, Some(&data))
+        .await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/banner",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Banner reset to the default", body = UserSchema)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn delete_banner(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = state
+        .user_service
+        .set_profile_image(&user.id, ProfileImage::Banner, None)
+        .await?;
+    Ok(Json(user))
+}
@@ -0,0 +1,199 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, patch};
+use axum::{Extension, Json, Router};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::routes::auth_middleware;
+use crate::schemas::bookmark::{
+    BookmarkFolderSchema, BookmarkSchema, BookmarksQuery, CreateBookmarkFolderSchema,
+    MoveBookmarkSchema, UpdateBookmarkFolderSchema,
+};
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+use crate::utils::validator::ValidatedJson;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        get_bookmarks,
+        move_bookmark,
+        get_folders,
+        create_folder,
+        rename_folder,
+        delete_folder,
+    ),
+    components(schemas(
+        BookmarkSchema,
+        BookmarkFolderSchema,
+        CreateBookmarkFolderSchema,
+        UpdateBookmarkFolderSchema,
+        MoveBookmarkSchema,
+    )),
+    tags(
+        (name = "bookmarks", description = "Private bookmarks api")
+    )
+)]
+pub(super) struct BookmarksDoc;
+
+pub fn init_bookmarks_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/", get(get_bookmarks))
+        .route("/folders", get(get_folders).post(create_folder))
+        .route("/folders/:id", patch(rename_folder).delete(delete_folder))
+        .route("/:post_id", patch(move_bookmark))
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "bookmarks",
+    params(BookmarksQuery),
+    responses(
+        (status = 200, description = "Your bookmarks, most recently bookmarked first", body = Vec<BookmarkSchema>),
+        (status = 404, description = "Folder not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_bookmarks(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Query(query): Query<BookmarksQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let bookmarks = state
+        .bookmark_service
+        .find_bookmarks(&user.id, query)
+        .await?;
+    Ok(Json(bookmarks))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{post_id}",
+    tag = "bookmarks",
+    params(
+        ("post_id" = Uuid, Path, description = "Id of the bookmarked post"),
+    ),
+    request_body = MoveBookmarkSchema,
+    responses(
+        (status = 200, description = "Bookmark moved"),
+        (status = 404, description = "Bookmark or folder not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn move_bookmark(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(post_id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<MoveBookmarkSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .bookmark_service
+        .move_bookmark(&post_id, &user.id, data)
+        .await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/folders",
+    tag = "bookmarks",
+    responses(
+        (status = 200, description = "Your bookmark folders by name", body = Vec<BookmarkFolderSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_folders(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let folders = state.bookmark_service.find_folders(&user.id).await?;
+    Ok(Json(folders))
+}
+
+#[utoipa::path(
+    post,
+    path = "/folders",
+    tag = "bookmarks",
+    request_body = CreateBookmarkFolderSchema,
+    responses(
+        (status = 201, description = "Folder created", body = BookmarkFolderSchema),
+        (status = 409, description = "You already have a folder with this name")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn create_folder(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    ValidatedJson(data): ValidatedJson<CreateBookmarkFolderSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let folder = state.bookmark_service.create_folder(&user.id, data).await?;
+    Ok((StatusCode::CREATED, Json(folder)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/folders/{id}",
+    tag = "bookmarks",
+    params(
+        ("id" = Uuid, Path, description = "Folder id"),
+    ),
+    request_body = UpdateBookmarkFolderSchema,
+    responses(
+        (status = 200, description = "Folder renamed", body = BookmarkFolderSchema),
+        (status = 404, description = "Folder not found"),
+        (status = 409, description = "You already have a folder with this name")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn rename_folder(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<UpdateBookmarkFolderSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let folder = state
+        .bookmark_service
+        .rename_folder(&id, &user.id, data)
+        .await?;
+    Ok(Json(folder))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/folders/{id}",
+    tag = "bookmarks",
+    params(
+        ("id" = Uuid, Path, description = "Folder id"),
+    ),
+    responses(
+        (status = 200, description = "Folder deleted, its bookmarks are kept"),
+        (status = 404, description = "Folder not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn delete_folder(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.bookmark_service.delete_folder(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
@@ -0,0 +1,203 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::routes::auth_middleware;
+use crate::schemas::message::{
+    ConversationSchema, CreateConversationSchema, MessageSchema, MessagesQuery, SendMessageSchema,
+};
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+use crate::utils::validator::ValidatedJson;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        start_conversation,
+        get_conversations,
+        get_conversation,
+        get_messages,
+        send_message,
+        mark_conversation_read,
+    ),
+    components(schemas(
+        ConversationSchema,
+        MessageSchema,
+        CreateConversationSchema,
+        SendMessageSchema,
+    )),
+    tags(
+        (name = "conversations", description = "Direct messages api")
+    )
+)]
+pub(super) struct ConversationsDoc;
+
+pub fn init_conversations_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/", post(start_conversation).get(get_conversations))
+        .route("/:id", get(get_conversation))
+        .route("/:id/messages", get(get_messages).post(send_message))
+        .route("/:id/read", post(mark_conversation_read))
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "conversations",
+    request_body = CreateConversationSchema,
+    responses(
+        (status = 201, description = "Conversation started, or the existing one-to-one conversation", body = ConversationSchema),
+        (status = 403, description = "A member only accepts messages from followers"),
+        (status = 404, description = "User not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn start_conversation(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    ValidatedJson(data): ValidatedJson<CreateConversationSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let conversation = state
+        .message_service
+        .start_conversation(data, &user)
+        .await?;
+    Ok((StatusCode::CREATED, Json(conversation)))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "conversations",
+    responses(
+        (status = 200, description = "Your conversations with their last message", body = Vec<ConversationSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_conversations(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let conversations = state
+        .message_service
+        .find_all_conversations(&user.id)
+        .await?;
+    Ok(Json(conversations))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "conversations",
+    params(
+        ("id" = Uuid, Path, description = "Conversation id")
+    ),
+    responses(
+        (status = 200, description = "Conversation found", body = ConversationSchema),
+        (status = 404, description = "Conversation not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_conversation(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let conversation = state
+        .message_service
+        .find_one_conversation(&id, &user.id)
+        .await?;
+    Ok(Json(conversation))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/messages",
+    tag = "conversations",
+    params(
+        ("id" = Uuid, Path, description = "Conversation id"),
+        MessagesQuery
+    ),
+    responses(
+        (status = 200, description = "Messages, newest first", body = Vec<MessageSchema>),
+        (status = 404, description = "Conversation not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_messages(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<MessagesQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let messages = state
+        .message_service
+        .find_messages(&id, &user.id, query)
+        .await?;
+    Ok(Json(messages))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/messages",
+    tag = "conversations",
+    request_body = SendMessageSchema,
+    params(
+        ("id" = Uuid, Path, description = "Conversation id")
+    ),
+    responses(
+        (status = 201, description = "Message sent", body = MessageSchema),
+        (status = 403, description = "A member only accepts messages from followers"),
+        (status = 404, description = "Conversation not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn send_message(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<SendMessageSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let message = state.message_service.send_message(&id, data, &user).await?;
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/read",
+    tag = "conversations",
+    params(
+        ("id" = Uuid, Path, description = "Conversation id")
+    ),
+    responses(
+        (status = 200, description = "Conversation marked as read"),
+        (status = 404, description = "Conversation not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn mark_conversation_read(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.message_service.mark_read(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
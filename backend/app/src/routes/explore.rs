@@ -0,0 +1,67 @@
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+
+use crate::routes::auth_middleware;
+use crate::schemas::explore::{TrendingQuery, TrendingTagSchema};
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_trending_tags, get_explore_posts),
+    components(schemas(TrendingTagSchema)),
+    tags(
+        (name = "explore", description = "Explore api")
+    )
+)]
+pub(super) struct ExploreDoc;
+
+pub fn init_explore_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/trending", get(get_trending_tags))
+        .route("/posts", get(get_explore_posts))
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    get,
+    path = "/trending",
+    tag = "explore",
+    params(TrendingQuery),
+    responses(
+        (status = 200, description = "Trending hashtags, hottest first", body = Vec<TrendingTagSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_trending_tags(
+    State(state): State<AppState>,
+    Query(query): Query<TrendingQuery>,
+) -> impl IntoResponse {
+    let tags = state.explore_service.find_trending_tags(query.window).await;
+    Json(tags)
+}
+
+#[utoipa::path(
+    get,
+    path = "/posts",
+    tag = "explore",
+    responses(
+        (status = 200, description = "Popular posts from people you don't follow", body = Vec<PostSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_explore_posts(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state.explore_service.find_explore_posts(&user.id).await?;
+    Ok(Json(posts))
+}
@@ -0,0 +1,70 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::schemas::health::{HealthSchema, ReadinessSchema, VersionSchema};
+use crate::state::AppState;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(healthz, readyz, version),
+    components(schemas(HealthSchema, ReadinessSchema, VersionSchema)),
+    tags(
+        (name = "health", description = "Probes for orchestrators and monitoring")
+    )
+)]
+pub(super) struct HealthDoc;
+
+pub fn init_health_router() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is running", body = HealthSchema)
+    )
+)]
+pub async fn healthz() -> impl IntoResponse {
+    Json(HealthSchema {
+        status: "ok".to_string(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The instance can serve requests", body = ReadinessSchema),
+        (status = 503, description = "The database is unreachable or not fully migrated, or the server is shutting down", body = ReadinessSchema)
+    )
+)]
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let readiness = state.health_service.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    tag = "health",
+    responses(
+        (status = 200, description = "Version and commit of the running build", body = VersionSchema)
+    )
+)]
+pub async fn version(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.health_service.version())
+}
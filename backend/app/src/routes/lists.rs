@@ -0,0 +1,387 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::routes::auth_middleware;
+use crate::schemas::list::{
+    AddListMemberSchema, CreateListSchema, ListSchema, ListTimelineQuery, UpdateListSchema,
+};
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::{AppError, AuthError};
+use crate::utils::validator::ValidatedJson;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        create_list,
+        get_my_lists,
+        get_subscriptions,
+        get_lists_by_username,
+        get_list,
+        update_list,
+        delete_list,
+        get_members,
+        add_member,
+        remove_member,
+        get_timeline,
+        subscribe,
+        unsubscribe,
+    ),
+    components(schemas(
+        ListSchema,
+        CreateListSchema,
+        UpdateListSchema,
+        AddListMemberSchema,
+    )),
+    tags(
+        (name = "lists", description = "User lists api")
+    )
+)]
+pub(super) struct ListsDoc;
+
+pub fn init_lists_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/", post(create_list).get(get_my_lists))
+        .route("/subscriptions", get(get_subscriptions))
+        .route("/@:username", get(get_lists_by_username))
+        .route("/:id", get(get_list).patch(update_list).delete(delete_list))
+        .route("/:id/members", get(get_members).post(add_member))
+        .route("/:id/members/:user_id", delete(remove_member))
+        .route("/:id/timeline", get(get_timeline))
+        .route("/:id/subscription", post(subscribe).delete(unsubscribe))
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "lists",
+    request_body = CreateListSchema,
+    responses(
+        (status = 201, description = "List created", body = ListSchema)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn create_list(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    ValidatedJson(data): ValidatedJson<CreateListSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let list = state.list_service.create_list(&user.id, data).await?;
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "lists",
+    responses(
+        (status = 200, description = "Your lists, newest first", body = Vec<ListSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_my_lists(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let lists = state
+        .list_service
+        .find_user_lists(&user.id, &user.id)
+        .await?;
+    Ok(Json(lists))
+}
+
+#[utoipa::path(
+    get,
+    path = "/subscriptions",
+    tag = "lists",
+    responses(
+        (status = 200, description = "Public lists you subscribed to", body = Vec<ListSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_subscriptions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let lists = state.list_service.find_subscriptions(&user.id).await?;
+    Ok(Json(lists))
+}
+
+#[utoipa::path(
+    get,
+    path = "/@{username}",
+    tag = "lists",
+    params(
+        ("username" = String, Path, description = "Username from database")
+    ),
+    responses(
+        (status = 200, description = "User's public lists", body = Vec<ListSchema>),
+        (status = 404, description = "User not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_lists_by_username(
+    State(state): State<AppState>,
+    Extension(me): Extension<UserSchema>,
+    Path(username): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = state
+        .user_service
+        .repository
+        .find_one_by_username(&username)
+        .await?
+        .ok_or(AuthError::UserNotFound)?;
+    let lists = state.list_service.find_user_lists(&user.id, &me.id).await?;
+    Ok(Json(lists))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    responses(
+        (status = 200, description = "List found", body = ListSchema),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_list(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let list = state.list_service.find_list(&id, &user.id).await?;
+    Ok(Json(list))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    request_body = UpdateListSchema,
+    responses(
+        (status = 200, description = "List updated", body = ListSchema),
+        (status = 403, description = "Doesn't have permission to do this"),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn update_list(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<UpdateListSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let list = state.list_service.update_list(&id, &user.id, data).await?;
+    Ok(Json(list))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    responses(
+        (status = 200, description = "List deleted"),
+        (status = 403, description = "Doesn't have permission to do this"),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn delete_list(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.list_service.delete_list(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/members",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    responses(
+        (status = 200, description = "List members in the order they were added", body = Vec<UserSchema>),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_members(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let members = state.list_service.find_members(&id, &user.id).await?;
+    Ok(Json(members))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/members",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    request_body = AddListMemberSchema,
+    responses(
+        (status = 200, description = "User added to the list"),
+        (status = 403, description = "Doesn't have permission to do this"),
+        (status = 404, description = "List or user not found"),
+        (status = 409, description = "User is already a member, or the list is full")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn add_member(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<AddListMemberSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    state.list_service.add_member(&id, &user.id, data).await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/members/{user_id}",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id"),
+        ("user_id" = Uuid, Path, description = "Member to remove")
+    ),
+    responses(
+        (status = 200, description = "User removed from the list"),
+        (status = 400, description = "User isn't a member"),
+        (status = 403, description = "Doesn't have permission to do this"),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn remove_member(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .list_service
+        .remove_member(&id, &user.id, &member_id)
+        .await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/timeline",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id"),
+        ListTimelineQuery
+    ),
+    responses(
+        (status = 200, description = "Posts of the list members, newest first", body = Vec<PostSchema>),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_timeline(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListTimelineQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state
+        .list_service
+        .find_timeline(&id, &user.id, query)
+        .await?;
+    Ok(Json(posts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/subscription",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    responses(
+        (status = 200, description = "Subscribed to the list"),
+        (status = 400, description = "Already subscribed, or it's your own list"),
+        (status = 404, description = "List not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn subscribe(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.list_service.subscribe(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/subscription",
+    tag = "lists",
+    params(
+        ("id" = Uuid, Path, description = "List id")
+    ),
+    responses(
+        (status = 200, description = "Unsubscribed from the list"),
+        (status = 400, description = "Not subscribed")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.list_service.unsubscribe(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
@@ -0,0 +1,188 @@
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Multipart, Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use uuid::Uuid;
+
+use crate::routes::{auth_middleware, optional_auth_middleware};
+use crate::schemas::media::{MediaSchema, MediaVariantsSchema, UploadMediaSchema};
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+use crate::utils::images::ImageVariant;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(upload_media, get_media, get_media_variant),
+    components(schemas(MediaSchema, MediaVariantsSchema, UploadMediaSchema)),
+    tags(
+        (name = "media", description = "Media api")
+    )
+)]
+pub(super) struct MediaDoc;
+
+pub fn init_media_router(state: AppState) -> Router<AppState> {
+    // Leave room for the multipart boundaries around the file
+    let body_limit = DefaultBodyLimit::max(state.config.media_max_bytes + 64 * 1024);
+    let auth_middleware = axum::middleware::from_fn_with_state(state.clone(), auth_middleware);
+    let optional_auth_middleware =
+        axum::middleware::from_fn_with_state(state, optional_auth_middleware);
+    Router::new()
+        .route(
+            "/",
+            post(upload_media).layer(body_limit).layer(auth_middleware),
+        )
+        .route(
+            "/:id",
+            get(get_media).layer(optional_auth_middleware.clone()),
+        )
+        .route(
+            "/:id/:variant",
+            get(get_media_variant).layer(optional_auth_middleware),
+        )
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "media",
+    request_body(content = UploadMediaSchema, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "File uploaded, reference its id in `media_ids` when creating a post", body = MediaSchema),
+        (status = 413, description = "File is too large"),
+        (status = 415, description = "Unsupported file type")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn upload_media(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let data = read_file(&mut multipart).await?;
+    let media = state.media_service.upload(&user.id, &data).await?;
+    tracing::info!("Uploaded media with id `{}`", media.id);
+    Ok((StatusCode::CREATED, Json(media)))
+}
+
+/// Reads the `file` field of a multipart upload.
+pub(super) async fn read_file(multipart: &mut Multipart) -> Result<Bytes, AppError> {
+    while let Some(field) = multipart.next_field().await? {
+        if field.name() == Some("file") {
+            return Ok(field.bytes().await?);
+        }
+    }
+    Err(AppError::MissingFile)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "media",
+    params(
+        ("id" = Uuid, Path, description = "Media id")
+    ),
+    responses(
+        (status = 200, description = "File content"),
+        (status = 304, description = "Not modified"),
+        (status = 404, description = "Media not found, or attached to a post the viewer can't see")
+    ),
+    security(
+        (),
+        ("http" = [])
+    )
+)]
+pub async fn get_media(
+    State(state): State<AppState>,
+    user: Option<Extension<UserSchema>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let viewer_id = user.map(|Extension(user)| user.id);
+    serve_media(state, id, ImageVariant::Original, viewer_id, headers).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/{variant}",
+    tag = "media",
+    params(
+        ("id" = Uuid, Path, description = "Media id"),
+        ("variant" = String, Path, description = "`original`, `small` or `thumbnail`")
+    ),
+    responses(
+        (status = 200, description = "File content"),
+        (status = 304, description = "Not modified"),
+        (status = 404, description = "Media or variant not found, or attached to a post the viewer can't see")
+    ),
+    security(
+        (),
+        ("http" = [])
+    )
+)]
+pub async fn get_media_variant(
+    State(state): State<AppState>,
+    user: Option<Extension<UserSchema>>,
+    Path((id, variant)): Path<(Uuid, String)>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let variant = ImageVariant::parse(&variant).ok_or(AppError::EntityNotFound {
+        entity: "Media",
+        id,
+    })?;
+    let viewer_id = user.map(|Extension(user)| user.id);
+    serve_media(state, id, variant, viewer_id, headers).await
+}
+
+async fn serve_media(
+    state: AppState,
+    id: Uuid,
+    variant: ImageVariant,
+    viewer_id: Option<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    // Visibility is checked before the validator, so a 304 doesn't reveal
+    // media the viewer can't see
+    let media = state
+        .media_service
+        .find_visible(&id, viewer_id.as_ref())
+        .await?;
+    // Files never change once uploaded, so the id is a strong validator
+    let etag = format!("\"{}-{}\"", id, variant.name());
+    // Caches revalidate every time, so media stops being served once it is
+    // deleted or its post is hidden. What a signed in viewer sees may be a
+    // draft, so shared caches must not keep it
+    let cache_control = match viewer_id {
+        None => "public, no-cache",
+        Some(_) => "private, no-cache",
+    };
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|value| value.as_bytes() == etag.as_bytes())
+    {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, cache_control.to_string()),
+            ],
+        )
+            .into_response());
+    }
+
+    let data = state.media_service.download(&media, variant).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, media.mime),
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, cache_control.to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
@@ -1,37 +1,65 @@
+mod admin;
 mod auth;
+mod bookmarks;
+mod conversations;
+mod explore;
+mod health;
+mod lists;
+mod media;
 mod posts;
+mod search;
+mod tags;
 mod users;
+mod webhooks;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Semaphore};
 
 use axum::body::Body;
 use axum::extract::{Request, State};
-use axum::http::{HeaderValue, Response, StatusCode};
+use axum::http::{Response, StatusCode};
 use axum::middleware::Next;
 use axum::response::IntoResponse;
 use axum::{response::Json, routing::get, Router};
 
 use tower::ServiceBuilder;
-use tower_http::cors::Any;
+use tower_http::cors::{AllowOrigin, Any};
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
 use serde_json::json;
+use sqlx::PgPool;
 use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa::{Modify, OpenApi};
 use utoipa_scalar::{Scalar, Servable};
 use utoipa_swagger_ui::SwaggerUi;
 
+use crate::config::ConfigError;
 use crate::db::db_connection;
+use crate::repositories::Repository;
 use crate::state::AppState;
-use crate::utils::errors::{APIError, AppError, AuthError};
-use crate::{repositories, services, utils, Config};
+use crate::utils::errors::{APIError, AppError, AuthError, StartupError};
+use crate::{repositories, services, storage, utils, Config};
 
 use crate::schemas::user::UserSchema;
-use crate::utils::auth::decode_token;
+use crate::utils::auth::Keys;
+use crate::utils::clock::SystemClock;
 
+use admin::AdminDoc;
 use auth::AuthDoc;
+use bookmarks::BookmarksDoc;
+use conversations::ConversationsDoc;
+use explore::ExploreDoc;
+use health::HealthDoc;
+use lists::ListsDoc;
+use media::MediaDoc;
 use posts::PostsDoc;
+use search::SearchDoc;
+use tags::TagsDoc;
 use users::UsersDoc;
+use webhooks::WebhooksDoc;
 
-pub async fn init_routers(settings: &Config) -> Router {
+pub fn init_routers(state: AppState) -> Router {
     #[derive(OpenApi)]
     #[openapi(
         modifiers(&SecurityAddon),
@@ -39,6 +67,15 @@ pub async fn init_routers(settings: &Config) -> Router {
             (path = "/users", api = UsersDoc),
             (path = "/auth", api = AuthDoc),
             (path = "/posts", api = PostsDoc),
+            (path = "/conversations", api = ConversationsDoc),
+            (path = "/tags", api = TagsDoc),
+            (path = "/explore", api = ExploreDoc),
+            (path = "/search", api = SearchDoc),
+            (path = "/media", api = MediaDoc),
+            (path = "/bookmarks", api = BookmarksDoc),
+            (path = "/lists", api = ListsDoc),
+            (path = "/admin", api = AdminDoc),
+            (path = "/webhooks", api = WebhooksDoc),
         ),
         components(schemas(
             utils::errors::APIError
@@ -64,45 +101,43 @@ pub async fn init_routers(settings: &Config) -> Router {
         }
     }
 
-    let pool = db_connection(settings).await.unwrap();
-
-    let user_repository = repositories::user::UserRepository { pool: pool.clone() };
-    let post_repository = repositories::post::PostRepository { pool: pool.clone() };
-    let following_repository = repositories::following::FollowingRepository { pool: pool.clone() };
-
-    let user_service = services::user::UserService {
-        repository: user_repository,
-    };
-    let post_service = services::post::PostService {
-        repository: post_repository,
-    };
-
-    let following_service = services::following::FollowingService {
-        repository: following_repository,
-    };
-
-    let state = AppState {
-        user_service,
-        post_service,
-        following_service,
-        config: settings.clone(),
+    // Origins are checked when the config is loaded
+    let origins = state.config.allowed_origins();
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().map(|origin| origin.parse().unwrap()))
     };
 
-    let origins: [HeaderValue; 2] = [
-        settings.frontend_origin.parse().unwrap(),
-        "http://localhost:3000".parse().unwrap(),
-    ];
+    // The health endpoints sit at the root, where `nest` can't put them
+    let openapi = ApiDoc::openapi().merge_from(HealthDoc::openapi());
 
     Router::new()
-        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
-        .merge(Scalar::with_url("/scalar", ApiDoc::openapi()))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi.clone()))
+        .merge(Scalar::with_url("/scalar", openapi))
         .route(
             "/",
             get(|| async { Json(json!({"message": "Hello world"})) }),
         )
+        .merge(health::init_health_router())
         .nest("/auth", auth::init_auth_router(state.clone()))
         .nest("/posts", posts::init_posts_router(state.clone()))
         .nest("/users", users::init_users_router(state.clone()))
+        .nest(
+            "/conversations",
+            conversations::init_conversations_router(state.clone()),
+        )
+        .nest("/tags", tags::init_tags_router(state.clone()))
+        .nest("/explore", explore::init_explore_router(state.clone()))
+        .nest("/search", search::init_search_router(state.clone()))
+        .nest("/media", media::init_media_router(state.clone()))
+        .nest(
+            "/bookmarks",
+            bookmarks::init_bookmarks_router(state.clone()),
+        )
+        .nest("/lists", lists::init_lists_router(state.clone()))
+        .nest("/admin", admin::init_admin_router(state.clone()))
+        .nest("/webhooks", webhooks::init_webhooks_router(state.clone()))
         .fallback(handler_404)
         .layer(
             ServiceBuilder::new()
@@ -111,12 +146,258 @@ pub async fn init_routers(settings: &Config) -> Router {
                     CorsLayer::new()
                         .allow_headers(Any)
                         .allow_methods(Any)
-                        .allow_origin(origins),
+                        .allow_origin(allow_origin),
                 ),
         )
         .with_state(state)
 }
 
+/// Services the API shares with the admin CLI
+struct CoreServices {
+    user_service: services::user::UserService,
+    media_service: services::media::MediaService,
+    webhook_service: services::webhook::WebhookService,
+    post_service: services::post::PostService,
+    data_export_service: services::data_export::DataExportService,
+}
+
+fn init_core_services(settings: &Config, pool: &PgPool) -> Result<CoreServices, StartupError> {
+    let user_repository = repositories::user::UserRepository { pool: pool.clone() };
+    let post_repository = repositories::post::PostRepository { pool: pool.clone() };
+    let following_repository = repositories::following::FollowingRepository { pool: pool.clone() };
+    let job_repository = repositories::job::JobRepository { pool: pool.clone() };
+    let data_export_repository =
+        repositories::data_export::DataExportRepository { pool: pool.clone() };
+
+    let media_service = services::media::MediaService {
+        repository: repositories::media::MediaRepository { pool: pool.clone() },
+        store: storage::media_store(settings)?,
+        max_size: settings.media_max_bytes,
+        image_workers: Arc::new(Semaphore::new(settings.image_workers.max(1))),
+    };
+
+    let user_service = services::user::UserService {
+        repository: user_repository.clone(),
+        following_repository: following_repository.clone(),
+        post_repository: post_repository.clone(),
+        message_repository: repositories::message::MessageRepository { pool: pool.clone() },
+        conversation_repository: repositories::conversation::ConversationRepository {
+            pool: pool.clone(),
+        },
+        data_export_repository: data_export_repository.clone(),
+        import_repository: repositories::import::ImportRepository { pool: pool.clone() },
+        media_service: media_service.clone(),
+        deletion_grace: chrono::Duration::days(settings.account_deletion_grace_days),
+        deleted_content: settings
+            .deleted_account_content
+            .parse()
+            .map_err(|e| ConfigError::Invalid(vec![e]))?,
+        keys: Keys::new(settings.jwt_secret.as_bytes()),
+        token_lifetime: chrono::Duration::minutes(settings.access_token_minutes),
+    };
+    let webhook_service = services::webhook::WebhookService {
+        repository: repositories::webhook::WebhookRepository { pool: pool.clone() },
+        delivery_repository: repositories::webhook_delivery::WebhookDeliveryRepository {
+            pool: pool.clone(),
+        },
+        user_repository: user_repository.clone(),
+        job_repository: job_repository.clone(),
+        sender: utils::webhook::WebhookSender {
+            user_agent: format!("twotty/{} (webhook)", env!("CARGO_PKG_VERSION")),
+            timeout: Duration::from_secs(settings.webhook_timeout_seconds),
+            allow_private_networks: settings.webhook_allow_private,
+        },
+        max_attempts: settings.job_max_attempts.max(1),
+        max_failures: settings.webhook_max_failures.max(1),
+    };
+    let link_preview_service = services::link_preview::LinkPreviewService {
+        repository: repositories::link_preview::LinkPreviewRepository { pool: pool.clone() },
+        fetcher: utils::unfurl::LinkFetcher {
+            user_agent: settings.link_preview_user_agent.clone(),
+            timeout: Duration::from_secs(settings.link_preview_timeout_seconds),
+            max_bytes: settings.link_preview_max_bytes,
+            allow_private_networks: settings.link_preview_allow_private,
+        },
+        job_repository: job_repository.clone(),
+    };
+
+    let post_service = services::post::PostService {
+        repository: post_repository.clone(),
+        user_repository: user_repository.clone(),
+        media_service: media_service.clone(),
+        poll_repository: repositories::poll::PollRepository { pool: pool.clone() },
+        link_preview_service,
+        webhook_service: webhook_service.clone(),
+        edit_window: chrono::Duration::minutes(settings.post_edit_window_minutes),
+        trash_retention: chrono::Duration::days(settings.post_trash_days),
+        max_pinned: settings.max_pinned_posts,
+        max_length: settings.max_post_length,
+    };
+
+    let data_export_service = services::data_export::DataExportService {
+        repository: data_export_repository,
+        user_repository,
+        post_repository,
+        following_repository,
+        bookmark_repository: repositories::bookmark::BookmarkRepository { pool: pool.clone() },
+        bookmark_folder_repository: repositories::bookmark_folder::BookmarkFolderRepository {
+            pool: pool.clone(),
+        },
+        job_repository,
+        media_service: media_service.clone(),
+        expiry: chrono::Duration::hours(settings.data_export_expiry_hours),
+    };
+
+    Ok(CoreServices {
+        user_service,
+        media_service,
+        webhook_service,
+        post_service,
+        data_export_service,
+    })
+}
+
+/// Connects to the database and builds only the admin service, for the
+/// admin CLI. Nothing runs in the background.
+pub async fn init_admin_service(
+    settings: &Config,
+) -> Result<services::admin::AdminService, StartupError> {
+    let pool = db_connection(settings).await?;
+    let core = init_core_services(settings, &pool)?;
+    Ok(services::admin::AdminService {
+        user_service: core.user_service,
+        post_service: core.post_service,
+        data_export_service: core.data_export_service,
+        webhook_service: core.webhook_service,
+        stats_repository: repositories::stats::StatsRepository { pool },
+    })
+}
+
+/// Builds the services on `pool`. Nothing runs in the background until the
+/// job workers and the trending refresh are spawned. Readiness turns off
+/// once `shutdown` turns `true`.
+pub fn init_state(
+    settings: &Config,
+    pool: PgPool,
+    shutdown: watch::Receiver<bool>,
+) -> Result<AppState, StartupError> {
+    let CoreServices {
+        user_service,
+        media_service,
+        webhook_service,
+        post_service,
+        data_export_service,
+    } = init_core_services(settings, &pool)?;
+
+    let user_repository = repositories::user::UserRepository { pool: pool.clone() };
+    let post_repository = repositories::post::PostRepository { pool: pool.clone() };
+    let following_repository = repositories::following::FollowingRepository { pool: pool.clone() };
+    let conversation_repository =
+        repositories::conversation::ConversationRepository { pool: pool.clone() };
+    let message_repository = repositories::message::MessageRepository { pool: pool.clone() };
+    let bookmark_repository = repositories::bookmark::BookmarkRepository { pool: pool.clone() };
+    let bookmark_folder_repository =
+        repositories::bookmark_folder::BookmarkFolderRepository { pool: pool.clone() };
+    let list_repository = repositories::list::ListRepository { pool: pool.clone() };
+    let import_repository = repositories::import::ImportRepository { pool: pool.clone() };
+    let job_repository = repositories::job::JobRepository { pool: pool.clone() };
+    let stats_repository = repositories::stats::StatsRepository { pool: pool.clone() };
+    let health_repository = repositories::health::HealthRepository { pool: pool.clone() };
+
+    let following_service = services::following::FollowingService {
+        repository: following_repository.clone(),
+        webhook_service: webhook_service.clone(),
+    };
+
+    let message_service = services::message::MessageService {
+        repository: message_repository,
+        conversation_repository,
+        following_repository: following_repository.clone(),
+        user_repository: user_repository.clone(),
+    };
+
+    let search_service = services::search::SearchService {
+        post_repository: post_repository.clone(),
+        user_repository: user_repository.clone(),
+        post_service: post_service.clone(),
+    };
+
+    let import_service = services::import::ImportService {
+        repository: import_repository,
+        user_repository: user_repository.clone(),
+        following_repository: following_repository.clone(),
+        job_repository: job_repository.clone(),
+        post_service: post_service.clone(),
+        media_service: media_service.clone(),
+    };
+
+    let job_service = services::job::JobService {
+        repository: job_repository,
+        post_service: post_service.clone(),
+        user_service: user_service.clone(),
+        link_preview_service: post_service.link_preview_service.clone(),
+        data_export_service: data_export_service.clone(),
+        import_service: import_service.clone(),
+        webhook_service: webhook_service.clone(),
+        max_attempts: settings.job_max_attempts.max(1),
+        retry_backoff: chrono::Duration::seconds(settings.job_retry_backoff_seconds),
+        scheduler_interval: Duration::from_secs(settings.scheduler_interval_seconds),
+        running: Default::default(),
+    };
+
+    let admin_service = services::admin::AdminService {
+        user_service: user_service.clone(),
+        post_service: post_service.clone(),
+        data_export_service: data_export_service.clone(),
+        webhook_service: webhook_service.clone(),
+        stats_repository,
+    };
+
+    let health_service = services::health::HealthService {
+        repository: health_repository,
+        shutdown,
+    };
+
+    let bookmark_service = services::bookmark::BookmarkService {
+        repository: bookmark_repository,
+        folder_repository: bookmark_folder_repository,
+        post_repository: post_repository.clone(),
+        post_service: post_service.clone(),
+    };
+
+    let list_service = services::list::ListService {
+        repository: list_repository,
+        user_repository: user_repository.clone(),
+        post_service: post_service.clone(),
+    };
+
+    let explore_service = services::explore::ExploreService {
+        repository: post_repository,
+        post_service: post_service.clone(),
+        clock: Arc::new(SystemClock),
+        trending: Default::default(),
+    };
+
+    Ok(AppState {
+        user_service,
+        post_service,
+        following_service,
+        message_service,
+        explore_service,
+        search_service,
+        media_service,
+        bookmark_service,
+        list_service,
+        data_export_service,
+        import_service,
+        job_service,
+        webhook_service,
+        admin_service,
+        health_service,
+        config: settings.clone(),
+    })
+}
+
 async fn handler_404() -> impl IntoResponse {
     (
         StatusCode::NOT_FOUND,
@@ -138,17 +419,58 @@ pub async fn auth_middleware(
     tracing::info!("{:?}", header);
     let (_token_type, token) = (header.next(), header.next().ok_or(AuthError::InvalidToken)?);
 
-    let token_data = decode_token(token).map_err(|_| AuthError::InvalidToken)?;
+    let token_data = state
+        .user_service
+        .keys
+        .decode_token(token)
+        .map_err(|_| AuthError::InvalidToken)?;
     request.extensions_mut().insert(token_data.claims.clone());
 
-    let user: UserSchema = state
+    let user = state
         .user_service
         .repository
         .find_one_by_username(&token_data.claims.sub)
-        .await
-        .ok_or(AuthError::InvalidToken)?
-        .into();
-    request.extensions_mut().insert(user);
+        .await?
+        .filter(|user| user.accepts_token(token_data.claims.iat))
+        .ok_or(AuthError::InvalidToken)?;
+    request.extensions_mut().insert(UserSchema::from(user));
+
+    Ok(next.run(request).await)
+}
 
+/// Like [`auth_middleware`], but lets requests without a token through as anonymous.
+pub async fn optional_auth_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response<Body>, AppError> {
+    if !request
+        .headers()
+        .contains_key(axum::http::header::AUTHORIZATION)
+    {
+        return Ok(next.run(request).await);
+    }
+    auth_middleware(State(state), request, next).await
+}
+
+/// Lets only admins through. Goes after [`auth_middleware`], which finds the user.
+pub async fn admin_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response<Body>, AppError> {
+    let user = request
+        .extensions()
+        .get::<UserSchema>()
+        .ok_or(AuthError::InvalidToken)?;
+    let is_admin = state
+        .user_service
+        .repository
+        .find_one(&user.id)
+        .await?
+        .is_some_and(|user| user.is_admin);
+    if !is_admin {
+        return Err(AppError::CantDoThis);
+    }
     Ok(next.run(request).await)
 }
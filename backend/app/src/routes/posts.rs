@@ -7,9 +7,14 @@ use serde_json::json;
 use uuid::Uuid;
 
 use crate::routes::auth_middleware;
-use crate::schemas::post::{CreatePostSchema, PostSchema, UpdatePostSchema};
+use crate::schemas::poll::{CreatePollSchema, PollOptionSchema, PollSchema, VoteSchema};
+use crate::schemas::post::{
+    CreatePostSchema, EntitySchema, LinkCardSchema, PostRevisionSchema, PostSchema,
+    UpdatePostSchema,
+};
 use crate::schemas::user::UserSchema;
 use crate::state::AppState;
+use crate::utils::entities::EntityKind;
 use crate::utils::errors::{AppError, AuthError};
 use crate::utils::validator::ValidatedJson;
 
@@ -21,12 +26,31 @@ use crate::utils::validator::ValidatedJson;
         get_all_posts,
         get_posts_by_username,
         delete_post,
-        update_post
+        update_post,
+        get_post_revisions,
+        get_trash,
+        restore_post,
+        get_scheduled,
+        get_drafts,
+        publish_post,
+        vote_in_poll,
+        bookmark_post,
+        unbookmark_post,
+        pin_post,
+        unpin_post,
     ),
     components(schemas(
         PostSchema,
+        EntitySchema,
+        EntityKind,
+        LinkCardSchema,
+        PostRevisionSchema,
         UpdatePostSchema,
         CreatePostSchema,
+        CreatePollSchema,
+        PollSchema,
+        PollOptionSchema,
+        VoteSchema,
     )),
     tags(
         (name = "posts", description = "Posts api")
@@ -38,7 +62,16 @@ pub fn init_posts_router(state: AppState) -> Router<AppState> {
     let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
     Router::new()
         .route("/", post(create_post).get(get_all_posts))
+        .route("/trash", get(get_trash))
+        .route("/scheduled", get(get_scheduled))
+        .route("/drafts", get(get_drafts))
         .route("/:id", get(get_post).delete(delete_post).patch(update_post))
+        .route("/:id/revisions", get(get_post_revisions))
+        .route("/:id/restore", post(restore_post))
+        .route("/:id/publish", post(publish_post))
+        .route("/:id/poll/vote", post(vote_in_poll))
+        .route("/:id/pin", post(pin_post).delete(unpin_post))
+        .route("/:id/bookmark", post(bookmark_post).delete(unbookmark_post))
         .route("/@:username", get(get_posts_by_username))
         .layer(auth_middleware)
 }
@@ -82,9 +115,10 @@ pub async fn create_post(
 )]
 pub async fn get_post(
     State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
-    let post = state.post_service.find_one_post(&id).await?;
+    let post = state.post_service.find_one_post(&id, &user.id).await?;
     Ok(Json(post))
 }
 
@@ -102,9 +136,12 @@ pub async fn get_post(
 pub async fn get_all_posts(
     State(state): State<AppState>,
     Extension(user): Extension<UserSchema>,
-) -> impl IntoResponse {
-    let posts = state.post_service.find_all_posts(&user.id).await;
-    Json(posts)
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state
+        .post_service
+        .find_all_posts(&user.id, &user.id)
+        .await?;
+    Ok(Json(posts))
 }
 
 #[utoipa::path(
@@ -123,18 +160,19 @@ pub async fn get_all_posts(
 )]
 pub async fn get_posts_by_username(
     State(state): State<AppState>,
+    Extension(me): Extension<UserSchema>,
     Path(username): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
     let user = state
         .user_service
         .repository
         .find_one_by_username(&username)
-        .await;
+        .await?;
     if user.is_none() {
         return Err(AuthError::UserNotFound.into());
     }
     let user = user.unwrap();
-    let posts = state.post_service.find_all_posts(&user.id).await;
+    let posts = state.post_service.find_all_posts(&user.id, &me.id).await?;
     Ok(Json(posts))
 }
 
@@ -174,7 +212,7 @@ pub async fn delete_post(
     responses(
         (status = 200, description = "Post updated"),
         (status = 404, description = "Post not found"),
-        (status = 403, description = "Doesn't have permission to do this")
+        (status = 403, description = "Doesn't have permission to do this, or the edit window has passed")
     ),
     security(
         ("http" = [])
@@ -189,3 +227,263 @@ pub async fn update_post(
     state.post_service.update_post(&id, data, &user.id).await?;
     Ok(Json(json!({"ok": true})))
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/revisions",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Earlier versions of the post, newest first", body = Vec<PostRevisionSchema>),
+        (status = 404, description = "Post not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_post_revisions(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let revisions = state.post_service.find_revisions(&id, &user.id).await?;
+    Ok(Json(revisions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/scheduled",
+    tag = "posts",
+    responses(
+        (status = 200, description = "Your scheduled posts, soonest first", body = Vec<PostSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_scheduled(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state.post_service.find_scheduled(&user.id).await?;
+    Ok(Json(posts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/drafts",
+    tag = "posts",
+    responses(
+        (status = 200, description = "Your drafts, newest first", body = Vec<PostSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_drafts(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state.post_service.find_drafts(&user.id).await?;
+    Ok(Json(posts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/publish",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Draft or scheduled post published now", body = PostSchema),
+        (status = 404, description = "Post not found"),
+        (status = 409, description = "Post is already published")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn publish_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let post = state.post_service.publish_post(&id, &user.id).await?;
+    Ok(Json(post))
+}
+
+#[utoipa::path(
+    get,
+    path = "/trash",
+    tag = "posts",
+    responses(
+        (status = 200, description = "Your deleted posts that can still be restored", body = Vec<PostSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_trash(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state.post_service.find_trash(&user.id).await?;
+    Ok(Json(posts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Post restored from the trash", body = PostSchema),
+        (status = 404, description = "Post not found in the trash"),
+        (status = 403, description = "Doesn't have permission to do this")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn restore_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let post = state.post_service.restore_post(&id, &user.id).await?;
+    Ok(Json(post))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/poll/vote",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    request_body = VoteSchema,
+    responses(
+        (status = 200, description = "Vote counted, the results are now visible", body = PollSchema),
+        (status = 400, description = "Invalid options"),
+        (status = 403, description = "Poll is closed"),
+        (status = 404, description = "Post or poll not found"),
+        (status = 409, description = "Already voted")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn vote_in_poll(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<VoteSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let poll = state.post_service.vote(&id, &user.id, data).await?;
+    Ok(Json(poll))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/bookmark",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Post bookmarked, or was already", body = PostSchema),
+        (status = 404, description = "Post not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn bookmark_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let post = state.bookmark_service.bookmark(&id, &user.id).await?;
+    Ok(Json(post))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/bookmark",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Bookmark removed"),
+        (status = 404, description = "Post isn't bookmarked")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn unbookmark_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.bookmark_service.unbookmark(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/pin",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Post pinned to your profile, or was already", body = PostSchema),
+        (status = 400, description = "Post isn't published"),
+        (status = 403, description = "Doesn't have permission to do this"),
+        (status = 404, description = "Post not found"),
+        (status = 409, description = "Too many pinned posts")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn pin_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let post = state.post_service.pin_post(&id, &user.id).await?;
+    Ok(Json(post))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/pin",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id from database"),
+    ),
+    responses(
+        (status = 200, description = "Post unpinned"),
+        (status = 403, description = "Doesn't have permission to do this"),
+        (status = 404, description = "Post not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn unpin_post(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.post_service.unpin_post(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
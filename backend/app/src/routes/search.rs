@@ -0,0 +1,49 @@
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+
+use crate::routes::auth_middleware;
+use crate::schemas::search::{
+    PostSearchHitSchema, SearchQuery, SearchResultsSchema, UserSearchHitSchema,
+};
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(search),
+    components(schemas(SearchResultsSchema, PostSearchHitSchema, UserSearchHitSchema)),
+    tags(
+        (name = "search", description = "Search api")
+    )
+)]
+pub(super) struct SearchDoc;
+
+pub fn init_search_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new().route("/", get(search)).layer(auth_middleware)
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Search results, best matches first", body = SearchResultsSchema),
+        (status = 400, description = "Empty query")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn search(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Query(query): Query<SearchQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let results = state.search_service.search(query, &user.id).await?;
+    Ok(Json(results))
+}
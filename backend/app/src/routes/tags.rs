@@ -0,0 +1,48 @@
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+
+use crate::routes::auth_middleware;
+use crate::schemas::user::UserSchema;
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_posts_by_tag),
+    tags(
+        (name = "tags", description = "Hashtags api")
+    )
+)]
+pub(super) struct TagsDoc;
+
+pub fn init_tags_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/:tag", get(get_posts_by_tag))
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{tag}",
+    tag = "tags",
+    params(
+        ("tag" = String, Path, description = "Hashtag without the leading `#`, case-insensitive")
+    ),
+    responses(
+        (status = 200, description = "Posts with this hashtag, newest first", body = Vec<PostSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_posts_by_tag(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(tag): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let posts = state.post_service.find_posts_by_tag(&tag, &user.id).await?;
+    Ok(Json(posts))
+}
@@ -73,9 +73,9 @@ pub(super) fn init_users_router(state: AppState) -> Router<AppState> {
         ("http" = [])
     )
 )]
-async fn get_all_users(State(state): State<AppState>) -> impl IntoResponse {
-    let tasks = state.user_service.find_all_users().await;
-    Json(json!(tasks))
+async fn get_all_users(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let tasks = state.user_service.find_all_users().await?;
+    Ok(Json(json!(tasks)))
 }
 
 #[utoipa::path(
@@ -124,11 +124,11 @@ async fn get_user_by_username(
         .user_service
         .repository
         .find_one_by_username(&username)
-        .await;
+        .await?;
     if let Some(user) = user {
         return Ok(Json(UserSchema::from(user)));
     }
-    return Err(AuthError::UserNotFound.into());
+    Err(AuthError::UserNotFound.into())
 }
 
 #[utoipa::path(
@@ -251,7 +251,7 @@ async fn is_followed(
     Path(id): Path<Uuid>
 ) -> Result<impl IntoResponse, AppError> {
     let to = state.user_service.find_one_user(&id).await?;
-    let following = state.following_service.get_following_by_from_and_to_ids(user, to).await;
+    let following = state.following_service.get_following_by_from_and_to_ids(user, to).await?;
     
     Ok(Json(json!({"isFollowed": following.is_some()})))
 }
\ No newline at end of file
@@ -0,0 +1,233 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::routes::auth_middleware;
+use crate::schemas::user::UserSchema;
+use crate::schemas::webhook::{
+    CreateWebhookSchema, UpdateWebhookSchema, WebhookDeliveriesQuery, WebhookDeliverySchema,
+    WebhookSchema,
+};
+use crate::state::AppState;
+use crate::utils::errors::AppError;
+use crate::utils::validator::ValidatedJson;
+
+/// Deliveries are posted as JSON with the event in `X-Twotty-Event`, the
+/// delivery id in `X-Twotty-Delivery`, the unix time of the attempt in
+/// `X-Twotty-Timestamp` and `sha256=` followed by the hex HMAC-SHA256 of
+/// `{timestamp}.{body}` with the webhook secret in `X-Twotty-Signature`.
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        create_webhook,
+        get_webhooks,
+        get_webhook,
+        update_webhook,
+        delete_webhook,
+        get_deliveries,
+        send_test_event,
+    ),
+    components(schemas(
+        WebhookSchema,
+        CreateWebhookSchema,
+        UpdateWebhookSchema,
+        WebhookDeliverySchema,
+    )),
+    tags(
+        (name = "webhooks", description = "Outbound webhooks api")
+    )
+)]
+pub(super) struct WebhooksDoc;
+
+pub fn init_webhooks_router(state: AppState) -> Router<AppState> {
+    let auth_middleware = axum::middleware::from_fn_with_state(state, auth_middleware);
+    Router::new()
+        .route("/", post(create_webhook).get(get_webhooks))
+        .route(
+            "/:id",
+            get(get_webhook)
+                .patch(update_webhook)
+                .delete(delete_webhook),
+        )
+        .route("/:id/deliveries", get(get_deliveries))
+        .route("/:id/test", post(send_test_event))
+        .layer(auth_middleware)
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "webhooks",
+    request_body = CreateWebhookSchema,
+    responses(
+        (status = 201, description = "Webhook created with its signing secret", body = WebhookSchema),
+        (status = 400, description = "Invalid url or events"),
+        (status = 403, description = "Only admins can receive the events of all users"),
+        (status = 409, description = "Too many webhooks")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    ValidatedJson(data): ValidatedJson<CreateWebhookSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let webhook = state.webhook_service.create_webhook(&user.id, data).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Your webhooks, newest first", body = Vec<WebhookSchema>)
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_webhooks(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let webhooks = state.webhook_service.find_webhooks(&user.id).await?;
+    Ok(Json(webhooks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 200, description = "Webhook found", body = WebhookSchema),
+        (status = 404, description = "Webhook not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let webhook = state.webhook_service.find_webhook(&id, &user.id).await?;
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook id")
+    ),
+    request_body = UpdateWebhookSchema,
+    responses(
+        (status = 200, description = "Webhook updated", body = WebhookSchema),
+        (status = 400, description = "Invalid url or events"),
+        (status = 404, description = "Webhook not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(data): ValidatedJson<UpdateWebhookSchema>,
+) -> Result<impl IntoResponse, AppError> {
+    let webhook = state
+        .webhook_service
+        .update_webhook(&id, &user.id, data)
+        .await?;
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 200, description = "Webhook and its deliveries deleted"),
+        (status = 404, description = "Webhook not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    state.webhook_service.delete_webhook(&id, &user.id).await?;
+    Ok(Json(json!({"ok": true})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook id"),
+        WebhookDeliveriesQuery
+    ),
+    responses(
+        (status = 200, description = "Deliveries of the webhook, newest first", body = Vec<WebhookDeliverySchema>),
+        (status = 404, description = "Webhook not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn get_deliveries(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let deliveries = state
+        .webhook_service
+        .find_deliveries(&id, &user.id, query)
+        .await?;
+    Ok(Json(deliveries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/test",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 200, description = "A `test` event was sent once, the delivery tells how it went", body = WebhookDeliverySchema),
+        (status = 404, description = "Webhook not found")
+    ),
+    security(
+        ("http" = [])
+    )
+)]
+pub async fn send_test_event(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserSchema>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let delivery = state.webhook_service.send_test(&id, &user.id).await?;
+    Ok(Json(delivery))
+}
@@ -8,6 +8,8 @@ pub struct Claims {
     pub sub: String,
     /// Expiration
     pub exp: usize,
+    /// Issued at
+    pub iat: usize,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -32,10 +34,12 @@ impl AuthBody {
 }
 
 impl Claims {
-    pub fn new(username: String) -> Self {
+    pub fn new(username: String, lifetime: Duration) -> Self {
+        let now = Utc::now();
         Self {
             sub: username,
-            exp: (Utc::now() + Duration::minutes(30)).timestamp() as usize,
+            exp: (now + lifetime).timestamp() as usize,
+            iat: now.timestamp() as usize,
         }
     }
 }
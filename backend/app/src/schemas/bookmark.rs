@@ -0,0 +1,70 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::bookmark::BookmarkFolder;
+use crate::schemas::post::PostSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct BookmarkSchema {
+    pub post: PostSchema,
+    pub folder_id: Option<Uuid>,
+    /// When the post was bookmarked, used for paging
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct BookmarkFolderSchema {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateBookmarkFolderSchema {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Folder name length must be between 1 and 64 characters"
+    ))]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateBookmarkFolderSchema {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Folder name length must be between 1 and 64 characters"
+    ))]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct MoveBookmarkSchema {
+    /// Folder to move the bookmark to, `null` takes it out of its folder
+    pub folder_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BookmarksQuery {
+    /// Only return bookmarks in this folder
+    pub folder_id: Option<Uuid>,
+    /// Return bookmarks created before this moment
+    pub before: Option<NaiveDateTime>,
+    /// Page size (default 20, maximum 100)
+    pub limit: Option<i64>,
+}
+
+impl From<BookmarkFolder> for BookmarkFolderSchema {
+    fn from(value: BookmarkFolder) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at,
+        }
+    }
+}
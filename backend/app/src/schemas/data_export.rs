@@ -0,0 +1,37 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::data_export::DataExport;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct DataExportSchema {
+    pub id: Uuid,
+    /// `pending`, `running`, `ready`, `failed` or `expired`
+    pub status: String,
+    /// Size of the archive in bytes, once it's ready
+    pub size: Option<i64>,
+    /// Where to download the archive from, once it's ready
+    pub download_url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    /// When the archive gets deleted
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl From<DataExport> for DataExportSchema {
+    fn from(value: DataExport) -> Self {
+        Self {
+            id: value.id,
+            download_url: value
+                .is_ready()
+                .then(|| format!("/auth/export/{}/download", value.id)),
+            status: value.status,
+            size: value.size,
+            created_at: value.created_at,
+            finished_at: value.finished_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
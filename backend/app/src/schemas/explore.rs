@@ -0,0 +1,53 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::utils::scoring::TrendingTag;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TrendingWindow {
+    #[serde(rename = "1h")]
+    Hour,
+    #[default]
+    #[serde(rename = "24h")]
+    Day,
+}
+
+impl TrendingWindow {
+    pub const ALL: [TrendingWindow; 2] = [TrendingWindow::Hour, TrendingWindow::Day];
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            TrendingWindow::Hour => Duration::hours(1),
+            TrendingWindow::Day => Duration::hours(24),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct TrendingTagSchema {
+    pub tag: String,
+    /// Time-decayed number of uses, only meaningful relative to other tags
+    pub score: f64,
+    /// Number of uses within the window
+    pub uses: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrendingQuery {
+    /// `1h` or `24h` (default)
+    #[serde(default)]
+    #[param(inline)]
+    pub window: TrendingWindow,
+}
+
+impl From<TrendingTag> for TrendingTagSchema {
+    fn from(value: TrendingTag) -> Self {
+        Self {
+            tag: value.tag,
+            score: value.score,
+            uses: value.uses,
+        }
+    }
+}
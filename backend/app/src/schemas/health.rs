@@ -0,0 +1,26 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthSchema {
+    pub status: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadinessSchema {
+    /// Whether the instance can serve requests
+    pub ready: bool,
+    /// Whether the database answers
+    pub database: bool,
+    /// Whether the server is shutting down and draining its requests
+    pub shutting_down: bool,
+    /// Migrations of this build that haven't run on the database
+    pub pending_migrations: Vec<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VersionSchema {
+    pub version: String,
+    /// Commit the binary was built from, when it's known
+    pub git_sha: Option<String>,
+}
@@ -0,0 +1,58 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::import::Import;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ImportSchema {
+    pub id: Uuid,
+    /// `twitter` or `mastodon`, `None` when only follows are imported
+    pub source: Option<String>,
+    /// `pending`, `running`, `done` or `failed`
+    pub status: String,
+    /// Number of posts and follows found in the uploaded files, known once the import runs
+    pub total: i32,
+    pub processed: i32,
+    pub posts_created: i32,
+    /// Posts that were empty or too long, and posts imported before
+    pub posts_skipped: i32,
+    pub follows_created: i32,
+    /// Why the import failed
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// Multipart form with an archive in `file`, a follows CSV in `follows`, or both
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct UploadImportSchema {
+    /// Twitter archive ZIP, Mastodon archive ZIP or its `outbox.json`
+    #[schema(value_type = Option<String>, format = Binary)]
+    pub file: Option<Vec<u8>>,
+    /// CSV with a username or `user@domain` address per line
+    #[schema(value_type = Option<String>, format = Binary)]
+    pub follows: Option<Vec<u8>>,
+}
+
+impl From<Import> for ImportSchema {
+    fn from(value: Import) -> Self {
+        Self {
+            id: value.id,
+            source: value.source,
+            status: value.status,
+            total: value.total,
+            processed: value.processed,
+            posts_created: value.posts_created,
+            posts_skipped: value.posts_skipped,
+            follows_created: value.follows_created,
+            error: value.error,
+            created_at: value.created_at,
+            started_at: value.started_at,
+            finished_at: value.finished_at,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::models::job::Job;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct JobSchema {
+    pub id: Uuid,
+    /// Type of the job, such as `fetch_link_preview`
+    pub kind: String,
+    /// What the job works on, with the kind in `type`
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    /// `pending`, `running` or `failed` once it ran out of attempts
+    pub status: String,
+    pub attempts: i32,
+    /// When the job runs next
+    pub run_at: NaiveDateTime,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub failed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct JobsQuery {
+    /// Only jobs with this status, `pending`, `running` or `failed`
+    pub status: Option<String>,
+    /// Page size (default 50, maximum 200)
+    pub limit: Option<i64>,
+}
+
+impl From<Job> for JobSchema {
+    fn from(value: Job) -> Self {
+        Self {
+            id: value.id,
+            kind: value.kind,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            run_at: value.run_at,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            failed_at: value.failed_at,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::list::List;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ListSchema {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub name: String,
+    /// Private lists are only visible to their owner
+    pub private: bool,
+    pub members_count: i64,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateListSchema {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "List name length must be between 1 and 64 characters"
+    ))]
+    pub name: String,
+    #[serde(default)]
+    pub private: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateListSchema {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "List name length must be between 1 and 64 characters"
+    ))]
+    pub name: Option<String>,
+    /// Making a list private hides it from its subscribers
+    pub private: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct AddListMemberSchema {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTimelineQuery {
+    /// Return posts created before this moment
+    pub before: Option<NaiveDateTime>,
+    /// Page size (default 20, maximum 100)
+    pub limit: Option<i64>,
+}
+
+impl From<List> for ListSchema {
+    fn from(value: List) -> Self {
+        Self {
+            id: value.id,
+            owner_id: value.owner_id,
+            name: value.name,
+            private: value.private,
+            members_count: 0,
+            created_at: value.created_at,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::media::Media;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct MediaSchema {
+    pub id: Uuid,
+    /// Path the file is served from
+    pub url: String,
+    pub mime: String,
+    /// Size in bytes
+    pub size: i64,
+    /// Image size in pixels, missing for videos
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Placeholder to show while the image loads, see https://blurha.sh
+    pub blurhash: Option<String>,
+    pub variants: MediaVariantsSchema,
+}
+
+/// Paths of the resized copies. Files without copies, like videos and GIFs,
+/// serve the original for every variant.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct MediaVariantsSchema {
+    pub original: String,
+    pub small: String,
+    pub thumbnail: String,
+}
+
+/// Multipart form with a single `file` field
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct UploadMediaSchema {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+impl From<Media> for MediaSchema {
+    fn from(value: Media) -> Self {
+        Self {
+            id: value.id,
+            url: format!("/media/{}", value.id),
+            mime: value.mime,
+            size: value.size,
+            width: value.width,
+            height: value.height,
+            blurhash: value.blurhash,
+            variants: MediaVariantsSchema::new(value.id),
+        }
+    }
+}
+
+impl MediaVariantsSchema {
+    pub fn new(id: Uuid) -> Self {
+        Self {
+            original: format!("/media/{}/original", id),
+            small: format!("/media/{}/small", id),
+            thumbnail: format!("/media/{}/thumbnail", id),
+        }
+    }
+}
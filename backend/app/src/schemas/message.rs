@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::conversation::ConversationPreview;
+use crate::models::message::Message;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct MessageSchema {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub text: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ConversationSchema {
+    pub id: Uuid,
+    pub member_ids: Vec<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub last_message: Option<MessageSchema>,
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateConversationSchema {
+    /// Users to start a conversation with, not including yourself
+    #[validate(length(
+        min = 1,
+        max = 7,
+        message = "A conversation must have between 1 and 7 other members"
+    ))]
+    pub member_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SendMessageSchema {
+    #[validate(length(
+        min = 1,
+        max = 1000,
+        message = "Text length must be between 1 and 1000 characters"
+    ))]
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MessagesQuery {
+    /// Return messages sent before this moment
+    pub before: Option<NaiveDateTime>,
+    /// Page size (default 50, maximum 100)
+    pub limit: Option<i64>,
+}
+
+impl From<Message> for MessageSchema {
+    fn from(value: Message) -> Self {
+        Self {
+            id: value.id,
+            conversation_id: value.conversation_id,
+            sender_id: value.sender_id,
+            text: value.text,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<ConversationPreview> for ConversationSchema {
+    fn from(value: ConversationPreview) -> Self {
+        let last_message = match (
+            value.last_message_id,
+            value.last_message_sender_id,
+            value.last_message_text,
+            value.last_message_created_at,
+        ) {
+            (Some(id), Some(sender_id), Some(text), Some(created_at)) => Some(MessageSchema {
+                id,
+                conversation_id: value.id,
+                sender_id,
+                text,
+                created_at,
+            }),
+            _ => None,
+        };
+
+        Self {
+            id: value.id,
+            member_ids: value.member_ids,
+            created_at: value.created_at,
+            last_message,
+            unread_count: value.unread_count,
+        }
+    }
+}
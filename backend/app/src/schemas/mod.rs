@@ -1,4 +1,16 @@
 pub mod auth;
+pub mod bookmark;
+pub mod data_export;
+pub mod explore;
 pub mod following;
+pub mod health;
+pub mod import;
+pub mod job;
+pub mod list;
+pub mod media;
+pub mod message;
+pub mod poll;
 pub mod post;
+pub mod search;
 pub mod user;
+pub mod webhook;
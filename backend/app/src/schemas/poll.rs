@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreatePollSchema {
+    /// Option texts in display order, each up to 50 characters
+    #[validate(length(min = 2, max = 4, message = "A poll must have 2 to 4 options"))]
+    pub options: Vec<String>,
+    /// How long the poll accepts votes, from 5 minutes to 7 days
+    #[validate(range(
+        min = 5,
+        max = 10080,
+        message = "Poll duration must be between 5 minutes and 7 days"
+    ))]
+    pub duration_minutes: i64,
+    /// Let voters pick more than one option
+    #[serde(default)]
+    pub multiple: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PollSchema {
+    pub options: Vec<PollOptionSchema>,
+    pub multiple: bool,
+    pub expires_at: NaiveDateTime,
+    pub closed: bool,
+    /// Number of users who voted, hidden like the option counts
+    pub voters_count: Option<i64>,
+    /// Positions of the options the current user voted for
+    pub voted_options: Vec<i16>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PollOptionSchema {
+    pub position: i16,
+    pub text: String,
+    /// Hidden until you vote or the poll closes
+    pub votes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct VoteSchema {
+    /// Positions of the chosen options, exactly one unless the poll is multiple choice
+    #[validate(length(min = 1, max = 4, message = "Pick between 1 and 4 options"))]
+    pub options: Vec<i16>,
+}
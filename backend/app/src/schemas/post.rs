@@ -1,4 +1,8 @@
-use crate::models::post::Post;
+use crate::models::link_preview::LinkPreview;
+use crate::models::post::{Post, PostRevision};
+use crate::schemas::media::MediaSchema;
+use crate::schemas::poll::{CreatePollSchema, PollSchema};
+use crate::utils::entities::{EntityKind, ParsedEntity};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -12,26 +16,92 @@ pub struct PostSchema {
     pub author_id: Uuid,
     pub created_at: NaiveDateTime,
     pub edited: bool,
+    pub edited_at: Option<NaiveDateTime>,
+    /// Number of earlier versions of the text
+    pub revision_count: i64,
+    /// When the post was moved to the trash, only set for posts in the trash
+    pub deleted_at: Option<NaiveDateTime>,
+    pub draft: bool,
+    /// When the post will be published, only set for scheduled posts
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// Pinned to the top of the author's profile
+    pub pinned: bool,
+    /// Post this one replies to
+    pub reply_to_id: Option<Uuid>,
+    /// The post this one replies to was deleted, clients show a tombstone in its place
+    pub reply_to_deleted: bool,
+    pub entities: Vec<EntitySchema>,
+    pub media: Vec<MediaSchema>,
+    /// Preview of the first link in the text, once the page has been fetched
+    pub card: Option<LinkCardSchema>,
+    pub poll: Option<PollSchema>,
+    /// Whether the viewer has bookmarked the post
+    pub bookmarked_by_me: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct LinkCardSchema {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute url of the preview image on the linked site
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PostRevisionSchema {
+    pub id: Uuid,
+    pub text: String,
+    /// When this version of the text was written
+    pub created_at: NaiveDateTime,
+}
+
+/// Mention or hashtag in a post text. Offsets include the leading `@` or `#`.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct EntitySchema {
+    pub kind: EntityKind,
+    /// Username or tag without the leading symbol
+    pub text: String,
+    /// Mentioned user, only set for mentions
+    pub user_id: Option<Uuid>,
+    /// Byte offset of the entity start
+    pub start: usize,
+    /// Byte offset of the entity end (exclusive)
+    pub end: usize,
+    /// Character offset of the entity start
+    pub char_start: usize,
+    /// Character offset of the entity end (exclusive)
+    pub char_end: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreatePostSchema {
-    #[validate(length(
-        min = 1,
-        max = 256,
-        message = "Text length must be between 1 and 256 characters"
-    ))]
+    /// Can't be longer than the instance's post length limit
+    #[validate(length(min = 1, message = "Text can't be empty"))]
     pub text: String,
+    /// Uploaded media to attach, in display order
+    #[serde(default)]
+    #[validate(length(max = 4, message = "A post can have at most 4 media attachments"))]
+    pub media_ids: Vec<Uuid>,
+    #[validate(nested)]
+    pub poll: Option<CreatePollSchema>,
+    /// Publish the post at this time instead of right away, must be in the future
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// Keep the post unpublished until it is scheduled or published
+    #[serde(default)]
+    pub draft: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct UpdatePostSchema {
-    #[validate(length(
-        min = 1,
-        max = 256,
-        message = "Text length must be between 1 and 256 characters"
-    ))]
+    /// Can't be longer than the instance's post length limit
+    #[validate(length(min = 1, message = "Text can't be empty"))]
     pub text: Option<String>,
+    /// Reschedules an unpublished post, drafts become scheduled
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// `true` moves a scheduled post back to the drafts
+    pub draft: Option<bool>,
 }
 
 impl From<&Post> for PostSchema {
@@ -42,6 +112,55 @@ impl From<&Post> for PostSchema {
             author_id: value.author_id,
             created_at: value.created_at,
             edited: value.edited,
+            edited_at: value.edited_at,
+            revision_count: 0,
+            deleted_at: value.deleted_at,
+            draft: value.draft,
+            scheduled_at: value.scheduled_at,
+            pinned: value.pinned_at.is_some(),
+            reply_to_id: value.reply_to_id,
+            reply_to_deleted: false,
+            entities: Vec::new(),
+            media: Vec::new(),
+            card: None,
+            poll: None,
+            bookmarked_by_me: value.bookmarked_by_me,
+        }
+    }
+}
+
+impl From<PostRevision> for PostRevisionSchema {
+    fn from(value: PostRevision) -> Self {
+        Self {
+            id: value.id,
+            text: value.text,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl EntitySchema {
+    pub fn new(entity: ParsedEntity, user_id: Option<Uuid>) -> Self {
+        Self {
+            kind: entity.kind,
+            text: entity.value,
+            user_id,
+            start: entity.start,
+            end: entity.end,
+            char_start: entity.char_start,
+            char_end: entity.char_end,
+        }
+    }
+}
+
+impl From<LinkPreview> for LinkCardSchema {
+    fn from(value: LinkPreview) -> Self {
+        Self {
+            url: value.url,
+            title: value.title,
+            description: value.description,
+            image: value.image,
+            site_name: value.site_name,
         }
     }
 }
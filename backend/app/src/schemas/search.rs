@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::schemas::post::PostSchema;
+use crate::schemas::user::UserSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchType {
+    Posts,
+    Users,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Search query, supports `"quoted phrases"`, `or` and `-excluded` words
+    pub q: String,
+    /// What to search for, both posts and users if omitted
+    #[serde(rename = "type")]
+    #[param(inline)]
+    pub kind: Option<SearchType>,
+    /// Page size (default 20, maximum 50)
+    pub limit: Option<i64>,
+    /// Number of results to skip
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PostSearchHitSchema {
+    pub post: PostSchema,
+    pub rank: f32,
+    /// Matching fragments of the HTML-escaped text, matches wrapped in `<mark>`
+    pub snippet: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct UserSearchHitSchema {
+    pub user: UserSchema,
+    pub rank: f32,
+    /// Matching fragments of the HTML-escaped bio, matches wrapped in `<mark>`
+    pub snippet: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default)]
+pub struct SearchResultsSchema {
+    pub posts: Vec<PostSearchHitSchema>,
+    pub users: Vec<UserSearchHitSchema>,
+}
@@ -1,4 +1,6 @@
 use crate::models::user::User;
+use crate::schemas::media::MediaVariantsSchema;
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -8,17 +10,30 @@ use validator::Validate;
 pub struct UserSchema {
     pub id: Uuid,
     pub username: String,
+    /// Path of the uploaded profile picture, 400x400 pixels, or the URL of
+    /// a picture linked before uploads were possible
     pub avatar: Option<String>,
+    pub avatar_variants: Option<MediaVariantsSchema>,
+    /// Path of the profile banner, 1500x500 pixels
+    pub banner: Option<String>,
+    pub banner_variants: Option<MediaVariantsSchema>,
     pub about: String,
 }
 
+/// The signed-in user's own profile, with the settings nobody else sees
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct MeSchema {
+    #[serde(flatten)]
+    pub user: UserSchema,
+    /// Accept direct messages only from users who follow you
+    pub dms_from_followers_only: bool,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreateUserSchema {
     #[validate(length(min = 4, message = "Username must be at least 4 characters long"))]
     pub username: String,
     pub password: String,
-    #[validate(url)]
-    pub avatar: Option<String>,
     #[validate(length(max = 255, message = "About must be less than 255 characters"))]
     pub about: String,
 }
@@ -28,10 +43,22 @@ pub struct UpdateUserSchema {
     #[validate(length(min = 4, message = "Username must be at least 4 characters long"))]
     pub username: Option<String>,
     pub password: Option<String>,
-    #[validate(url)]
-    pub avatar: Option<String>,
     #[validate(length(max = 255, message = "About must be less than 255 characters"))]
     pub about: Option<String>,
+    /// Accept direct messages only from users who follow you
+    pub dms_from_followers_only: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DeleteAccountSchema {
+    /// Current password, to confirm it's really you
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AccountDeletionSchema {
+    /// When the account gets purged, `None` if it was deleted right away
+    pub deletion_scheduled_at: Option<NaiveDateTime>,
 }
 
 impl From<User> for UserSchema {
@@ -39,8 +66,23 @@ impl From<User> for UserSchema {
         Self {
             id: value.id,
             username: value.username,
-            avatar: value.avatar,
+            avatar: match value.avatar_media_id {
+                Some(id) => Some(format!("/media/{}", id)),
+                None => value.avatar,
+            },
+            avatar_variants: value.avatar_media_id.map(MediaVariantsSchema::new),
+            banner: value.banner_media_id.map(|id| format!("/media/{}", id)),
+            banner_variants: value.banner_media_id.map(MediaVariantsSchema::new),
             about: value.about,
         }
     }
 }
+
+impl From<User> for MeSchema {
+    fn from(value: User) -> Self {
+        Self {
+            dms_from_followers_only: value.dms_from_followers_only,
+            user: value.into(),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::webhook::{Webhook, WebhookDelivery};
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct WebhookSchema {
+    pub id: Uuid,
+    pub url: String,
+    /// Key of the `X-Twotty-Signature` HMAC-SHA256 signature
+    pub secret: String,
+    /// `post.created`, `post.deleted`, `user.followed`, `mention` or `poll.closed`
+    pub events: Vec<String>,
+    /// Receives the events of every user instead of only yours
+    pub all_users: bool,
+    pub enabled: bool,
+    /// Failed attempts in a row, the webhook is disabled once there are too many
+    pub failure_count: i32,
+    /// When it was disabled for failing
+    pub disabled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateWebhookSchema {
+    /// http or https url the events are posted to
+    #[validate(length(max = 2048, message = "Url must be at most 2048 characters long"))]
+    pub url: String,
+    /// `post.created`, `post.deleted`, `user.followed`, `mention` or `poll.closed`
+    pub events: Vec<String>,
+    /// Receive the events of every user, for admins only
+    #[serde(default)]
+    pub all_users: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateWebhookSchema {
+    #[validate(length(max = 2048, message = "Url must be at most 2048 characters long"))]
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    /// Enabling a webhook that was disabled for failing clears its failures
+    pub enabled: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct WebhookDeliverySchema {
+    /// Sent in `X-Twotty-Delivery`, the same for every attempt
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    /// Body that is sent
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    /// `pending`, `delivered` or `failed` once it ran out of attempts
+    pub status: String,
+    pub attempts: i32,
+    /// Status code of the last response
+    pub response_status: Option<i32>,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WebhookDeliveriesQuery {
+    /// Only deliveries with this status, `pending`, `delivered` or `failed`
+    pub status: Option<String>,
+    /// Page size (default 50, maximum 200)
+    pub limit: Option<i64>,
+}
+
+impl From<Webhook> for WebhookSchema {
+    fn from(value: Webhook) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            secret: value.secret,
+            events: value.events,
+            all_users: value.all_users,
+            enabled: value.enabled,
+            failure_count: value.failure_count,
+            disabled_at: value.disabled_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<WebhookDelivery> for WebhookDeliverySchema {
+    fn from(value: WebhookDelivery) -> Self {
+        Self {
+            id: value.id,
+            webhook_id: value.webhook_id,
+            event: value.event,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            response_status: value.response_status,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::stats::InstanceStats;
+use crate::models::user::{User, DELETED_USER_ID};
+use crate::repositories::stats::StatsRepository;
+use crate::repositories::user::UpdateUserDTO;
+use crate::repositories::UnitOfWork;
+use crate::schemas::user::{CreateUserSchema, UserSchema};
+use crate::services::data_export::DataExportService;
+use crate::services::post::PostService;
+use crate::services::user::UserService;
+use crate::services::webhook::WebhookService;
+use crate::utils::auth::hash_password;
+use crate::utils::errors::{AppError, AuthError, Result};
+
+/// What a purge deleted
+#[derive(Clone, Debug, Default)]
+pub struct PurgeReport {
+    pub posts: u64,
+    pub accounts: u64,
+    pub data_exports: usize,
+    pub webhook_deliveries: u64,
+}
+
+/// Operations on the instance for its operators, run from the admin CLI.
+/// Users are looked up by username.
+#[derive(Clone)]
+pub struct AdminService {
+    pub user_service: UserService,
+    pub post_service: PostService,
+    pub data_export_service: DataExportService,
+    pub webhook_service: WebhookService,
+    pub stats_repository: StatsRepository,
+}
+
+impl AdminService {
+    pub async fn create_user(
+        &self,
+        username: String,
+        password: String,
+        is_admin: bool,
+    ) -> Result<UserSchema> {
+        let data = CreateUserSchema {
+            username,
+            password,
+            about: String::new(),
+        };
+        data.validate()?;
+        let user = self.user_service.create_user(data).await?;
+        if is_admin {
+            self.user_service
+                .repository
+                .set_admin(&user.id, true)
+                .await?;
+        }
+        Ok(user)
+    }
+
+    /// Grants or takes away access to the `/admin` endpoints.
+    pub async fn set_admin(&self, username: &str, is_admin: bool) -> Result<()> {
+        let user = self.find_user(username).await?;
+        Ok(self
+            .user_service
+            .repository
+            .set_admin(&user.id, is_admin)
+            .await?)
+    }
+
+    /// Sets a new password and signs the user out everywhere.
+    pub async fn reset_password(&self, username: &str, password: String) -> Result<()> {
+        let user = self.find_user(username).await?;
+        let repository = &self.user_service.repository;
+        let mut uow = UnitOfWork::begin(&repository.pool).await?;
+        repository
+            .update_in(
+                &mut uow,
+                &user.id,
+                UpdateUserDTO {
+                    username: None,
+                    password: Some(hash_password(password)),
+                    about: None,
+                    dms_from_followers_only: None,
+                },
+            )
+            .await?;
+        repository
+            .revoke_tokens_in(&mut uow, &user.id, Utc::now().naive_utc())
+            .await?;
+        uow.commit().await?;
+        Ok(())
+    }
+
+    /// Suspends the account and signs the user out everywhere. Returns
+    /// `false` when it was suspended already.
+    pub async fn suspend(&self, username: &str) -> Result<bool> {
+        let user = self.find_user(username).await?;
+        let now = Utc::now().naive_utc();
+        let repository = &self.user_service.repository;
+        let mut uow = UnitOfWork::begin(&repository.pool).await?;
+        let suspended = repository
+            .set_suspended_in(&mut uow, &user.id, Some(now))
+            .await?;
+        repository.revoke_tokens_in(&mut uow, &user.id, now).await?;
+        uow.commit().await?;
+        Ok(suspended)
+    }
+
+    /// Lifts a suspension. Returns `false` when the account wasn't suspended.
+    pub async fn unsuspend(&self, username: &str) -> Result<bool> {
+        let user = self.find_user(username).await?;
+        Ok(self
+            .user_service
+            .repository
+            .set_suspended(&user.id, None)
+            .await?)
+    }
+
+    /// Deletes the account and everything it owns right away, without a grace period.
+    pub async fn delete_user(&self, username: &str) -> Result<Uuid> {
+        let user = self.find_user(username).await?;
+        self.user_service.delete_user(&user.id).await?;
+        Ok(user.id)
+    }
+
+    /// Rebuilds the full-text indexes of posts and users. Searches keep
+    /// working while they are rebuilt.
+    pub async fn reindex_search(&self) -> Result<()> {
+        self.post_service.repository.reindex_search().await?;
+        self.user_service.repository.reindex_search().await?;
+        Ok(())
+    }
+
+    /// Deletes what is due for deletion: posts in the trash past the
+    /// retention period, accounts past their grace period, expired data exports
+    /// and old webhook deliveries. With `all_trash` the whole trash is emptied.
+    pub async fn purge(&self, all_trash: bool) -> Result<PurgeReport> {
+        let now = Utc::now().naive_utc();
+        let trash_before = if all_trash {
+            now
+        } else {
+            now - self.post_service.trash_retention
+        };
+        Ok(PurgeReport {
+            posts: self.post_service.purge_trash_before(trash_before).await?,
+            accounts: self.user_service.purge_due_accounts().await?,
+            data_exports: self.data_export_service.purge_expired().await?,
+            webhook_deliveries: self.webhook_service.purge_deliveries().await?,
+        })
+    }
+
+    pub async fn stats(&self) -> Result<InstanceStats> {
+        Ok(self.stats_repository.fetch().await?)
+    }
+
+    /// Finds the user an action is run on. The placeholder that owns the
+    /// anonymized content of deleted accounts can't be changed.
+    async fn find_user(&self, username: &str) -> Result<User> {
+        let user = self
+            .user_service
+            .repository
+            .find_one_by_username(username)
+            .await?
+            .ok_or(AuthError::UserNotFound)?;
+        if user.id == DELETED_USER_ID {
+            return Err(AppError::PlaceholderAccount);
+        }
+        Ok(user)
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::models::bookmark::{Bookmark, BookmarkFolder};
+use crate::repositories::bookmark::{
+    BookmarkFindAllParams, BookmarkRepository, CreateBookmarkDTO, UpdateBookmarkDTO,
+};
+use crate::repositories::bookmark_folder::{
+    BookmarkFolderFindAllParams, BookmarkFolderRepository, CreateBookmarkFolderDTO,
+    UpdateBookmarkFolderDTO,
+};
+use crate::repositories::post::PostRepository;
+use crate::repositories::Repository;
+use crate::schemas::bookmark::{
+    BookmarkFolderSchema, BookmarkSchema, BookmarksQuery, CreateBookmarkFolderSchema,
+    MoveBookmarkSchema, UpdateBookmarkFolderSchema,
+};
+use crate::schemas::post::PostSchema;
+use crate::services::post::PostService;
+use crate::utils::errors::{AppError, Result};
+
+/// Bookmarks and their folders are private, every method only ever touches
+/// the data of the given user.
+#[derive(Clone)]
+pub struct BookmarkService {
+    pub repository: BookmarkRepository,
+    pub folder_repository: BookmarkFolderRepository,
+    pub post_repository: PostRepository,
+    pub post_service: PostService,
+}
+
+impl BookmarkService {
+    /// Bookmarks a visible post. Bookmarking it again keeps the existing bookmark.
+    pub async fn bookmark(&self, post_id: &Uuid, user_id: &Uuid) -> Result<PostSchema> {
+        self.post_service.find_one_post(post_id, user_id).await?;
+        self.repository
+            .create(CreateBookmarkDTO {
+                user_id: *user_id,
+                post_id: *post_id,
+            })
+            .await?;
+        self.post_service.find_one_post(post_id, user_id).await
+    }
+
+    pub async fn unbookmark(&self, post_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        let bookmark = self.find_bookmark(post_id, user_id).await?;
+        self.repository.delete(&bookmark.id).await?;
+        Ok(())
+    }
+
+    /// Bookmarked posts, most recently bookmarked first.
+    pub async fn find_bookmarks(
+        &self,
+        user_id: &Uuid,
+        query: BookmarksQuery,
+    ) -> Result<Vec<BookmarkSchema>> {
+        if let Some(folder_id) = &query.folder_id {
+            self.find_own_folder(folder_id, user_id).await?;
+        }
+        let bookmarks = self
+            .repository
+            .find_all(BookmarkFindAllParams {
+                user_id: *user_id,
+                folder_id: query.folder_id,
+                before: query.before,
+                limit: query.limit.unwrap_or(20).clamp(1, 100),
+            })
+            .await?;
+        let ids: Vec<Uuid> = bookmarks.iter().map(|bookmark| bookmark.post_id).collect();
+        let posts = self.post_repository.find_all_by_ids(&ids, user_id).await?;
+        let mut posts: HashMap<Uuid, PostSchema> = self
+            .post_service
+            .to_schemas(posts, user_id)
+            .await?
+            .into_iter()
+            .map(|post| (post.id, post))
+            .collect();
+
+        Ok(bookmarks
+            .into_iter()
+            .filter_map(|bookmark| {
+                Some(BookmarkSchema {
+                    post: posts.remove(&bookmark.post_id)?,
+                    folder_id: bookmark.folder_id,
+                    created_at: bookmark.created_at,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn move_bookmark(
+        &self,
+        post_id: &Uuid,
+        user_id: &Uuid,
+        data: MoveBookmarkSchema,
+    ) -> Result<()> {
+        let bookmark = self.find_bookmark(post_id, user_id).await?;
+        if let Some(folder_id) = &data.folder_id {
+            self.find_own_folder(folder_id, user_id).await?;
+        }
+        self.repository
+            .update(
+                &bookmark.id,
+                UpdateBookmarkDTO {
+                    folder_id: data.folder_id,
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_folders(&self, user_id: &Uuid) -> Result<Vec<BookmarkFolderSchema>> {
+        Ok(self
+            .folder_repository
+            .find_all(BookmarkFolderFindAllParams { user_id: *user_id })
+            .await?
+            .into_iter()
+            .map(BookmarkFolderSchema::from)
+            .collect())
+    }
+
+    pub async fn create_folder(
+        &self,
+        user_id: &Uuid,
+        data: CreateBookmarkFolderSchema,
+    ) -> Result<BookmarkFolderSchema> {
+        let name = data.name.trim().to_string();
+        self.check_folder_name(user_id, &name, None).await?;
+        let folder = self
+            .folder_repository
+            .create(CreateBookmarkFolderDTO {
+                user_id: *user_id,
+                name,
+            })
+            .await?;
+        Ok(folder.into())
+    }
+
+    pub async fn rename_folder(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+        data: UpdateBookmarkFolderSchema,
+    ) -> Result<BookmarkFolderSchema> {
+        let mut folder = self.find_own_folder(id, user_id).await?;
+        let name = data.name.trim().to_string();
+        self.check_folder_name(user_id, &name, Some(id)).await?;
+        self.folder_repository
+            .update(id, UpdateBookmarkFolderDTO { name: name.clone() })
+            .await?;
+        folder.name = name;
+        Ok(folder.into())
+    }
+
+    /// Deletes the folder, its bookmarks are kept outside of any folder.
+    pub async fn delete_folder(&self, id: &Uuid, user_id: &Uuid) -> Result<()> {
+        self.find_own_folder(id, user_id).await?;
+        self.folder_repository.delete(id).await?;
+        Ok(())
+    }
+
+    async fn find_bookmark(&self, post_id: &Uuid, user_id: &Uuid) -> Result<Bookmark> {
+        self.repository
+            .find_one_by_post(user_id, post_id)
+            .await?
+            .ok_or(AppError::EntityNotFound {
+                entity: "Bookmark",
+                id: *post_id,
+            })
+    }
+
+    /// Folders of other users are reported as missing.
+    async fn find_own_folder(&self, id: &Uuid, user_id: &Uuid) -> Result<BookmarkFolder> {
+        self.folder_repository
+            .find_one(id)
+            .await?
+            .filter(|folder| folder.user_id == *user_id)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Bookmark folder",
+                id: *id,
+            })
+    }
+
+    async fn check_folder_name(&self, user_id: &Uuid, name: &str, id: Option<&Uuid>) -> Result<()> {
+        if name.is_empty() {
+            return Err(AppError::InvalidFolderName);
+        }
+        match self
+            .folder_repository
+            .find_one_by_name(user_id, name)
+            .await?
+        {
+            Some(folder) if Some(&folder.id) != id => Err(AppError::FolderNameTaken),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::io::Seek;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use zip::result::ZipError;
+
+use crate::models::data_export::DataExport;
+use crate::models::job::JobPayload;
+use crate::repositories::bookmark::BookmarkRepository;
+use crate::repositories::bookmark_folder::{BookmarkFolderFindAllParams, BookmarkFolderRepository};
+use crate::repositories::data_export::{
+    CreateDataExportDTO, DataExportFindAllParams, DataExportRepository,
+};
+use crate::repositories::following::FollowingRepository;
+use crate::repositories::job::JobRepository;
+use crate::repositories::post::PostRepository;
+use crate::repositories::user::UserRepository;
+use crate::repositories::{RepoError, Repository};
+use crate::schemas::data_export::DataExportSchema;
+use crate::services::media::MediaService;
+use crate::storage::{ByteStream, StorageError};
+use crate::utils::errors::{AppError, Result};
+use crate::utils::export::{
+    ArchiveBookmark, ArchiveContents, ArchiveMedia, ArchivePost, ArchiveProfile, ArchiveRevision,
+    ArchiveUser, ArchiveWriter,
+};
+use crate::utils::heartbeat::with_heartbeat;
+
+/// Running exports whose lock wasn't refreshed for this long are assumed to be
+/// abandoned and built again
+const STALE_AFTER_MINUTES: i64 = 5;
+
+#[derive(Clone)]
+pub struct DataExportService {
+    pub repository: DataExportRepository,
+    pub user_repository: UserRepository,
+    pub post_repository: PostRepository,
+    pub following_repository: FollowingRepository,
+    pub bookmark_repository: BookmarkRepository,
+    pub bookmark_folder_repository: BookmarkFolderRepository,
+    /// Queues the building of requested exports
+    pub job_repository: JobRepository,
+    /// Reads the user's media and keeps the finished archives
+    pub media_service: MediaService,
+    /// How long a finished archive can be downloaded
+    pub expiry: Duration,
+}
+
+impl DataExportService {
+    /// Queues an export of everything the user has stored.
+    pub async fn request_export(&self, user_id: &Uuid) -> Result<DataExportSchema> {
+        if self
+            .find_exports(user_id)
+            .await?
+            .iter()
+            .any(DataExport::is_in_progress)
+        {
+            return Err(AppError::ExportInProgress);
+        }
+        let export = self
+            .repository
+            .create(CreateDataExportDTO { user_id: *user_id })
+            .await
+            .map_err(|e| match e {
+                // Another request started one in the meantime
+                RepoError::UniqueViolation(_) => AppError::ExportInProgress,
+                e => e.into(),
+            })?;
+        self.job_repository
+            .enqueue(&JobPayload::BuildDataExports, Utc::now().naive_utc())
+            .await?;
+        Ok(export.into())
+    }
+
+    /// Finds an export of the user. Exports of other users are reported as missing.
+    pub async fn find_export(&self, id: &Uuid, user_id: &Uuid) -> Result<DataExportSchema> {
+        Ok(self.find_own_export(id, user_id).await?.into())
+    }
+
+    /// Streams the archive of a ready export.
+    pub async fn download(&self, id: &Uuid, user_id: &Uuid) -> Result<ByteStream> {
+        let export = self.find_own_export(id, user_id).await?;
+        let Some(key) = export.storage_key.clone().filter(|_| export.is_ready()) else {
+            return Err(AppError::ExportNotReady);
+        };
+        self.media_service
+            .store
+            .get_stream(&key)
+            .await?
+            .ok_or(AppError::ExportNotReady)
+    }
+
+    async fn find_exports(&self, user_id: &Uuid) -> Result<Vec<DataExport>> {
+        Ok(self
+            .repository
+            .find_all(DataExportFindAllParams { user_id: *user_id })
+            .await?)
+    }
+
+    async fn find_own_export(&self, id: &Uuid, user_id: &Uuid) -> Result<DataExport> {
+        self.repository
+            .find_one(id)
+            .await?
+            .filter(|export| export.user_id == *user_id)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Data export",
+                id: *id,
+            })
+    }
+
+    /// Builds pending exports one after another until none are left.
+    pub async fn run_pending(&self) -> Result<usize> {
+        let mut built = 0;
+        loop {
+            let now = Utc::now().naive_utc();
+            let Some(export) = self
+                .repository
+                .claim_next(now, now - Duration::minutes(STALE_AFTER_MINUTES))
+                .await?
+            else {
+                return Ok(built);
+            };
+            let result = with_heartbeat(self.build(&export), || {
+                self.repository
+                    .heartbeat(&export.id, Utc::now().naive_utc())
+            })
+            .await;
+            match result {
+                Ok(()) => built += 1,
+                Err(e) => {
+                    tracing::error!("Can't build data export {}: {}", export.id, e);
+                    self.repository
+                        .mark_failed(&export.id, Utc::now().naive_utc())
+                        .await?;
+                }
+            }
+        }
+    }
+
+    async fn build(&self, export: &DataExport) -> Result<()> {
+        let (mut contents, storage_keys) = self.collect(&export.user_id).await?;
+        // Written to a temporary file in blocking tasks, a large archive would
+        // take too much memory and stall the async workers
+        let mut archive = ArchiveWriter::new(tempfile::tempfile().map_err(StorageError::from)?);
+        for (media, key) in contents.media.iter_mut().zip(storage_keys) {
+            let data = match self.media_service.store.get(&key).await {
+                Ok(Some(data)) => data,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("Can't read media {} for a data export: {}", media.id, e);
+                    continue;
+                }
+            };
+            let path = format!("media/{}", key);
+            media.file = Some(path.clone());
+            archive = tokio::task::spawn_blocking(move || {
+                archive.add_media(&path, &data)?;
+                Ok::<_, ZipError>(archive)
+            })
+            .await??;
+        }
+        let mut file = tokio::task::spawn_blocking(move || archive.finish(&contents)).await??;
+        file.rewind().map_err(StorageError::from)?;
+        let size = file.metadata().map_err(StorageError::from)?.len();
+
+        let key = format!("export_{}.zip", export.id);
+        self.media_service
+            .store
+            .put_stream(
+                &key,
+                &mut tokio::fs::File::from_std(file),
+                "application/zip",
+            )
+            .await?;
+        let now = Utc::now().naive_utc();
+        self.repository
+            .mark_ready(&export.id, &key, size as i64, now, now + self.expiry)
+            .await?;
+        Ok(())
+    }
+
+    /// Gathers the user's data and the storage keys of their media files, in
+    /// the order of `media` in the contents.
+    async fn collect(&self, user_id: &Uuid) -> Result<(ArchiveContents, Vec<String>)> {
+        let user =
+            self.user_repository
+                .find_one(user_id)
+                .await?
+                .ok_or(AppError::EntityNotFound {
+                    entity: "User",
+                    id: *user_id,
+                })?;
+
+        let posts = self.post_repository.find_all_of_author(user_id).await?;
+        let post_ids: Vec<Uuid> = posts.iter().map(|post| post.id).collect();
+        let mut revisions: HashMap<Uuid, Vec<ArchiveRevision>> = HashMap::new();
+        for revision in self
+            .post_repository
+            .find_revisions_of_posts(&post_ids)
+            .await?
+        {
+            revisions
+                .entry(revision.post_id)
+                .or_default()
+                .push(ArchiveRevision {
+                    text: revision.text,
+                    created_at: revision.created_at,
+                });
+        }
+
+        let mut media = self
+            .media_service
+            .repository
+            .find_all_by_owner(user_id)
+            .await?;
+        media.sort_by_key(|media| (media.post_id, media.position, media.created_at));
+        let mut media_ids: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for media in &media {
+            if let Some(post_id) = media.post_id {
+                media_ids.entry(post_id).or_default().push(media.id);
+            }
+        }
+
+        let followers = self
+            .find_users(
+                self.following_repository
+                    .find_all_by_to_id(user_id)
+                    .await?
+                    .into_iter()
+                    .map(|following| following.from_id),
+            )
+            .await?;
+        let following = self
+            .find_users(
+                self.following_repository
+                    .find_all_by_from_id(user_id)
+                    .await?
+                    .into_iter()
+                    .map(|following| following.to_id),
+            )
+            .await?;
+
+        let folders: HashMap<Uuid, String> = self
+            .bookmark_folder_repository
+            .find_all(BookmarkFolderFindAllParams { user_id: *user_id })
+            .await?
+            .into_iter()
+            .map(|folder| (folder.id, folder.name))
+            .collect();
+        let bookmarks = self
+            .bookmark_repository
+            .find_all_of_user(user_id)
+            .await?
+            .into_iter()
+            .map(|bookmark| ArchiveBookmark {
+                post_id: bookmark.post_id,
+                folder: bookmark.folder_id.and_then(|id| folders.get(&id).cloned()),
+                created_at: bookmark.created_at,
+            })
+            .collect();
+
+        let mut storage_keys = Vec::new();
+        let mut archive_media = Vec::new();
+        for media in media {
+            archive_media.push(ArchiveMedia {
+                id: media.id,
+                post_id: media.post_id,
+                mime: media.mime,
+                size: media.size,
+                created_at: media.created_at,
+                file: None,
+            });
+            storage_keys.push(media.storage_key);
+        }
+
+        let contents = ArchiveContents {
+            profile: ArchiveProfile {
+                id: user.id,
+                username: user.username,
+                about: user.about,
+                dms_from_followers_only: user.dms_from_followers_only,
+                avatar: user.avatar_media_id,
+                avatar_url: user.avatar,
+                banner: user.banner_media_id,
+            },
+            posts: posts
+                .into_iter()
+                .map(|post| ArchivePost {
+                    revisions: revisions.remove(&post.id).unwrap_or_default(),
+                    media_ids: media_ids.remove(&post.id).unwrap_or_default(),
+                    id: post.id,
+                    text: post.text,
+                    created_at: post.created_at,
+                    edited_at: post.edited_at,
+                    deleted_at: post.deleted_at,
+                    draft: post.draft,
+                    scheduled_at: post.scheduled_at,
+                    pinned: post.pinned_at.is_some(),
+                })
+                .collect(),
+            followers,
+            following,
+            bookmarks,
+            media: archive_media,
+            exported_at: Utc::now().naive_utc(),
+        };
+        Ok((contents, storage_keys))
+    }
+
+    async fn find_users(&self, ids: impl Iterator<Item = Uuid>) -> Result<Vec<ArchiveUser>> {
+        let ids: Vec<Uuid> = ids.collect();
+        let mut users: Vec<ArchiveUser> = self
+            .user_repository
+            .find_all_by_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|user| ArchiveUser {
+                id: user.id,
+                username: user.username,
+            })
+            .collect();
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(users)
+    }
+
+    /// Deletes the archives of expired exports. An export is only marked
+    /// expired once its archive is gone, so archives that can't be deleted
+    /// now are tried again on the next purge.
+    pub async fn purge_expired(&self) -> Result<usize> {
+        let mut purged = 0;
+        for export in self.repository.find_expired(Utc::now().naive_utc()).await? {
+            if let Some(key) = &export.storage_key {
+                if let Err(e) = self.media_service.store.delete(key).await {
+                    tracing::error!("Can't delete archive of export {}: {}", export.id, e);
+                    continue;
+                }
+            }
+            if self.repository.expire(&export.id).await? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Duration;
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::repositories::post::PostRepository;
+use crate::schemas::explore::{TrendingTagSchema, TrendingWindow};
+use crate::schemas::post::PostSchema;
+use crate::services::post::PostService;
+use crate::utils::clock::Clock;
+use crate::utils::errors::AppError;
+use crate::utils::scoring::{trending_tags, TrendingTag};
+
+const TRENDING_TAGS_LIMIT: usize = 10;
+const EXPLORE_POSTS_LIMIT: i64 = 50;
+/// Gravity of the engagement velocity score, higher values favour fresh posts more.
+const EXPLORE_GRAVITY: f64 = 1.5;
+
+#[derive(Clone)]
+pub struct ExploreService {
+    pub repository: PostRepository,
+    pub post_service: PostService,
+    pub clock: Arc<dyn Clock>,
+    pub trending: Arc<RwLock<HashMap<TrendingWindow, Vec<TrendingTag>>>>,
+}
+
+impl ExploreService {
+    /// Recomputes trending tags for every window and replaces the cached ones.
+    pub async fn refresh_trending(&self) -> Result<(), AppError> {
+        let now = self.clock.now();
+        let longest = TrendingWindow::ALL
+            .iter()
+            .map(TrendingWindow::duration)
+            .max()
+            .unwrap_or_else(Duration::zero);
+        let uses = self.repository.find_tag_uses_since(now - longest).await?;
+
+        let trending = TrendingWindow::ALL
+            .into_iter()
+            .map(|window| (window, trending_tags(&uses, window.duration(), now)))
+            .collect();
+        *self.trending.write().await = trending;
+        Ok(())
+    }
+
+    /// Refreshes trending tags right away and then every `every`, until
+    /// `shutdown` turns `true`. A refresh that has started is finished first.
+    pub fn spawn_trending_refresh(
+        self,
+        every: std::time::Duration,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            while !*shutdown.borrow() {
+                tokio::select! {
+                    changed = shutdown.changed() => if changed.is_err() { break },
+                    _ = interval.tick() => {
+                        if let Err(e) = self.refresh_trending().await {
+                            tracing::error!("Can't refresh trending tags: {}", e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    pub async fn find_trending_tags(&self, window: TrendingWindow) -> Vec<TrendingTagSchema> {
+        self.trending
+            .read()
+            .await
+            .get(&window)
+            .map(|tags| {
+                tags.iter()
+                    .take(TRENDING_TAGS_LIMIT)
+                    .cloned()
+                    .map(TrendingTagSchema::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Popular recent posts from people the user doesn't follow.
+    pub async fn find_explore_posts(&self, user_id: &Uuid) -> Result<Vec<PostSchema>, AppError> {
+        let now = self.clock.now();
+        let posts = self
+            .repository
+            .find_explore(
+                user_id,
+                now - TrendingWindow::Day.duration(),
+                now,
+                EXPLORE_GRAVITY,
+                EXPLORE_POSTS_LIMIT,
+            )
+            .await?;
+        self.post_service.to_schemas(posts, user_id).await
+    }
+}
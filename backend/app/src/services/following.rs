@@ -1,15 +1,19 @@
+use serde_json::json;
 use validator::ValidateLength;
 
 use crate::{
-    models::following::Following, repositories::{
-        following::{CreateFollowingDTO, FollowingRepository},
-        Repository,
-    }, schemas::user::UserSchema, utils::errors::{AppError, Result}
+    models::{following::Following, webhook::WebhookEvent},
+    repositories::{following::FollowingRepository, RepoError},
+    schemas::user::UserSchema,
+    services::webhook::WebhookService,
+    utils::errors::{AppError, Result},
 };
 
 #[derive(Clone)]
 pub struct FollowingService {
     pub repository: FollowingRepository,
+    /// Tells the followed user's webhooks about new followers
+    pub webhook_service: WebhookService,
 }
 
 impl FollowingService {
@@ -18,47 +22,56 @@ impl FollowingService {
             return Err(AppError::CantFollowYourself);
         }
 
-        if let Some(_following) = self
+        let followed = self
             .repository
-            .find_one_by_from_and_to_ids(&from.id, &to.id)
+            .follow(&from.id, &to.id)
             .await
-        {
+            .map_err(|e| match e {
+                // The account was deleted after it was looked up
+                RepoError::ForeignKeyViolation(_) => AppError::EntityNotFound {
+                    entity: "User",
+                    id: to.id,
+                },
+                e => e.into(),
+            })?;
+        if !followed {
             return Err(AppError::AlreadyFollowed);
         }
-
-        let data = CreateFollowingDTO {
-            from_id: from.id,
-            to_id: to.id,
-        };
-        self.repository.create(data).await;
+        self.webhook_service
+            .emit(
+                WebhookEvent::UserFollowed,
+                &[to.id],
+                json!({ "follower": from, "followed_id": to.id }),
+            )
+            .await;
         Ok(())
     }
 
     pub async fn unfollow(&self, from: UserSchema, to: UserSchema) -> Result<()> {
-        match self
-            .repository
-            .find_one_by_from_and_to_ids(&from.id, &to.id)
-            .await
-        {
-            None => Err(AppError::NotFollowed),
-            Some(following) => {
-                self.repository.delete(&following.id).await;
-                Ok(())
-            }
+        if !self.repository.unfollow(&from.id, &to.id).await? {
+            return Err(AppError::NotFollowed);
         }
+        Ok(())
     }
 
     pub async fn get_followings_count(&self, user: UserSchema) -> Result<u64> {
-        let followings = self.repository.find_all_by_from_id(&user.id).await;
+        let followings = self.repository.find_all_by_from_id(&user.id).await?;
         Ok(followings.length().unwrap_or(0))
     }
 
     pub async fn get_followers_count(&self, user: UserSchema) -> Result<u64> {
-        let followings = self.repository.find_all_by_to_id(&user.id).await;
+        let followings = self.repository.find_all_by_to_id(&user.id).await?;
         Ok(followings.length().unwrap_or(0))
     }
 
-    pub async fn get_following_by_from_and_to_ids(&self, from: UserSchema, to: UserSchema) -> Option<Following> {
-        self.repository.find_one_by_from_and_to_ids(&from.id, &to.id).await
+    pub async fn get_following_by_from_and_to_ids(
+        &self,
+        from: UserSchema,
+        to: UserSchema,
+    ) -> Result<Option<Following>> {
+        Ok(self
+            .repository
+            .find_one_by_from_and_to_ids(&from.id, &to.id)
+            .await?)
     }
 }
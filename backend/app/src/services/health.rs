@@ -0,0 +1,51 @@
+use tokio::sync::watch;
+
+use crate::db::MIGRATOR;
+use crate::repositories::health::HealthRepository;
+use crate::schemas::health::{ReadinessSchema, VersionSchema};
+
+#[derive(Clone)]
+pub struct HealthService {
+    pub repository: HealthRepository,
+    /// Turns `true` when the server starts shutting down
+    pub shutdown: watch::Receiver<bool>,
+}
+
+impl HealthService {
+    /// The instance is ready when the database answers and has all the
+    /// migrations of this build, until it starts shutting down.
+    pub async fn readiness(&self) -> ReadinessSchema {
+        let shutting_down = *self.shutdown.borrow();
+        let applied = match self.repository.applied_migrations().await {
+            Ok(applied) => applied,
+            Err(e) => {
+                tracing::warn!("Readiness check failed: {}", e);
+                return ReadinessSchema {
+                    ready: false,
+                    database: false,
+                    shutting_down,
+                    pending_migrations: Vec::new(),
+                };
+            }
+        };
+        let pending_migrations: Vec<i64> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect();
+        ReadinessSchema {
+            ready: !shutting_down && pending_migrations.is_empty(),
+            database: true,
+            shutting_down,
+            pending_migrations,
+        }
+    }
+
+    pub fn version(&self) -> VersionSchema {
+        VersionSchema {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: option_env!("TWOTTY_GIT_SHA").map(String::from),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::models::import::Import;
+use crate::models::job::JobPayload;
+use crate::models::user::DELETED_USER_ID;
+use crate::repositories::following::FollowingRepository;
+use crate::repositories::import::{
+    CreateImportDTO, ImportFindAllParams, ImportProgressDTO, ImportRepository,
+};
+use crate::repositories::job::JobRepository;
+use crate::repositories::post::CreateImportedPostDTO;
+use crate::repositories::user::UserRepository;
+use crate::repositories::{RepoError, Repository};
+use crate::schemas::import::ImportSchema;
+use crate::services::media::MediaService;
+use crate::services::post::PostService;
+use crate::utils::errors::{AppError, Result};
+use crate::utils::heartbeat::with_heartbeat;
+use crate::utils::import::{detect, read_follows, read_posts};
+
+/// Running imports whose lock wasn't refreshed for this long are assumed to be
+/// abandoned and run again
+const STALE_AFTER_MINUTES: i64 = 5;
+/// How many posts and follows are imported between progress updates
+const PROGRESS_EVERY: i32 = 25;
+
+#[derive(Clone)]
+pub struct ImportService {
+    pub repository: ImportRepository,
+    pub user_repository: UserRepository,
+    pub following_repository: FollowingRepository,
+    /// Queues the running of requested imports
+    pub job_repository: JobRepository,
+    pub post_service: PostService,
+    /// Keeps the uploaded files until the import has run
+    pub media_service: MediaService,
+}
+
+impl ImportService {
+    /// Queues an import of an archive, a follows CSV or both. The archive is
+    /// only checked to come from a supported network here, it's read by the worker.
+    pub async fn request_import(
+        &self,
+        user_id: &Uuid,
+        archive: Option<&[u8]>,
+        follows: Option<&[u8]>,
+    ) -> Result<ImportSchema> {
+        if archive.is_none() && follows.is_none() {
+            return Err(AppError::NothingToImport);
+        }
+        let source = archive.map(detect).transpose()?;
+        if self
+            .repository
+            .find_all(ImportFindAllParams { user_id: *user_id })
+            .await?
+            .iter()
+            .any(Import::is_in_progress)
+        {
+            return Err(AppError::ImportInProgress);
+        }
+
+        let id = Uuid::new_v4();
+        let archive_key = archive.map(|_| format!("import_{}_archive", id));
+        let follows_key = follows.map(|_| format!("import_{}_follows.csv", id));
+        let stored = self
+            .store_uploads(
+                archive_key.as_deref().zip(archive),
+                follows_key.as_deref().zip(follows),
+            )
+            .await;
+        let created = match stored {
+            Ok(()) => self
+                .repository
+                .create(CreateImportDTO {
+                    id,
+                    user_id: *user_id,
+                    source: source.map(|kind| kind.as_str().to_string()),
+                    archive_key: archive_key.clone(),
+                    follows_key: follows_key.clone(),
+                })
+                .await
+                .map_err(|e| match e {
+                    // Another request started one in the meantime
+                    RepoError::UniqueViolation(_) => AppError::ImportInProgress,
+                    e => e.into(),
+                }),
+            Err(e) => Err(e),
+        };
+        let import = match created {
+            Ok(import) => import,
+            Err(e) => {
+                // Without an import nothing refers to the files, so they'd never be deleted
+                for key in [&archive_key, &follows_key].into_iter().flatten() {
+                    if let Err(e) = self.media_service.store.delete(key).await {
+                        tracing::error!("Can't delete a file of a failed import: {}", e);
+                    }
+                }
+                return Err(e);
+            }
+        };
+        self.job_repository
+            .enqueue(&JobPayload::RunImports, Utc::now().naive_utc())
+            .await?;
+        Ok(import.into())
+    }
+
+    /// Writes the uploaded archive and follows CSV to the store under their keys.
+    async fn store_uploads(
+        &self,
+        archive: Option<(&str, &[u8])>,
+        follows: Option<(&str, &[u8])>,
+    ) -> Result<()> {
+        if let Some((key, data)) = archive {
+            self.media_service
+                .store
+                .put(key, data, "application/octet-stream")
+                .await?;
+        }
+        if let Some((key, data)) = follows {
+            self.media_service.store.put(key, data, "text/csv").await?;
+        }
+        Ok(())
+    }
+
+    /// Finds an import of the user. Imports of other users are reported as missing.
+    pub async fn find_import(&self, id: &Uuid, user_id: &Uuid) -> Result<ImportSchema> {
+        self.repository
+            .find_one(id)
+            .await?
+            .filter(|import| import.user_id == *user_id)
+            .map(ImportSchema::from)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Import",
+                id: *id,
+            })
+    }
+
+    /// Runs pending imports one after another until none are left.
+    pub async fn run_pending(&self) -> Result<usize> {
+        let mut finished = 0;
+        loop {
+            let now = Utc::now().naive_utc();
+            let Some(import) = self
+                .repository
+                .claim_next(now, now - Duration::minutes(STALE_AFTER_MINUTES))
+                .await?
+            else {
+                return Ok(finished);
+            };
+            let result = with_heartbeat(self.run(&import), || {
+                self.repository
+                    .heartbeat(&import.id, Utc::now().naive_utc())
+            })
+            .await;
+            let error = match result {
+                Ok(()) => None,
+                Err(e) => {
+                    tracing::error!("Can't run import {}: {}", import.id, e);
+                    Some(e.to_string())
+                }
+            };
+            self.repository
+                .finish(&import.id, error.as_deref(), Utc::now().naive_utc())
+                .await?;
+            for key in [&import.archive_key, &import.follows_key]
+                .into_iter()
+                .flatten()
+            {
+                if let Err(e) = self.media_service.store.delete(key).await {
+                    tracing::error!("Can't delete uploaded file of import {}: {}", import.id, e);
+                }
+            }
+            finished += 1;
+        }
+    }
+
+    async fn run(&self, import: &Import) -> Result<()> {
+        let posts = match &import.archive_key {
+            Some(key) => {
+                let data = self.read_upload(key).await?;
+                tokio::task::spawn_blocking(move || read_posts(&data))
+                    .await??
+                    .1
+            }
+            None => Vec::new(),
+        };
+        let follows = match &import.follows_key {
+            Some(key) => read_follows(&self.read_upload(key).await?),
+            None => Vec::new(),
+        };
+        let mut progress = ImportProgressDTO {
+            total: (posts.len() + follows.len()) as i32,
+            processed: 0,
+            posts_created: 0,
+            posts_skipped: 0,
+            follows_created: 0,
+        };
+        self.update_progress(import, &progress).await?;
+
+        // Posts imported before are found by their source id, replies to them
+        // are linked on a re-run just like replies to posts imported now
+        let source_ids: Vec<String> = posts.iter().map(|post| post.source_id.clone()).collect();
+        let mut post_ids: HashMap<String, Uuid> = self
+            .post_service
+            .repository
+            .find_all_by_source_ids(&import.user_id, &source_ids)
+            .await?
+            .into_iter()
+            .filter_map(|post| Some((post.source_id?, post.id)))
+            .collect();
+        for post in posts {
+            let source_id = post.source_id.clone();
+            let imported = self
+                .post_service
+                .import_post(CreateImportedPostDTO {
+                    text: post.text,
+                    author_id: import.user_id,
+                    created_at: post.created_at,
+                    reply_to_id: post
+                        .reply_to
+                        .and_then(|reply_to| post_ids.get(&reply_to).copied()),
+                    source_id: post.source_id,
+                })
+                .await?;
+            match imported {
+                Some(post) => {
+                    post_ids.insert(source_id, post.id);
+                    progress.posts_created += 1;
+                }
+                None => progress.posts_skipped += 1,
+            }
+            progress.processed += 1;
+            if progress.processed % PROGRESS_EVERY == 0 {
+                self.update_progress(import, &progress).await?;
+            }
+        }
+
+        for username in follows {
+            if self.follow(&import.user_id, &username).await? {
+                progress.follows_created += 1;
+            }
+            progress.processed += 1;
+            if progress.processed % PROGRESS_EVERY == 0 {
+                self.update_progress(import, &progress).await?;
+            }
+        }
+        self.update_progress(import, &progress).await
+    }
+
+    async fn read_upload(&self, key: &str) -> Result<Vec<u8>> {
+        self.media_service
+            .store
+            .get(key)
+            .await?
+            .ok_or(AppError::ImportFileMissing)
+    }
+
+    async fn update_progress(&self, import: &Import, progress: &ImportProgressDTO) -> Result<()> {
+        Ok(self.repository.update(&import.id, progress.clone()).await?)
+    }
+
+    /// Follows the user with `username` if there is one. Returns `false` when
+    /// there isn't or they are followed already.
+    async fn follow(&self, user_id: &Uuid, username: &str) -> Result<bool> {
+        let Some(to) = self.user_repository.find_one_by_username(username).await? else {
+            return Ok(false);
+        };
+        if to.id == *user_id || to.id == DELETED_USER_ID {
+            return Ok(false);
+        }
+        match self.following_repository.follow(user_id, &to.id).await {
+            Ok(followed) => Ok(followed),
+            // The account was deleted after it was looked up
+            Err(RepoError::ForeignKeyViolation(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
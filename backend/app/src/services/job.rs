@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use url::Url;
+use uuid::Uuid;
+
+use crate::models::job::{Job, JobPayload};
+use crate::repositories::job::{JobFindAllParams, JobRepository};
+use crate::repositories::Repository;
+use crate::schemas::job::{JobSchema, JobsQuery};
+use crate::services::data_export::DataExportService;
+use crate::services::import::ImportService;
+use crate::services::link_preview::LinkPreviewService;
+use crate::services::post::PostService;
+use crate::services::user::UserService;
+use crate::services::webhook::WebhookService;
+use crate::utils::errors::{AppError, Result};
+use crate::utils::heartbeat::with_heartbeat;
+
+/// Running jobs whose lock wasn't refreshed for this long are assumed to be
+/// abandoned and run again
+const STALE_AFTER_MINUTES: i64 = 5;
+/// Longest wait between two attempts of a job
+const MAX_BACKOFF_MINUTES: i64 = 60;
+/// How long idle workers wait before looking for due jobs again
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct JobService {
+    pub repository: JobRepository,
+    pub post_service: PostService,
+    pub user_service: UserService,
+    pub link_preview_service: LinkPreviewService,
+    pub data_export_service: DataExportService,
+    pub import_service: ImportService,
+    pub webhook_service: WebhookService,
+    /// How many times a job is tried before it's moved to the failed jobs
+    pub max_attempts: i32,
+    /// Wait before the second attempt, doubled for every attempt after it
+    pub retry_backoff: Duration,
+    /// How often due scheduled posts are published
+    pub scheduler_interval: std::time::Duration,
+    /// Leases of the jobs the workers of this process are running
+    pub running: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+impl JobService {
+    /// Jobs in the queue, newest first.
+    pub async fn find_jobs(&self, query: JobsQuery) -> Result<Vec<JobSchema>> {
+        let jobs = self
+            .repository
+            .find_all(JobFindAllParams {
+                status: query.status,
+                limit: query.limit.unwrap_or(50).clamp(1, 200),
+            })
+            .await?;
+        Ok(jobs.into_iter().map(JobSchema::from).collect())
+    }
+
+    pub async fn find_job(&self, id: &Uuid) -> Result<JobSchema> {
+        self.repository
+            .find_one(id)
+            .await?
+            .map(JobSchema::from)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Job",
+                id: *id,
+            })
+    }
+
+    /// Queues a failed job again with fresh attempts.
+    pub async fn retry_job(&self, id: &Uuid) -> Result<JobSchema> {
+        match self.repository.retry(id, Utc::now().naive_utc()).await? {
+            Some(job) => Ok(job.into()),
+            None => {
+                self.find_job(id).await?;
+                Err(AppError::JobNotFailed)
+            }
+        }
+    }
+
+    /// Takes the next due job and runs it. Returns `false` when there was none.
+    pub async fn run_next(&self) -> Result<bool> {
+        let now = Utc::now().naive_utc();
+        let lease_id = Uuid::new_v4();
+        let Some(job) = self
+            .repository
+            .claim_next(now, now - Duration::minutes(STALE_AFTER_MINUTES), &lease_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        self.running.lock().await.insert(lease_id);
+        let result = with_heartbeat(self.run(&job, &lease_id), || {
+            self.repository
+                .heartbeat(&job.id, &lease_id, Utc::now().naive_utc())
+        })
+        .await;
+        self.running.lock().await.remove(&lease_id);
+        result?;
+        Ok(true)
+    }
+
+    /// Puts the jobs that are still running back in the queue, for when the
+    /// shutdown doesn't wait for them any longer.
+    pub async fn release_running(&self) -> Result<u64> {
+        let lease_ids: Vec<Uuid> = self.running.lock().await.drain().collect();
+        if lease_ids.is_empty() {
+            return Ok(0);
+        }
+        Ok(self
+            .repository
+            .release(&lease_ids, Utc::now().naive_utc())
+            .await?)
+    }
+
+    async fn run(&self, job: &Job, lease_id: &Uuid) -> Result<()> {
+        let result = match serde_json::from_value::<JobPayload>(job.payload.clone()) {
+            Ok(payload) => self.execute(payload).await,
+            Err(e) => {
+                // Retrying won't make the payload readable
+                tracing::error!("Can't read payload of job {}: {}", job.id, e);
+                let error = format!("Can't read payload: {}", e);
+                let held = self
+                    .repository
+                    .mark_failed(&job.id, lease_id, &error, Utc::now().naive_utc())
+                    .await?;
+                warn_if_lost(job, held);
+                return Ok(());
+            }
+        };
+        let held = match result {
+            Ok(()) => self.repository.complete(&job.id, lease_id).await?,
+            // Kept for the admins, unlike the message shown to users
+            Err(AppError::Database(e)) => self.fail(job, lease_id, &e.to_string()).await?,
+            Err(e) => self.fail(job, lease_id, &e.to_string()).await?,
+        };
+        warn_if_lost(job, held);
+        Ok(())
+    }
+
+    /// Retries the job after a backoff, or moves it to the failed jobs when
+    /// it ran out of attempts. Returns `false` when the lease was lost.
+    async fn fail(&self, job: &Job, lease_id: &Uuid, error: &str) -> Result<bool> {
+        let now = Utc::now().naive_utc();
+        if job.attempts >= self.max_attempts {
+            tracing::error!(
+                "Job {} ({}) failed after {} attempts: {}",
+                job.id,
+                job.kind,
+                job.attempts,
+                error
+            );
+            return Ok(self
+                .repository
+                .mark_failed(&job.id, lease_id, error, now)
+                .await?);
+        }
+        let backoff = (self.retry_backoff * 2i32.pow((job.attempts - 1).clamp(0, 16) as u32))
+            .min(Duration::minutes(MAX_BACKOFF_MINUTES));
+        tracing::warn!(
+            "Job {} ({}) failed, retrying in {}s: {}",
+            job.id,
+            job.kind,
+            backoff.num_seconds(),
+            error
+        );
+        Ok(self
+            .repository
+            .reschedule(&job.id, lease_id, error, now + backoff)
+            .await?)
+    }
+
+    async fn execute(&self, payload: JobPayload) -> Result<()> {
+        match payload {
+            JobPayload::FetchLinkPreview { url } => {
+                let Ok(url) = Url::parse(&url) else {
+                    tracing::warn!("Skipping link preview of invalid url {}", url);
+                    return Ok(());
+                };
+                self.link_preview_service.unfurl(&url).await?;
+            }
+            JobPayload::PublishScheduledPosts => {
+                let published = self.post_service.publish_scheduled().await?;
+                if published > 0 {
+                    tracing::info!("Published {} scheduled posts", published);
+                }
+            }
+            JobPayload::ClosePolls => {
+                let closed = self.post_service.close_polls().await?;
+                if closed > 0 {
+                    tracing::info!("Closed {} polls", closed);
+                }
+            }
+            JobPayload::PurgeTrash => {
+                let purged = self.post_service.purge_trash().await?;
+                if purged > 0 {
+                    tracing::info!("Purged {} posts from the trash", purged);
+                }
+            }
+            JobPayload::PurgeDeletedAccounts => {
+                let purged = self.user_service.purge_due_accounts().await?;
+                if purged > 0 {
+                    tracing::info!("Purged {} deleted accounts", purged);
+                }
+            }
+            JobPayload::BuildDataExports => {
+                let built = self.data_export_service.run_pending().await?;
+                if built > 0 {
+                    tracing::info!("Built {} data exports", built);
+                }
+            }
+            JobPayload::PurgeDataExports => {
+                let purged = self.data_export_service.purge_expired().await?;
+                if purged > 0 {
+                    tracing::info!("Deleted {} expired data exports", purged);
+                }
+            }
+            JobPayload::RunImports => {
+                let finished = self.import_service.run_pending().await?;
+                if finished > 0 {
+                    tracing::info!("Ran {} imports", finished);
+                }
+            }
+            JobPayload::DeliverWebhook { delivery_id } => {
+                self.webhook_service.deliver(&delivery_id).await?;
+            }
+            JobPayload::PurgeWebhookDeliveries => {
+                let purged = self.webhook_service.purge_deliveries().await?;
+                if purged > 0 {
+                    tracing::info!("Deleted {} old webhook deliveries", purged);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Spawns `count` workers that run due jobs until `shutdown` turns
+    /// `true`. A job a worker has started is finished before it stops.
+    pub fn spawn_workers(
+        &self,
+        count: usize,
+        shutdown: watch::Receiver<bool>,
+    ) -> Vec<JoinHandle<()>> {
+        (0..count.max(1))
+            .map(|_| {
+                let service = self.clone();
+                let mut shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    while !*shutdown.borrow() {
+                        match service.run_next().await {
+                            Ok(true) => continue,
+                            Ok(false) => {}
+                            Err(e) => tracing::error!("Can't run jobs: {}", e),
+                        }
+                        tokio::select! {
+                            changed = shutdown.changed() => if changed.is_err() { break },
+                            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Queues the recurring jobs right away and then on their intervals,
+    /// until `shutdown` turns `true`. Exports and imports are also queued
+    /// when they are requested, their interval only picks up abandoned ones.
+    pub fn spawn_scheduler(&self, shutdown: watch::Receiver<bool>) -> Vec<JoinHandle<()>> {
+        let hour = std::time::Duration::from_secs(60 * 60);
+        let minute = std::time::Duration::from_secs(60);
+        [
+            (JobPayload::PublishScheduledPosts, self.scheduler_interval),
+            (JobPayload::ClosePolls, self.scheduler_interval),
+            (JobPayload::PurgeTrash, hour),
+            (JobPayload::PurgeDeletedAccounts, hour),
+            (JobPayload::PurgeDataExports, hour),
+            (JobPayload::PurgeWebhookDeliveries, hour),
+            (JobPayload::BuildDataExports, minute),
+            (JobPayload::RunImports, minute),
+        ]
+        .into_iter()
+        .map(|(payload, every)| {
+            let repository = self.repository.clone();
+            let mut shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(every);
+                while !*shutdown.borrow() {
+                    tokio::select! {
+                        changed = shutdown.changed() => if changed.is_err() { break },
+                        _ = interval.tick() => {
+                            if let Err(e) = repository.enqueue(&payload, Utc::now().naive_utc()).await {
+                                tracing::error!("Can't queue {} job: {}", payload.kind(), e);
+                            }
+                        }
+                    }
+                }
+            })
+        })
+        .collect()
+    }
+}
+
+/// Logs a run that ended after its job was taken over by another worker.
+/// Its outcome is dropped, the run that took over decides what happens next.
+fn warn_if_lost(job: &Job, held: bool) {
+    if !held {
+        tracing::warn!(
+            "Job {} ({}) was taken over by another worker, dropping the outcome of this run",
+            job.id,
+            job.kind
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use url::Url;
+
+use crate::models::job::JobPayload;
+use crate::models::link_preview::LinkPreview;
+use crate::repositories::job::JobRepository;
+use crate::repositories::link_preview::{
+    CreateLinkPreviewDTO, LinkPreviewFindAllParams, LinkPreviewRepository,
+};
+use crate::repositories::{RepoError, Repository};
+use crate::utils::links::{find_urls, normalize_url};
+use crate::utils::unfurl::LinkFetcher;
+
+/// How long a fetched preview is used before the page is fetched again
+const PREVIEW_TTL_DAYS: i64 = 7;
+
+#[derive(Clone)]
+pub struct LinkPreviewService {
+    pub repository: LinkPreviewRepository,
+    pub fetcher: LinkFetcher,
+    /// Queues the fetching of links
+    pub job_repository: JobRepository,
+}
+
+impl LinkPreviewService {
+    /// Queues the first link of a post text for fetching, unless a fresh
+    /// preview of it is cached already. Posts are saved by then, so failing to
+    /// queue the fetch is logged instead of failing the request.
+    pub async fn enqueue(&self, text: &str) {
+        if let Err(e) = self.queue_fetch(text).await {
+            tracing::error!("Can't queue a link preview fetch: {}", e);
+        }
+    }
+
+    async fn queue_fetch(&self, text: &str) -> Result<(), RepoError> {
+        let Some(url) = find_urls(text).into_iter().next() else {
+            return Ok(());
+        };
+        if self.is_fresh(&normalize_url(&url)).await? {
+            return Ok(());
+        }
+        self.job_repository
+            .enqueue(
+                &JobPayload::FetchLinkPreview {
+                    url: url.to_string(),
+                },
+                Utc::now().naive_utc(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Cached previews by normalized url. Failed fetches are left out.
+    pub async fn find_previews(
+        &self,
+        urls: Vec<String>,
+    ) -> Result<HashMap<String, LinkPreview>, RepoError> {
+        Ok(self
+            .repository
+            .find_all(LinkPreviewFindAllParams { urls })
+            .await?
+            .into_iter()
+            .filter(|preview| preview.title.is_some())
+            .map(|preview| (preview.url.clone(), preview))
+            .collect())
+    }
+
+    async fn is_fresh(&self, url: &str) -> Result<bool, RepoError> {
+        Ok(self
+            .repository
+            .find_one(&url.to_string())
+            .await?
+            .is_some_and(|preview| {
+                Utc::now().naive_utc() - preview.fetched_at < Duration::days(PREVIEW_TTL_DAYS)
+            }))
+    }
+
+    /// Fetches the page and caches its metadata. Failures are cached as an
+    /// empty preview so broken links aren't retried on every post.
+    pub async fn unfurl(&self, url: &Url) -> Result<(), RepoError> {
+        let key = normalize_url(url);
+        if self.is_fresh(&key).await? {
+            return Ok(());
+        }
+        let metadata = match self.fetcher.fetch(url).await {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                tracing::info!("Can't fetch link preview for {}: {}", key, e);
+                Default::default()
+            }
+        };
+        self.repository
+            .create(CreateLinkPreviewDTO {
+                url: key,
+                title: metadata.title,
+                description: metadata.description,
+                image: metadata.image,
+                site_name: metadata.site_name,
+            })
+            .await?;
+        Ok(())
+    }
+}
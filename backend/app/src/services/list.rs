@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::models::list::List;
+use crate::repositories::list::{
+    AddMemberOutcome, CreateListDTO, ListFindAllParams, ListRepository, UpdateListDTO,
+};
+use crate::repositories::user::UserRepository;
+use crate::repositories::Repository;
+use crate::schemas::list::{
+    AddListMemberSchema, CreateListSchema, ListSchema, ListTimelineQuery, UpdateListSchema,
+};
+use crate::schemas::post::PostSchema;
+use crate::schemas::user::UserSchema;
+use crate::services::post::PostService;
+use crate::utils::errors::{AppError, AuthError, Result};
+
+/// Most accounts a single list can hold
+const MAX_LIST_MEMBERS: i64 = 500;
+
+#[derive(Clone)]
+pub struct ListService {
+    pub repository: ListRepository,
+    pub user_repository: UserRepository,
+    pub post_service: PostService,
+}
+
+impl ListService {
+    pub async fn create_list(&self, owner_id: &Uuid, data: CreateListSchema) -> Result<ListSchema> {
+        let name = data.name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::InvalidListName);
+        }
+        let list = self
+            .repository
+            .create(CreateListDTO {
+                owner_id: *owner_id,
+                name,
+                private: data.private,
+            })
+            .await?;
+        Ok(list.into())
+    }
+
+    /// Lists owned by the user. Private ones are only included for the owner.
+    pub async fn find_user_lists(
+        &self,
+        owner_id: &Uuid,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<ListSchema>> {
+        let lists = self
+            .repository
+            .find_all(ListFindAllParams {
+                owner_id: *owner_id,
+                public_only: owner_id != viewer_id,
+            })
+            .await?;
+        self.to_schemas(lists).await
+    }
+
+    pub async fn find_subscriptions(&self, user_id: &Uuid) -> Result<Vec<ListSchema>> {
+        let lists = self.repository.find_subscribed(user_id).await?;
+        self.to_schemas(lists).await
+    }
+
+    pub async fn find_list(&self, id: &Uuid, viewer_id: &Uuid) -> Result<ListSchema> {
+        let list = self.find_visible_list(id, viewer_id).await?;
+        Ok(self.to_schemas(vec![list]).await?.remove(0))
+    }
+
+    pub async fn update_list(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+        data: UpdateListSchema,
+    ) -> Result<ListSchema> {
+        self.find_own_list(id, user_id).await?;
+        let name = data.name.map(|name| name.trim().to_string());
+        if name.as_ref().is_some_and(|name| name.is_empty()) {
+            return Err(AppError::InvalidListName);
+        }
+        self.repository
+            .update(
+                id,
+                UpdateListDTO {
+                    name,
+                    private: data.private,
+                },
+            )
+            .await?;
+        self.find_list(id, user_id).await
+    }
+
+    pub async fn delete_list(&self, id: &Uuid, user_id: &Uuid) -> Result<()> {
+        self.find_own_list(id, user_id).await?;
+        self.repository.delete(id).await?;
+        Ok(())
+    }
+
+    pub async fn find_members(&self, id: &Uuid, viewer_id: &Uuid) -> Result<Vec<UserSchema>> {
+        self.find_visible_list(id, viewer_id).await?;
+        let ids = self.repository.find_member_ids(id).await?;
+        let mut users: HashMap<Uuid, UserSchema> = self
+            .user_repository
+            .find_all_by_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user.into()))
+            .collect();
+        Ok(ids.iter().filter_map(|id| users.remove(id)).collect())
+    }
+
+    pub async fn add_member(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+        data: AddListMemberSchema,
+    ) -> Result<()> {
+        self.find_own_list(id, user_id).await?;
+        self.user_repository
+            .find_one(&data.user_id)
+            .await?
+            .ok_or(AuthError::UserNotFound)?;
+        match self
+            .repository
+            .add_member(id, &data.user_id, MAX_LIST_MEMBERS)
+            .await?
+        {
+            AddMemberOutcome::Added => Ok(()),
+            AddMemberOutcome::AlreadyMember => Err(AppError::AlreadyListMember),
+            AddMemberOutcome::Full => Err(AppError::ListFull(MAX_LIST_MEMBERS)),
+        }
+    }
+
+    pub async fn remove_member(&self, id: &Uuid, user_id: &Uuid, member_id: &Uuid) -> Result<()> {
+        self.find_own_list(id, user_id).await?;
+        if !self.repository.remove_member(id, member_id).await? {
+            return Err(AppError::NotListMember);
+        }
+        Ok(())
+    }
+
+    /// Posts of the list members, newest first.
+    pub async fn find_timeline(
+        &self,
+        id: &Uuid,
+        viewer_id: &Uuid,
+        query: ListTimelineQuery,
+    ) -> Result<Vec<PostSchema>> {
+        self.find_visible_list(id, viewer_id).await?;
+        let posts = self
+            .repository
+            .find_timeline(
+                id,
+                viewer_id,
+                query.before,
+                query.limit.unwrap_or(20).clamp(1, 100),
+            )
+            .await?;
+        self.post_service.to_schemas(posts, viewer_id).await
+    }
+
+    /// Subscribes to a public list of another user.
+    pub async fn subscribe(&self, id: &Uuid, user_id: &Uuid) -> Result<()> {
+        let list = self.find_visible_list(id, user_id).await?;
+        if list.owner_id == *user_id {
+            return Err(AppError::CantSubscribeToOwnList);
+        }
+        if !self.repository.subscribe(id, user_id).await? {
+            return Err(AppError::AlreadySubscribed);
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, id: &Uuid, user_id: &Uuid) -> Result<()> {
+        if !self.repository.unsubscribe(id, user_id).await? {
+            return Err(AppError::NotSubscribed);
+        }
+        Ok(())
+    }
+
+    /// Private lists of other users are reported as missing.
+    async fn find_visible_list(&self, id: &Uuid, viewer_id: &Uuid) -> Result<List> {
+        self.repository
+            .find_one(id)
+            .await?
+            .filter(|list| !list.private || list.owner_id == *viewer_id)
+            .ok_or(AppError::EntityNotFound {
+                entity: "List",
+                id: *id,
+            })
+    }
+
+    async fn find_own_list(&self, id: &Uuid, user_id: &Uuid) -> Result<List> {
+        let list = self.find_visible_list(id, user_id).await?;
+        if list.owner_id != *user_id {
+            return Err(AppError::CantDoThis);
+        }
+        Ok(list)
+    }
+
+    async fn to_schemas(&self, lists: Vec<List>) -> Result<Vec<ListSchema>> {
+        let ids: Vec<Uuid> = lists.iter().map(|list| list.id).collect();
+        let counts: HashMap<Uuid, i64> = self
+            .repository
+            .count_members(&ids)
+            .await?
+            .into_iter()
+            .map(|count| (count.list_id, count.count))
+            .collect();
+        Ok(lists
+            .into_iter()
+            .map(|list| {
+                let mut schema = ListSchema::from(list);
+                schema.members_count = counts.get(&schema.id).copied().unwrap_or(0);
+                schema
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use image::ImageFormat;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::models::media::Media;
+use crate::repositories::media::{CreateMediaDTO, MediaRepository};
+use crate::repositories::Repository;
+use crate::schemas::media::MediaSchema;
+use crate::storage::MediaStore;
+use crate::utils::errors::{AppError, Result};
+use crate::utils::images::{process_image, ImageVariant, ProcessedImage, ProfileImage};
+
+/// Types accepted for upload. The type is sniffed from the file content, the
+/// `Content-Type` sent by the client is ignored.
+pub const ALLOWED_MIME_TYPES: [&str; 6] = [
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "video/mp4",
+    "video/webm",
+];
+
+#[derive(Clone)]
+pub struct MediaService {
+    pub repository: MediaRepository,
+    pub store: Arc<dyn MediaStore>,
+    /// Largest accepted upload, in bytes
+    pub max_size: usize,
+    /// Limits how many images are processed at once on the blocking pool
+    pub image_workers: Arc<Semaphore>,
+}
+
+impl MediaService {
+    pub async fn upload(&self, owner_id: &Uuid, data: &[u8]) -> Result<MediaSchema> {
+        let media = self.store_upload(owner_id, data, None).await?;
+        Ok(media.into())
+    }
+
+    /// Stores an avatar or banner cropped to its profile size.
+    /// Only still images are accepted.
+    pub async fn upload_profile_image(
+        &self,
+        owner_id: &Uuid,
+        data: &[u8],
+        kind: ProfileImage,
+    ) -> Result<Media> {
+        self.store_upload(owner_id, data, Some(kind.size())).await
+    }
+
+    /// Deletes the media record and all of its files.
+    pub async fn remove(&self, id: &Uuid) -> Result<()> {
+        let Some(media) = self.repository.find_one(id).await? else {
+            return Ok(());
+        };
+        self.repository.delete(id).await?;
+        self.delete_files(media).await
+    }
+
+    /// Deletes the stored files of media whose record is already gone.
+    pub async fn delete_files(&self, media: Media) -> Result<()> {
+        for key in [
+            Some(media.storage_key),
+            media.small_key,
+            media.thumbnail_key,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.store.delete(&key).await?;
+        }
+        Ok(())
+    }
+
+    async fn store_upload(
+        &self,
+        owner_id: &Uuid,
+        data: &[u8],
+        crop: Option<(u32, u32)>,
+    ) -> Result<Media> {
+        if data.len() > self.max_size {
+            return Err(AppError::MediaTooLarge);
+        }
+        let kind = infer::get(data)
+            .filter(|kind| ALLOWED_MIME_TYPES.contains(&kind.mime_type()))
+            .ok_or(AppError::UnsupportedMediaType)?;
+        let format = ImageFormat::from_mime_type(kind.mime_type());
+        if crop.is_some() && matches!(format, None | Some(ImageFormat::Gif)) {
+            return Err(AppError::UnsupportedMediaType);
+        }
+
+        let id = Uuid::new_v4();
+        let mime = kind.mime_type();
+        let mut dto = CreateMediaDTO {
+            id,
+            owner_id: *owner_id,
+            storage_key: format!("{}.{}", id, kind.extension()),
+            mime: mime.to_string(),
+            size: data.len() as i64,
+            width: None,
+            height: None,
+            blurhash: None,
+            small_key: None,
+            thumbnail_key: None,
+        };
+
+        let stored = self
+            .store_files(&mut dto, data, format, crop, kind.extension())
+            .await;
+        let keys: Vec<String> = [
+            Some(dto.storage_key.clone()),
+            dto.small_key.clone(),
+            dto.thumbnail_key.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let created = match stored {
+            Ok(()) => self.repository.create(dto).await.map_err(AppError::from),
+            Err(e) => Err(e),
+        };
+        if created.is_err() {
+            // Without a record nothing refers to the files, so they'd never be deleted
+            for key in keys {
+                if let Err(e) = self.store.delete(&key).await {
+                    tracing::error!("Can't delete a file of a failed upload: {}", e);
+                }
+            }
+        }
+        created
+    }
+
+    /// Writes the file and its resized copies to the store, filling in the
+    /// keys and image details of `dto` as it goes.
+    async fn store_files(
+        &self,
+        dto: &mut CreateMediaDTO,
+        data: &[u8],
+        format: Option<ImageFormat>,
+        crop: Option<(u32, u32)>,
+        extension: &str,
+    ) -> Result<()> {
+        let mime = dto.mime.as_str();
+        match format {
+            None => self.store.put(&dto.storage_key, data, mime).await?,
+            Some(format) => {
+                let image = self.process(data.to_vec(), format, crop).await?;
+                dto.width = Some(image.width as i32);
+                dto.height = Some(image.height as i32);
+                dto.blurhash = Some(image.blurhash);
+                if image.variants.is_empty() {
+                    self.store.put(&dto.storage_key, data, mime).await?;
+                }
+                for (variant, content) in image.variants {
+                    let key = format!("{}_{}.{}", dto.id, variant.name(), extension);
+                    match variant {
+                        ImageVariant::Original => {
+                            self.store.put(&dto.storage_key, &content, mime).await?;
+                            dto.size = content.len() as i64;
+                            continue;
+                        }
+                        ImageVariant::Small => dto.small_key = Some(key.clone()),
+                        ImageVariant::Thumbnail => dto.thumbnail_key = Some(key.clone()),
+                    }
+                    self.store.put(&key, &content, mime).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs image processing on the blocking pool so decoding large files
+    /// doesn't stall the async workers.
+    async fn process(
+        &self,
+        data: Vec<u8>,
+        format: ImageFormat,
+        crop: Option<(u32, u32)>,
+    ) -> Result<ProcessedImage> {
+        let _permit = self
+            .image_workers
+            .acquire()
+            .await
+            .map_err(|_| AppError::ImageWorkersClosed)?;
+        let image =
+            tokio::task::spawn_blocking(move || process_image(&data, format, crop)).await??;
+        Ok(image)
+    }
+
+    /// Loads the media record. Media of a post the viewer can't see isn't found.
+    pub async fn find_visible(&self, id: &Uuid, viewer_id: Option<&Uuid>) -> Result<Media> {
+        self.repository
+            .find_one_visible(id, viewer_id)
+            .await?
+            .ok_or(AppError::EntityNotFound {
+                entity: "Media",
+                id: *id,
+            })
+    }
+
+    /// Reads the content of the requested variant. Files without resized
+    /// copies serve the original instead.
+    pub async fn download(&self, media: &Media, variant: ImageVariant) -> Result<Vec<u8>> {
+        let key = match variant {
+            ImageVariant::Original => None,
+            ImageVariant::Small => media.small_key.as_ref(),
+            ImageVariant::Thumbnail => media.thumbnail_key.as_ref(),
+        }
+        .unwrap_or(&media.storage_key);
+        self.store.get(key).await?.ok_or(AppError::EntityNotFound {
+            entity: "Media",
+            id: media.id,
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::models::user::User;
+use crate::repositories::conversation::{ConversationRepository, CreateConversationDTO};
+use crate::repositories::following::FollowingRepository;
+use crate::repositories::message::{CreateMessageDTO, MessageFindAllParams, MessageRepository};
+use crate::repositories::user::UserRepository;
+use crate::repositories::Repository;
+use crate::schemas::message::{
+    ConversationSchema, CreateConversationSchema, MessageSchema, MessagesQuery, SendMessageSchema,
+};
+use crate::schemas::user::UserSchema;
+use crate::utils::errors::{AppError, Result};
+
+#[derive(Clone)]
+pub struct MessageService {
+    pub repository: MessageRepository,
+    pub conversation_repository: ConversationRepository,
+    pub following_repository: FollowingRepository,
+    pub user_repository: UserRepository,
+}
+
+impl MessageService {
+    pub async fn start_conversation(
+        &self,
+        data: CreateConversationSchema,
+        user: &UserSchema,
+    ) -> Result<ConversationSchema> {
+        let mut member_ids = data.member_ids;
+        member_ids.sort();
+        member_ids.dedup();
+        member_ids.retain(|id| *id != user.id);
+        if member_ids.is_empty() {
+            return Err(AppError::CantMessageYourself);
+        }
+
+        for id in &member_ids {
+            let recipient =
+                self.user_repository
+                    .find_one(id)
+                    .await?
+                    .ok_or(AppError::EntityNotFound {
+                        entity: "User",
+                        id: *id,
+                    })?;
+            self.ensure_can_message(&user.id, &recipient).await?;
+        }
+
+        if let [recipient_id] = member_ids.as_slice() {
+            if let Some(conversation) = self
+                .conversation_repository
+                .find_one_direct(&user.id, recipient_id)
+                .await?
+            {
+                return self.find_one_conversation(&conversation.id, &user.id).await;
+            }
+        }
+
+        member_ids.insert(0, user.id);
+        let conversation = self
+            .conversation_repository
+            .create(CreateConversationDTO { member_ids })
+            .await?;
+        self.find_one_conversation(&conversation.id, &user.id).await
+    }
+
+    pub async fn find_one_conversation(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<ConversationSchema> {
+        self.conversation_repository
+            .find_previews(user_id, Some(id))
+            .await?
+            .pop()
+            .map(ConversationSchema::from)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Conversation",
+                id: *id,
+            })
+    }
+
+    pub async fn find_all_conversations(&self, user_id: &Uuid) -> Result<Vec<ConversationSchema>> {
+        Ok(self
+            .conversation_repository
+            .find_previews(user_id, None)
+            .await?
+            .into_iter()
+            .map(ConversationSchema::from)
+            .collect())
+    }
+
+    pub async fn send_message(
+        &self,
+        conversation_id: &Uuid,
+        data: SendMessageSchema,
+        sender: &UserSchema,
+    ) -> Result<MessageSchema> {
+        let members = self
+            .conversation_repository
+            .find_members(conversation_id)
+            .await?;
+        if !members.iter().any(|member| member.user_id == sender.id) {
+            return Err(AppError::EntityNotFound {
+                entity: "Conversation",
+                id: *conversation_id,
+            });
+        }
+
+        for member in members.iter().filter(|member| member.user_id != sender.id) {
+            if let Some(recipient) = self.user_repository.find_one(&member.user_id).await? {
+                self.ensure_can_message(&sender.id, &recipient).await?;
+            }
+        }
+
+        let message = self
+            .repository
+            .create(CreateMessageDTO {
+                conversation_id: *conversation_id,
+                sender_id: sender.id,
+                text: data.text,
+            })
+            .await?;
+        self.conversation_repository
+            .mark_read(conversation_id, &sender.id, message.created_at)
+            .await?;
+        Ok(message.into())
+    }
+
+    pub async fn find_messages(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        query: MessagesQuery,
+    ) -> Result<Vec<MessageSchema>> {
+        self.ensure_member(conversation_id, user_id).await?;
+
+        let messages = self
+            .repository
+            .find_all(MessageFindAllParams {
+                conversation_id: *conversation_id,
+                before: query.before,
+                limit: query.limit.unwrap_or(50).clamp(1, 100),
+            })
+            .await?;
+        Ok(messages.into_iter().map(MessageSchema::from).collect())
+    }
+
+    pub async fn mark_read(&self, conversation_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        self.ensure_member(conversation_id, user_id).await?;
+        self.conversation_repository
+            .mark_read(conversation_id, user_id, Utc::now().naive_utc())
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_member(&self, conversation_id: &Uuid, user_id: &Uuid) -> Result<()> {
+        match self
+            .conversation_repository
+            .find_member(conversation_id, user_id)
+            .await?
+        {
+            None => Err(AppError::EntityNotFound {
+                entity: "Conversation",
+                id: *conversation_id,
+            }),
+            Some(_) => Ok(()),
+        }
+    }
+
+    async fn ensure_can_message(&self, sender_id: &Uuid, recipient: &User) -> Result<()> {
+        if !recipient.dms_from_followers_only {
+            return Ok(());
+        }
+
+        match self
+            .following_repository
+            .find_one_by_from_and_to_ids(sender_id, &recipient.id)
+            .await?
+        {
+            None => Err(AppError::MessagesRestricted),
+            Some(_) => Ok(()),
+        }
+    }
+}
@@ -1,3 +1,16 @@
+pub mod admin;
+pub mod bookmark;
+pub mod data_export;
+pub mod explore;
 pub mod following;
+pub mod health;
+pub mod import;
+pub mod job;
+pub mod link_preview;
+pub mod list;
+pub mod media;
+pub mod message;
 pub mod post;
+pub mod search;
 pub mod user;
+pub mod webhook;
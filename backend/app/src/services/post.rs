@@ -1,13 +1,47 @@
-use crate::repositories::post::{CreatePostDTO, PostFindAllParams, PostRepository, UpdatePostDTO};
-use crate::repositories::Repository;
-use crate::schemas::post::{CreatePostSchema, PostSchema, UpdatePostSchema};
+use crate::models::post::Post;
+use crate::models::webhook::WebhookEvent;
+use crate::repositories::media::MediaFindAllParams;
+use crate::repositories::poll::{CreatePollDTO, PollFindAllParams, PollRepository};
+use crate::repositories::post::{
+    CreateImportedPostDTO, CreatePostDTO, PostFindAllParams, PostRepository, UpdatePostDTO,
+};
+use crate::repositories::user::UserRepository;
+use crate::repositories::{Repository, UnitOfWork};
+use crate::schemas::media::MediaSchema;
+use crate::schemas::poll::{PollOptionSchema, PollSchema, VoteSchema};
+use crate::schemas::post::{
+    CreatePostSchema, EntitySchema, LinkCardSchema, PostRevisionSchema, PostSchema,
+    UpdatePostSchema,
+};
+use crate::services::link_preview::LinkPreviewService;
+use crate::services::media::MediaService;
+use crate::services::webhook::WebhookService;
+use crate::utils::entities::{normalize_tag, parse_entities, EntityKind};
 use crate::utils::errors::AppError;
+use crate::utils::links::first_link;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde_json::json;
+use std::collections::HashMap;
 use uuid::Uuid;
 use validator::ValidateLength;
 
 #[derive(Clone)]
 pub struct PostService {
     pub repository: PostRepository,
+    pub user_repository: UserRepository,
+    pub media_service: MediaService,
+    pub poll_repository: PollRepository,
+    pub link_preview_service: LinkPreviewService,
+    /// Tells webhooks about published and deleted posts
+    pub webhook_service: WebhookService,
+    /// How long after creation a post can still be edited
+    pub edit_window: Duration,
+    /// How long deleted posts stay in the trash before they are purged
+    pub trash_retention: Duration,
+    /// How many posts a user can pin to their profile
+    pub max_pinned: i64,
+    /// Most characters in the text of a post
+    pub max_length: usize,
 }
 
 impl PostService {
@@ -16,59 +50,667 @@ impl PostService {
         data: CreatePostSchema,
         author_id: &Uuid,
     ) -> Result<PostSchema, AppError> {
-        if !data.text.validate_length(Some(1), Some(256), None) {
-            return Err(AppError::TextTooLong);
+        self.check_length(&data.text)?;
+        self.check_media(&data.media_ids, author_id).await?;
+        if let Some(poll) = &data.poll {
+            if poll
+                .options
+                .iter()
+                .any(|option| !option.trim().validate_length(Some(1), Some(50), None))
+            {
+                return Err(AppError::InvalidPollOption);
+            }
         }
+        if let Some(scheduled_at) = data.scheduled_at {
+            if data.draft || scheduled_at <= Utc::now().naive_utc() {
+                return Err(AppError::InvalidSchedule);
+            }
+        }
+
         let dto = CreatePostDTO {
             text: data.text,
             author_id: *author_id,
+            draft: data.draft,
+            scheduled_at: data.scheduled_at,
         };
-        let post: PostSchema = self.repository.create(dto).await.into();
-        Ok(post)
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        let post = self.repository.create_in(&mut uow, dto).await?;
+        self.store_entities(&mut uow, &post).await?;
+        if !data.media_ids.is_empty() {
+            // Another post may have taken the media since it was checked
+            let attached = self
+                .media_service
+                .repository
+                .attach_to_post(&mut uow, &post.id, author_id, &data.media_ids)
+                .await?;
+            if let Some(id) = data.media_ids.iter().find(|id| !attached.contains(id)) {
+                return Err(AppError::InvalidMedia(*id));
+            }
+        }
+        if let Some(poll) = data.poll {
+            self.poll_repository
+                .create_in(
+                    &mut uow,
+                    CreatePollDTO {
+                        post_id: post.id,
+                        multiple: poll.multiple,
+                        expires_at: post.created_at + Duration::minutes(poll.duration_minutes),
+                        options: poll
+                            .options
+                            .into_iter()
+                            .map(|option| option.trim().to_string())
+                            .collect(),
+                    },
+                )
+                .await?;
+        }
+        uow.commit().await?;
+
+        self.link_preview_service.enqueue(&post.text).await;
+        if post.is_published() {
+            self.announce(std::slice::from_ref(&post)).await;
+        }
+        Ok(self.to_schemas(vec![post], author_id).await?.remove(0))
     }
 
-    pub async fn find_one_post(&self, id: &Uuid) -> Result<PostSchema, AppError> {
-        match self.repository.find_one(id).await {
+    /// Creates a post brought over from another network, published at its
+    /// original time. Returns `None` when the post is skipped because its text
+    /// doesn't fit or it was imported before. Links aren't previewed.
+    pub async fn import_post(&self, data: CreateImportedPostDTO) -> Result<Option<Post>, AppError> {
+        if self.check_length(&data.text).is_err() {
+            return Ok(None);
+        }
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        let Some(post) = self.repository.create_imported(&mut uow, data).await? else {
+            return Ok(None);
+        };
+        self.store_entities(&mut uow, &post).await?;
+        uow.commit().await?;
+        Ok(Some(post))
+    }
+
+    pub async fn find_one_post(&self, id: &Uuid, viewer_id: &Uuid) -> Result<PostSchema, AppError> {
+        match self.repository.find_one_for(id, viewer_id).await? {
             None => Err(AppError::EntityNotFound {
                 entity: "Post",
                 id: *id,
             }),
-            Some(post) => Ok(post.into()),
+            Some(post) => Ok(self.to_schemas(vec![post], viewer_id).await?.remove(0)),
         }
     }
 
-    pub async fn find_all_posts(&self, author_id: &Uuid) -> Vec<PostSchema> {
+    pub async fn find_all_posts(
+        &self,
+        author_id: &Uuid,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<PostSchema>, AppError> {
         let posts = self
             .repository
             .find_all(PostFindAllParams {
                 author_id: *author_id,
+                viewer_id: *viewer_id,
             })
-            .await;
-        posts.iter().map(|post| post.into()).collect()
+            .await?;
+        self.to_schemas(posts, viewer_id).await
     }
 
-    pub async fn delete_post(&self, id: &Uuid, user_id: &Uuid) -> Result<(), AppError> {
-        let post = self.find_one_post(&id).await?;
+    pub async fn find_posts_by_tag(
+        &self,
+        tag: &str,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<PostSchema>, AppError> {
+        let posts = self
+            .repository
+            .find_all_by_tag(&normalize_tag(tag), viewer_id)
+            .await?;
+        self.to_schemas(posts, viewer_id).await
+    }
+
+    /// Unpublished posts of the user, soonest first.
+    pub async fn find_scheduled(&self, author_id: &Uuid) -> Result<Vec<PostSchema>, AppError> {
+        let posts = self.repository.find_all_scheduled(author_id).await?;
+        self.to_schemas(posts, author_id).await
+    }
+
+    pub async fn find_drafts(&self, author_id: &Uuid) -> Result<Vec<PostSchema>, AppError> {
+        let posts = self.repository.find_all_drafts(author_id).await?;
+        self.to_schemas(posts, author_id).await
+    }
+
+    /// Finds a post for its author to change. Unpublished posts of other
+    /// users are reported as missing.
+    async fn find_own_post(&self, id: &Uuid, user_id: &Uuid) -> Result<Post, AppError> {
+        let post = self
+            .repository
+            .find_one_unpublished(id)
+            .await?
+            .filter(|post| post.is_published() || post.author_id == *user_id)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Post",
+                id: *id,
+            })?;
         if post.author_id != *user_id {
             return Err(AppError::CantDoThis);
         }
-        self.repository.delete(&id).await;
+        Ok(post)
+    }
+
+    /// Publishes a draft or scheduled post right away.
+    pub async fn publish_post(&self, id: &Uuid, user_id: &Uuid) -> Result<PostSchema, AppError> {
+        let post = self.find_own_post(id, user_id).await?;
+        if post.is_published() {
+            return Err(AppError::AlreadyPublished);
+        }
+        if !self.repository.publish(id, Utc::now().naive_utc()).await? {
+            return Err(AppError::AlreadyPublished);
+        }
+        if let Some(post) = self.repository.find_one(id).await? {
+            self.announce(&[post]).await;
+        }
+        self.find_one_post(id, user_id).await
+    }
+
+    /// Publishes scheduled posts that are due, in batches.
+    pub async fn publish_scheduled(&self) -> Result<usize, AppError> {
+        const BATCH_SIZE: i64 = 100;
+        let mut published = 0;
+        loop {
+            let posts = self
+                .repository
+                .publish_due(Utc::now().naive_utc(), BATCH_SIZE)
+                .await?;
+            self.announce(&posts).await;
+            published += posts.len();
+            if (posts.len() as i64) < BATCH_SIZE {
+                return Ok(published);
+            }
+        }
+    }
+
+    /// Closes expired polls in batches and sends their results to the
+    /// authors as a `poll.closed` webhook event.
+    pub async fn close_polls(&self) -> Result<usize, AppError> {
+        const BATCH_SIZE: i64 = 100;
+        let mut closed = 0;
+        loop {
+            let polls = self
+                .poll_repository
+                .close_due(Utc::now().naive_utc(), BATCH_SIZE)
+                .await?;
+            for poll in &polls {
+                if let Err(e) = self.notify_poll_closed(&poll.post_id).await {
+                    tracing::error!("Can't notify the author of poll {}: {}", poll.post_id, e);
+                }
+            }
+            closed += polls.len();
+            if (polls.len() as i64) < BATCH_SIZE {
+                return Ok(closed);
+            }
+        }
+    }
+
+    /// Pins a published post to the top of its author's profile.
+    pub async fn pin_post(&self, id: &Uuid, user_id: &Uuid) -> Result<PostSchema, AppError> {
+        let post = self.find_own_post(id, user_id).await?;
+        if !post.is_published() {
+            return Err(AppError::CantPinUnpublished);
+        }
+        if post.pinned_at.is_none()
+            && !self
+                .repository
+                .pin(id, &post.author_id, Utc::now().naive_utc(), self.max_pinned)
+                .await?
+        {
+            return Err(AppError::TooManyPinnedPosts(self.max_pinned));
+        }
+        self.find_one_post(id, user_id).await
+    }
+
+    pub async fn unpin_post(&self, id: &Uuid, user_id: &Uuid) -> Result<(), AppError> {
+        self.find_own_post(id, user_id).await?;
+        self.repository.unpin(id).await?;
+        Ok(())
+    }
+
+    /// Moves the post to the trash. Unpublished posts are cancelled this way.
+    pub async fn delete_post(&self, id: &Uuid, user_id: &Uuid) -> Result<(), AppError> {
+        let post = self.find_own_post(id, user_id).await?;
+        self.repository.delete(id).await?;
+        if post.is_published() {
+            self.webhook_service
+                .emit(
+                    WebhookEvent::PostDeleted,
+                    &[post.author_id],
+                    json!({ "post_id": post.id, "author_id": post.author_id }),
+                )
+                .await;
+        }
         Ok(())
     }
 
+    pub async fn find_trash(&self, author_id: &Uuid) -> Result<Vec<PostSchema>, AppError> {
+        let posts = self.repository.find_all_deleted(author_id).await?;
+        self.to_schemas(posts, author_id).await
+    }
+
+    pub async fn restore_post(&self, id: &Uuid, user_id: &Uuid) -> Result<PostSchema, AppError> {
+        let mut post =
+            self.repository
+                .find_one_deleted(id)
+                .await?
+                .ok_or(AppError::EntityNotFound {
+                    entity: "Post",
+                    id: *id,
+                })?;
+        if post.author_id != *user_id {
+            return Err(AppError::CantDoThis);
+        }
+        if !self.repository.restore(id).await? {
+            return Err(AppError::EntityNotFound {
+                entity: "Post",
+                id: *id,
+            });
+        }
+        post.deleted_at = None;
+        Ok(self.to_schemas(vec![post], user_id).await?.remove(0))
+    }
+
+    /// Permanently deletes posts that have been in the trash for longer than the retention period.
+    pub async fn purge_trash(&self) -> Result<u64, AppError> {
+        self.purge_trash_before(Utc::now().naive_utc() - self.trash_retention)
+            .await
+    }
+
+    /// Permanently deletes posts moved to the trash before `before`, with
+    /// their media and its files.
+    pub async fn purge_trash_before(&self, before: NaiveDateTime) -> Result<u64, AppError> {
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        let ids = self
+            .repository
+            .lock_deleted_before(&mut uow, before)
+            .await?;
+        let media = self
+            .media_service
+            .repository
+            .delete_all_by_posts(&mut uow, &ids)
+            .await?;
+        let purged = self.repository.delete_all_by_ids(&mut uow, &ids).await?;
+        uow.commit().await?;
+
+        for media in media {
+            if let Err(e) = self.media_service.delete_files(media).await {
+                tracing::error!("Can't delete files of a purged post: {}", e);
+            }
+        }
+        Ok(purged)
+    }
+
     pub async fn update_post(
         &self,
         id: &Uuid,
         data: UpdatePostSchema,
         author_id: &Uuid,
     ) -> Result<(), AppError> {
-        let post = self.find_one_post(&id).await?;
-        if post.author_id != *author_id {
-            return Err(AppError::CantDoThis);
+        if let Some(text) = &data.text {
+            self.check_length(text)?;
+        }
+        let post = self.find_own_post(id, author_id).await?;
+        if post.is_published() {
+            if data.scheduled_at.is_some() || data.draft.is_some() {
+                return Err(AppError::AlreadyPublished);
+            }
+            if Utc::now().naive_utc() - post.created_at > self.edit_window {
+                return Err(AppError::EditWindowExpired);
+            }
+        }
+        if let Some(scheduled_at) = data.scheduled_at {
+            if data.draft == Some(true) || scheduled_at <= Utc::now().naive_utc() {
+                return Err(AppError::InvalidSchedule);
+            }
         }
 
+        let text_changed = data.text.is_some();
         let dto = UpdatePostDTO { text: data.text };
-        self.repository.update(&id, dto).await;
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        if let Some(scheduled_at) = data.scheduled_at {
+            self.repository
+                .schedule(&mut uow, id, Some(scheduled_at))
+                .await?;
+        } else if data.draft == Some(true) {
+            self.repository.schedule(&mut uow, id, None).await?;
+        }
+        self.repository.update_in(&mut uow, id, dto).await?;
+        let post = if text_changed {
+            self.repository.lock(&mut uow, id).await?
+        } else {
+            None
+        };
+        if let Some(post) = &post {
+            self.store_entities(&mut uow, post).await?;
+        }
+        uow.commit().await?;
+
+        if let Some(post) = post {
+            self.link_preview_service.enqueue(&post.text).await;
+        }
+        Ok(())
+    }
+
+    pub async fn find_revisions(
+        &self,
+        id: &Uuid,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<PostRevisionSchema>, AppError> {
+        self.find_one_post(id, viewer_id).await?;
+        let revisions = self.repository.find_revisions(id).await?;
+        Ok(revisions
+            .into_iter()
+            .map(PostRevisionSchema::from)
+            .collect())
+    }
+
+    pub async fn vote(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+        data: VoteSchema,
+    ) -> Result<PollSchema, AppError> {
+        self.find_one_post(id, user_id).await?;
+        let poll = self
+            .poll_repository
+            .find_one(id)
+            .await?
+            .ok_or(AppError::EntityNotFound {
+                entity: "Poll",
+                id: *id,
+            })?;
+        if Utc::now().naive_utc() >= poll.expires_at {
+            return Err(AppError::PollClosed);
+        }
+
+        let option_count = self.poll_repository.find_options(&[*id]).await?.len() as i16;
+        let mut options = data.options;
+        options.sort();
+        options.dedup();
+        if options
+            .iter()
+            .any(|option| !(0..option_count).contains(option))
+            || (!poll.multiple && options.len() != 1)
+        {
+            return Err(AppError::InvalidPollVote);
+        }
+        if !self.poll_repository.vote(id, user_id, &options).await? {
+            return Err(AppError::AlreadyVoted);
+        }
+
+        self.find_one_post(id, user_id)
+            .await?
+            .poll
+            .ok_or(AppError::EntityNotFound {
+                entity: "Poll",
+                id: *id,
+            })
+    }
+
+    /// Post texts can't be empty or longer than `max_length` characters.
+    fn check_length(&self, text: &str) -> Result<(), AppError> {
+        if !text.validate_length(Some(1), Some(self.max_length as u64), None) {
+            return Err(AppError::TextTooLong(self.max_length));
+        }
+        Ok(())
+    }
+
+    /// Media can only be attached once, and only by the user who uploaded it.
+    /// Avatars and banners can't be attached.
+    async fn check_media(&self, ids: &[Uuid], author_id: &Uuid) -> Result<(), AppError> {
+        let media = self.media_service.repository.find_all_by_ids(ids).await?;
+        let profile_images = match self.user_repository.find_one(author_id).await? {
+            Some(user) => vec![user.avatar_media_id, user.banner_media_id],
+            None => vec![],
+        };
+        for (i, id) in ids.iter().enumerate() {
+            let attachable = !ids[..i].contains(id)
+                && !profile_images.contains(&Some(*id))
+                && media
+                    .iter()
+                    .any(|m| m.id == *id && m.owner_id == *author_id && m.post_id.is_none());
+            if !attachable {
+                return Err(AppError::InvalidMedia(*id));
+            }
+        }
         Ok(())
     }
+
+    /// Parses mentions and hashtags out of the post text and stores them.
+    /// Mentions of unknown usernames are ignored.
+    async fn store_entities(&self, uow: &mut UnitOfWork, post: &Post) -> Result<(), AppError> {
+        let mut user_ids = Vec::new();
+        let mut tags = Vec::new();
+        for entity in parse_entities(&post.text) {
+            match entity.kind {
+                EntityKind::Mention => {
+                    if let Some(user) = self
+                        .user_repository
+                        .find_one_by_username(&entity.value)
+                        .await?
+                    {
+                        user_ids.push(user.id);
+                    }
+                }
+                EntityKind::Hashtag => tags.push(normalize_tag(&entity.value)),
+            }
+        }
+        user_ids.sort();
+        user_ids.dedup();
+        tags.sort();
+        tags.dedup();
+
+        self.repository
+            .set_mentions(uow, &post.id, &user_ids)
+            .await?;
+        self.repository.set_tags(uow, &post.id, &tags).await?;
+        Ok(())
+    }
+
+    /// Sends the author a `poll.closed` event with the post and its final
+    /// results. The post may have been deleted since the poll closed.
+    async fn notify_poll_closed(&self, post_id: &Uuid) -> Result<(), AppError> {
+        let Some(post) = self.repository.find_one(post_id).await? else {
+            return Ok(());
+        };
+        let author_id = post.author_id;
+        let schema = self.to_schemas(vec![post], &author_id).await?;
+        self.webhook_service
+            .emit(
+                WebhookEvent::PollClosed,
+                &[author_id],
+                json!({ "post": schema[0] }),
+            )
+            .await;
+        Ok(())
+    }
+
+    /// Tells webhooks about newly published posts and the users they mention.
+    /// The posts are saved already, so failures are only logged.
+    async fn announce(&self, posts: &[Post]) {
+        let ids: Vec<Uuid> = posts.iter().map(|post| post.id).collect();
+        let mentions = match self.repository.find_mentions(&ids).await {
+            Ok(mentions) => mentions,
+            Err(e) => {
+                tracing::error!("Can't announce published posts: {}", e);
+                return;
+            }
+        };
+        for post in posts {
+            let schema = PostSchema::from(post.clone());
+            self.webhook_service
+                .emit(
+                    WebhookEvent::PostCreated,
+                    &[post.author_id],
+                    json!({ "post": schema }),
+                )
+                .await;
+            for mention in mentions
+                .iter()
+                .filter(|m| m.post_id == post.id && m.user_id != post.author_id)
+            {
+                self.webhook_service
+                    .emit(
+                        WebhookEvent::Mention,
+                        &[mention.user_id],
+                        json!({ "post": schema, "mentioned_user_id": mention.user_id }),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// Converts posts to schemas, linking mentions to the users stored for each post.
+    /// Poll results and bookmarks are shown as `viewer_id` is allowed to see them.
+    pub async fn to_schemas(
+        &self,
+        posts: Vec<Post>,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<PostSchema>, AppError> {
+        let ids: Vec<Uuid> = posts.iter().map(|post| post.id).collect();
+        let mentions = self.repository.find_mentions(&ids).await?;
+        let mut media: HashMap<Uuid, Vec<MediaSchema>> = HashMap::new();
+        for m in self
+            .media_service
+            .repository
+            .find_all(MediaFindAllParams {
+                post_ids: ids.clone(),
+            })
+            .await?
+        {
+            if let Some(post_id) = m.post_id {
+                media.entry(post_id).or_default().push(m.into());
+            }
+        }
+        let links: Vec<Option<String>> = posts.iter().map(|post| first_link(&post.text)).collect();
+        let cards = self
+            .link_preview_service
+            .find_previews(links.iter().flatten().cloned().collect())
+            .await?;
+        let mut polls = self.poll_schemas(&ids, viewer_id).await?;
+        let parent_ids: Vec<Uuid> = posts.iter().filter_map(|post| post.reply_to_id).collect();
+        let parents = self.repository.find_existing(&parent_ids).await?;
+        let revision_counts: HashMap<Uuid, i64> = self
+            .repository
+            .count_revisions(&ids)
+            .await?
+            .into_iter()
+            .map(|count| (count.post_id, count.count))
+            .collect();
+
+        Ok(posts
+            .into_iter()
+            .zip(links)
+            .map(|(post, link)| {
+                let entities = parse_entities(&post.text)
+                    .into_iter()
+                    .filter_map(|entity| {
+                        let user_id = match entity.kind {
+                            EntityKind::Mention => Some(
+                                mentions
+                                    .iter()
+                                    .find(|m| {
+                                        m.post_id == post.id
+                                            && m.username.to_lowercase()
+                                                == entity.value.to_lowercase()
+                                    })?
+                                    .user_id,
+                            ),
+                            EntityKind::Hashtag => None,
+                        };
+                        Some(EntitySchema::new(entity, user_id))
+                    })
+                    .collect();
+
+                let mut schema = PostSchema::from(post);
+                schema.revision_count = revision_counts.get(&schema.id).copied().unwrap_or(0);
+                schema.entities = entities;
+                schema.media = media.remove(&schema.id).unwrap_or_default();
+                schema.card = link
+                    .and_then(|link| cards.get(&link).cloned())
+                    .map(LinkCardSchema::from);
+                schema.poll = polls.remove(&schema.id);
+                schema.reply_to_deleted = schema
+                    .reply_to_id
+                    .is_some_and(|parent_id| !parents.contains(&parent_id));
+                schema
+            })
+            .collect())
+    }
+
+    /// Vote counts are only included once the viewer has voted or the poll is closed.
+    async fn poll_schemas(
+        &self,
+        ids: &[Uuid],
+        viewer_id: &Uuid,
+    ) -> Result<HashMap<Uuid, PollSchema>, AppError> {
+        let polls = self
+            .poll_repository
+            .find_all(PollFindAllParams {
+                post_ids: ids.to_vec(),
+            })
+            .await?;
+        if polls.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let poll_ids: Vec<Uuid> = polls.iter().map(|poll| poll.post_id).collect();
+        let options = self.poll_repository.find_options(&poll_ids).await?;
+        let counts = self.poll_repository.count_votes(&poll_ids).await?;
+        let voters: HashMap<Uuid, i64> = self
+            .poll_repository
+            .count_voters(&poll_ids)
+            .await?
+            .into_iter()
+            .map(|count| (count.post_id, count.count))
+            .collect();
+        let votes = self
+            .poll_repository
+            .find_votes_by_user(&poll_ids, viewer_id)
+            .await?;
+        let now = Utc::now().naive_utc();
+
+        Ok(polls
+            .into_iter()
+            .map(|poll| {
+                let closed = now >= poll.expires_at;
+                let voted_options = votes
+                    .iter()
+                    .find(|vote| vote.post_id == poll.post_id)
+                    .map(|vote| vote.options.clone());
+                let show_results = closed || voted_options.is_some();
+                let options = options
+                    .iter()
+                    .filter(|option| option.post_id == poll.post_id)
+                    .map(|option| PollOptionSchema {
+                        position: option.position,
+                        text: option.text.clone(),
+                        votes: show_results.then(|| {
+                            counts
+                                .iter()
+                                .find(|count| {
+                                    count.post_id == poll.post_id
+                                        && count.position == option.position
+                                })
+                                .map_or(0, |count| count.count)
+                        }),
+                    })
+                    .collect();
+                let schema = PollSchema {
+                    options,
+                    multiple: poll.multiple,
+                    expires_at: poll.expires_at,
+                    closed,
+                    voters_count: show_results
+                        .then(|| voters.get(&poll.post_id).copied().unwrap_or(0)),
+                    voted_options: voted_options.unwrap_or_default(),
+                };
+                (poll.post_id, schema)
+            })
+            .collect())
+    }
 }
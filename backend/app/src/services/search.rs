@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::repositories::post::PostRepository;
+use crate::repositories::user::UserRepository;
+use crate::schemas::search::{
+    PostSearchHitSchema, SearchQuery, SearchResultsSchema, SearchType, UserSearchHitSchema,
+};
+use crate::schemas::user::UserSchema;
+use crate::services::post::PostService;
+use crate::utils::errors::{AppError, Result};
+
+#[derive(Clone)]
+pub struct SearchService {
+    pub post_repository: PostRepository,
+    pub user_repository: UserRepository,
+    pub post_service: PostService,
+}
+
+impl SearchService {
+    pub async fn search(
+        &self,
+        query: SearchQuery,
+        viewer_id: &Uuid,
+    ) -> Result<SearchResultsSchema> {
+        let q = query.q.trim();
+        if q.is_empty() {
+            return Err(AppError::EmptySearchQuery);
+        }
+        let limit = query.limit.unwrap_or(20).clamp(1, 50);
+        let offset = query.offset.unwrap_or(0).max(0);
+
+        let mut results = SearchResultsSchema::default();
+        if query.kind != Some(SearchType::Users) {
+            results.posts = self.search_posts(q, limit, offset, viewer_id).await?;
+        }
+        if query.kind != Some(SearchType::Posts) {
+            results.users = self.search_users(q, limit, offset).await?;
+        }
+        Ok(results)
+    }
+
+    async fn search_posts(
+        &self,
+        q: &str,
+        limit: i64,
+        offset: i64,
+        viewer_id: &Uuid,
+    ) -> Result<Vec<PostSearchHitSchema>> {
+        let hits = self.post_repository.search(q, limit, offset).await?;
+        let ids: Vec<Uuid> = hits.iter().map(|hit| hit.id).collect();
+        let posts = self
+            .post_repository
+            .find_all_by_ids(&ids, viewer_id)
+            .await?;
+        let mut posts: HashMap<Uuid, _> = self
+            .post_service
+            .to_schemas(posts, viewer_id)
+            .await?
+            .into_iter()
+            .map(|post| (post.id, post))
+            .collect();
+
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| {
+                Some(PostSearchHitSchema {
+                    post: posts.remove(&hit.id)?,
+                    rank: hit.rank,
+                    snippet: hit.snippet,
+                })
+            })
+            .collect())
+    }
+
+    async fn search_users(
+        &self,
+        q: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<UserSearchHitSchema>> {
+        let hits = self.user_repository.search(q, limit, offset).await?;
+        let ids: Vec<Uuid> = hits.iter().map(|hit| hit.id).collect();
+        let mut users: HashMap<Uuid, _> = self
+            .user_repository
+            .find_all_by_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| {
+                Some(UserSearchHitSchema {
+                    user: UserSchema::from(users.remove(&hit.id)?),
+                    rank: hit.rank,
+                    snippet: hit.snippet,
+                })
+            })
+            .collect())
+    }
+}
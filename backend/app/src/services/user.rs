@@ -1,19 +1,73 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Utc};
+
+use crate::models::user::DELETED_USER_ID;
+use crate::repositories::conversation::ConversationRepository;
+use crate::repositories::data_export::DataExportRepository;
+use crate::repositories::following::FollowingRepository;
+use crate::repositories::import::ImportRepository;
+use crate::repositories::message::MessageRepository;
+use crate::repositories::post::PostRepository;
 use crate::repositories::user::{CreateUserDTO, UpdateUserDTO, UserRepository};
-use crate::repositories::Repository;
+use crate::repositories::{RepoError, Repository, UnitOfWork};
 use crate::schemas::auth::{AuthPayload, Claims};
-use crate::schemas::user::{CreateUserSchema, UpdateUserSchema, UserSchema};
-use crate::utils::auth::{create_token, hash_password, verify_password};
+use crate::schemas::user::{
+    AccountDeletionSchema, CreateUserSchema, MeSchema, UpdateUserSchema, UserSchema,
+};
+use crate::services::media::MediaService;
+use crate::utils::auth::{hash_password, verify_password, Keys};
 use crate::utils::errors::{AppError, AuthError};
+use crate::utils::images::ProfileImage;
 use uuid::Uuid;
 
+/// What happens to the posts and messages of a deleted account
+#[derive(Clone, Copy, Debug)]
+pub enum DeletedAccountContent {
+    Delete,
+    /// Published posts and messages stay up under a placeholder user
+    Anonymize,
+}
+
+impl FromStr for DeletedAccountContent {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "delete" => Ok(Self::Delete),
+            "anonymize" => Ok(Self::Anonymize),
+            other => Err(format!("unknown deleted account content policy `{other}`")),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct UserService {
     pub repository: UserRepository,
+    pub following_repository: FollowingRepository,
+    pub post_repository: PostRepository,
+    pub message_repository: MessageRepository,
+    pub conversation_repository: ConversationRepository,
+    pub data_export_repository: DataExportRepository,
+    pub import_repository: ImportRepository,
+    pub media_service: MediaService,
+    /// How long a deleted account can be restored, zero deletes right away
+    pub deletion_grace: chrono::Duration,
+    pub deleted_content: DeletedAccountContent,
+    /// Signs the access tokens handed out at login
+    pub keys: Keys,
+    /// How long an access token is valid
+    pub token_lifetime: chrono::Duration,
 }
 
 impl UserService {
     pub async fn create_user(&self, data: CreateUserSchema) -> Result<UserSchema, AppError> {
-        if let Some(_) = self.repository.find_one_by_username(&data.username).await {
+        if self
+            .repository
+            .find_one_by_username(&data.username)
+            .await?
+            .is_some()
+        {
             return Err(AuthError::UsernameAlreadyOccupied.into());
         }
 
@@ -23,88 +77,230 @@ impl UserService {
             .create(CreateUserDTO {
                 username: data.username,
                 password: hashed_password,
-                avatar: data.avatar,
                 about: data.about,
             })
-            .await;
+            .await
+            .map_err(username_taken)?;
 
-        Ok(UserSchema {
-            id: response.id,
-            username: response.username,
-            avatar: response.avatar,
-            about: response.about,
-        })
+        Ok(response.into())
     }
 
     pub async fn find_one_user(&self, id: &Uuid) -> Result<UserSchema, AppError> {
-        let response = self.repository.find_one(id).await;
+        let response = self.repository.find_one(id).await?;
         match response {
             None => Err(AppError::EntityNotFound {
                 entity: "User",
                 id: *id,
             }),
-            Some(user) => Ok(UserSchema {
-                id: user.id,
-                username: user.username,
-                avatar: user.avatar,
-                about: user.about,
+            Some(user) => Ok(user.into()),
+        }
+    }
+
+    /// The user's own profile, including their private settings
+    pub async fn find_me(&self, id: &Uuid) -> Result<MeSchema, AppError> {
+        let response = self.repository.find_one(id).await?;
+        match response {
+            None => Err(AppError::EntityNotFound {
+                entity: "User",
+                id: *id,
             }),
+            Some(user) => Ok(user.into()),
         }
     }
 
-    pub async fn login_by_username(&self, payload: AuthPayload) -> Result<String, AuthError> {
+    pub async fn login_by_username(&self, payload: AuthPayload) -> Result<String, AppError> {
         if payload.username.is_empty() || payload.password.is_empty() {
-            return Err(AuthError::MissingCredentials);
+            return Err(AuthError::MissingCredentials.into());
         }
 
         let user = self
             .repository
             .find_one_by_username(&payload.username)
-            .await;
+            .await?;
         if let Some(user) = user {
             if !verify_password(payload.password, user.password) {
-                return Err(AuthError::WrongCredentials);
+                return Err(AuthError::WrongCredentials.into());
+            }
+            if user.suspended_at.is_some() {
+                return Err(AuthError::AccountSuspended.into());
             }
 
-            let claims = Claims::new(payload.username);
-            let token = create_token(&claims).map_err(|_| AuthError::TokenCreation)?;
+            let claims = Claims::new(payload.username, self.token_lifetime);
+            let token = self
+                .keys
+                .create_token(&claims)
+                .map_err(|_| AuthError::TokenCreation)?;
 
             return Ok(token);
         }
 
-        Err(AuthError::WrongCredentials)
+        Err(AuthError::WrongCredentials.into())
     }
 
-    pub async fn find_all_users(&self) -> Vec<UserSchema> {
-        let response = self.repository.find_all(()).await;
-        let tasks: Vec<UserSchema> = response
-            .iter()
-            .map(|u| UserSchema {
-                id: u.clone().id,
-                username: u.clone().username,
-                avatar: u.clone().avatar,
-                about: u.clone().about,
-            })
-            .collect();
-        tasks
+    pub async fn find_all_users(&self) -> Result<Vec<UserSchema>, AppError> {
+        let response = self.repository.find_all(()).await?;
+        let tasks: Vec<UserSchema> = response.into_iter().map(UserSchema::from).collect();
+        Ok(tasks)
     }
 
+    /// Deletes the account and everything it owns right away.
     pub async fn delete_user(&self, id: &Uuid) -> Result<(), AppError> {
-        let task = self.repository.find_one(id).await;
-        if task.is_none() {
+        if !self.purge(id, None).await? {
             return Err(AppError::EntityNotFound {
                 entity: "User",
                 id: *id,
             });
         }
+        Ok(())
+    }
+
+    /// Schedules the account for deletion after the grace period and signs
+    /// the user out everywhere. Without a grace period it's deleted right away.
+    pub async fn request_deletion(
+        &self,
+        id: &Uuid,
+        password: String,
+    ) -> Result<AccountDeletionSchema, AppError> {
+        let user = self
+            .repository
+            .find_one(id)
+            .await?
+            .ok_or(AppError::EntityNotFound {
+                entity: "User",
+                id: *id,
+            })?;
+        if !verify_password(password, user.password) {
+            return Err(AuthError::WrongCredentials.into());
+        }
+        if user.deletion_scheduled_at.is_some() {
+            return Err(AppError::DeletionAlreadyScheduled);
+        }
+
+        if self.deletion_grace.is_zero() {
+            self.delete_user(id).await?;
+            return Ok(AccountDeletionSchema {
+                deletion_scheduled_at: None,
+            });
+        }
 
-        self.repository.delete(id).await;
+        let now = Utc::now().naive_utc();
+        let deletion_scheduled_at = now + self.deletion_grace;
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        self.repository
+            .schedule_deletion_in(&mut uow, id, Some(deletion_scheduled_at))
+            .await?;
+        self.repository.revoke_tokens_in(&mut uow, id, now).await?;
+        uow.commit().await?;
+        Ok(AccountDeletionSchema {
+            deletion_scheduled_at: Some(deletion_scheduled_at),
+        })
+    }
+
+    pub async fn cancel_deletion(&self, id: &Uuid) -> Result<(), AppError> {
+        let user = self
+            .repository
+            .find_one(id)
+            .await?
+            .ok_or(AppError::EntityNotFound {
+                entity: "User",
+                id: *id,
+            })?;
+        if user.deletion_scheduled_at.is_none() {
+            return Err(AppError::DeletionNotScheduled);
+        }
+        self.repository.schedule_deletion(id, None).await?;
         Ok(())
     }
 
+    /// Deletes the accounts whose grace period is over. An account that
+    /// can't be deleted is logged and left for the next run, the others are
+    /// still deleted.
+    pub async fn purge_due_accounts(&self) -> Result<u64, AppError> {
+        let now = Utc::now().naive_utc();
+        let mut purged = 0;
+        for id in self.repository.find_due_deletions(now).await? {
+            match self.purge(&id, Some(now)).await {
+                Ok(true) => purged += 1,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Can't delete account {}: {}", id, e),
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Deletes the account with its posts, messages, relationships and media
+    /// in one transaction. With `due` set, only an account whose deletion was
+    /// scheduled by then is deleted, so a cancellation that won the race sticks.
+    async fn purge(&self, id: &Uuid, due: Option<NaiveDateTime>) -> Result<bool, AppError> {
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        let Some(user) = self.repository.lock(&mut uow, id).await? else {
+            return Ok(false);
+        };
+        if let Some(due) = due {
+            if !matches!(user.deletion_scheduled_at, Some(at) if at <= due) {
+                return Ok(false);
+            }
+        }
+
+        if let DeletedAccountContent::Anonymize = self.deleted_content {
+            self.post_repository
+                .reassign_published(&mut uow, id, &DELETED_USER_ID, Utc::now().naive_utc())
+                .await?;
+            self.media_service
+                .repository
+                .reassign_attached(&mut uow, id, &DELETED_USER_ID)
+                .await?;
+            self.message_repository
+                .reassign_sender(&mut uow, id, &DELETED_USER_ID)
+                .await?;
+        }
+        self.post_repository
+            .delete_all_by_author(&mut uow, id)
+            .await?;
+        self.message_repository
+            .delete_all_by_sender(&mut uow, id)
+            .await?;
+        self.conversation_repository.leave_all(&mut uow, id).await?;
+        self.following_repository
+            .delete_all_of_user(&mut uow, id)
+            .await?;
+        // The records go with the user, the files once that's committed
+        let media = self
+            .media_service
+            .repository
+            .find_all_by_owner_in(&mut uow, id)
+            .await?;
+        let mut files = self
+            .data_export_repository
+            .find_storage_keys(&mut uow, id)
+            .await?;
+        files.extend(
+            self.import_repository
+                .find_storage_keys(&mut uow, id)
+                .await?,
+        );
+        self.repository.delete_in(&mut uow, id).await?;
+        uow.commit().await?;
+
+        for media in media {
+            if let Err(e) = self.media_service.delete_files(media).await {
+                tracing::error!("Can't delete files of a deleted account: {}", e);
+            }
+        }
+        for key in files {
+            if let Err(e) = self.media_service.store.delete(&key).await {
+                tracing::error!(
+                    "Can't delete an export or import of a deleted account: {}",
+                    e
+                );
+            }
+        }
+        Ok(true)
+    }
+
     pub async fn update_user(&self, id: &Uuid, data: UpdateUserSchema) -> Result<(), AppError> {
-        let user = self.repository.find_one(id).await;
-        if user.is_none() {
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        if self.repository.lock(&mut uow, id).await?.is_none() {
             return Err(AppError::EntityNotFound {
                 entity: "User",
                 id: *id,
@@ -112,7 +308,10 @@ impl UserService {
         }
 
         if let Some(u) = data.username.clone() {
-            let user_with_same_username = self.repository.find_one_by_username(&u).await;
+            let user_with_same_username = self
+                .repository
+                .find_one_by_username_in(&mut uow, &u)
+                .await?;
             if let Some(user) = user_with_same_username {
                 if user.id != *id {
                     return Err(AuthError::UsernameAlreadyOccupied.into());
@@ -131,10 +330,74 @@ impl UserService {
         let dto = UpdateUserDTO {
             username: data.username.clone(),
             password,
-            avatar: data.avatar,
             about: data.about,
+            dms_from_followers_only: data.dms_from_followers_only,
         };
-        self.repository.update(id, dto).await;
+        self.repository
+            .update_in(&mut uow, id, dto)
+            .await
+            .map_err(username_taken)?;
+        uow.commit().await?;
         Ok(())
     }
+
+    /// Replaces the avatar or banner with an upload, or resets it to the
+    /// default when `data` is `None`. The previous file is deleted.
+    pub async fn set_profile_image(
+        &self,
+        id: &Uuid,
+        kind: ProfileImage,
+        data: Option<&[u8]>,
+    ) -> Result<UserSchema, AppError> {
+        let media_id = match data {
+            None => None,
+            Some(data) => Some(
+                self.media_service
+                    .upload_profile_image(id, data, kind)
+                    .await?
+                    .id,
+            ),
+        };
+
+        // The user row stays locked until the new image is set, so concurrent
+        // uploads each replace the image the other one set
+        let mut uow = UnitOfWork::begin(&self.repository.pool).await?;
+        let Some(user) = self.repository.lock(&mut uow, id).await? else {
+            drop(uow);
+            if let Some(media_id) = media_id {
+                self.media_service.remove(&media_id).await?;
+            }
+            return Err(AppError::EntityNotFound {
+                entity: "User",
+                id: *id,
+            });
+        };
+        let previous = match kind {
+            ProfileImage::Avatar => {
+                self.repository
+                    .set_avatar_in(&mut uow, id, media_id)
+                    .await?;
+                user.avatar_media_id
+            }
+            ProfileImage::Banner => {
+                self.repository
+                    .set_banner_in(&mut uow, id, media_id)
+                    .await?;
+                user.banner_media_id
+            }
+        };
+        uow.commit().await?;
+        if let Some(previous) = previous {
+            self.media_service.remove(&previous).await?;
+        }
+        self.find_one_user(id).await
+    }
+}
+
+/// Another user took the username between the check and the write.
+fn username_taken(error: RepoError) -> AppError {
+    match error {
+        RepoError::UniqueViolation(_) => AuthError::UsernameAlreadyOccupied.into(),
+        e => e.into(),
+    }
 }
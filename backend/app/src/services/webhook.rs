@@ -0,0 +1,562 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use crate::models::job::JobPayload;
+use crate::models::webhook::{Webhook, WebhookDelivery, WebhookEvent};
+use crate::repositories::job::JobRepository;
+use crate::repositories::user::UserRepository;
+use crate::repositories::webhook::{
+    CreateWebhookDTO, UpdateWebhookDTO, WebhookFindAllParams, WebhookRepository,
+};
+use crate::repositories::webhook_delivery::{
+    CreateWebhookDeliveryDTO, WebhookAttemptDTO, WebhookDeliveryFindAllParams,
+    WebhookDeliveryRepository,
+};
+use crate::repositories::Repository;
+use crate::schemas::webhook::{
+    CreateWebhookSchema, UpdateWebhookSchema, WebhookDeliveriesQuery, WebhookDeliverySchema,
+    WebhookSchema,
+};
+use crate::utils::errors::{AppError, Result};
+use crate::utils::webhook::{generate_secret, WebhookSender};
+
+/// How many webhooks a user can register
+const MAX_WEBHOOKS: i64 = 10;
+/// How long finished deliveries stay in the log
+const DELIVERY_RETENTION_DAYS: i64 = 30;
+
+#[derive(Clone)]
+pub struct WebhookService {
+    pub repository: WebhookRepository,
+    pub delivery_repository: WebhookDeliveryRepository,
+    pub user_repository: UserRepository,
+    /// Queues the deliveries, failed attempts are retried with the job backoff
+    pub job_repository: JobRepository,
+    pub sender: WebhookSender,
+    /// How many times a delivery is tried before it's given up on
+    pub max_attempts: i32,
+    /// How many attempts in a row can fail before the webhook is disabled
+    pub max_failures: i32,
+}
+
+impl WebhookService {
+    pub async fn create_webhook(
+        &self,
+        user_id: &Uuid,
+        data: CreateWebhookSchema,
+    ) -> Result<WebhookSchema> {
+        if data.all_users {
+            let is_admin = self
+                .user_repository
+                .find_one(user_id)
+                .await?
+                .is_some_and(|user| user.is_admin);
+            if !is_admin {
+                return Err(AppError::CantDoThis);
+            }
+        }
+        let url = check_url(&data.url)?;
+        let events = check_events(data.events)?;
+        if self.repository.count_by_owner(user_id).await? >= MAX_WEBHOOKS {
+            return Err(AppError::TooManyWebhooks(MAX_WEBHOOKS));
+        }
+
+        let webhook = self
+            .repository
+            .create(CreateWebhookDTO {
+                owner_id: *user_id,
+                url,
+                secret: generate_secret(),
+                events,
+                all_users: data.all_users,
+            })
+            .await?;
+        Ok(webhook.into())
+    }
+
+    /// Webhooks of the user, newest first.
+    pub async fn find_webhooks(&self, user_id: &Uuid) -> Result<Vec<WebhookSchema>> {
+        let webhooks = self
+            .repository
+            .find_all(WebhookFindAllParams { owner_id: *user_id })
+            .await?;
+        Ok(webhooks.into_iter().map(WebhookSchema::from).collect())
+    }
+
+    pub async fn find_webhook(&self, id: &Uuid, user_id: &Uuid) -> Result<WebhookSchema> {
+        Ok(self.find_own_webhook(id, user_id).await?.into())
+    }
+
+    pub async fn update_webhook(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+        data: UpdateWebhookSchema,
+    ) -> Result<WebhookSchema> {
+        self.find_own_webhook(id, user_id).await?;
+        self.repository
+            .update(
+                id,
+                UpdateWebhookDTO {
+                    url: data.url.as_deref().map(check_url).transpose()?,
+                    events: data.events.map(check_events).transpose()?,
+                    enabled: data.enabled,
+                },
+            )
+            .await?;
+        self.find_webhook(id, user_id).await
+    }
+
+    /// Deletes the webhook along with its deliveries.
+    pub async fn delete_webhook(&self, id: &Uuid, user_id: &Uuid) -> Result<()> {
+        self.find_own_webhook(id, user_id).await?;
+        self.repository.delete(id).await?;
+        Ok(())
+    }
+
+    /// Deliveries of the webhook, newest first.
+    pub async fn find_deliveries(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+        query: WebhookDeliveriesQuery,
+    ) -> Result<Vec<WebhookDeliverySchema>> {
+        self.find_own_webhook(id, user_id).await?;
+        let deliveries = self
+            .delivery_repository
+            .find_all(WebhookDeliveryFindAllParams {
+                webhook_id: *id,
+                status: query.status,
+                limit: query.limit.unwrap_or(50).clamp(1, 200),
+            })
+            .await?;
+        Ok(deliveries
+            .into_iter()
+            .map(WebhookDeliverySchema::from)
+            .collect())
+    }
+
+    /// Sends a `test` event right away and returns how it went. It's tried
+    /// once, even when the webhook is disabled, and doesn't count towards its failures.
+    pub async fn send_test(&self, id: &Uuid, user_id: &Uuid) -> Result<WebhookDeliverySchema> {
+        let webhook = self.find_own_webhook(id, user_id).await?;
+        let delivery = self
+            .create_delivery(
+                &webhook,
+                WebhookEvent::Test,
+                json!({ "webhook_id": webhook.id }),
+            )
+            .await?;
+        let (response_status, error) = self.attempt(&webhook, &delivery).await;
+        self.record_attempt(&delivery, response_status, error, true)
+            .await?;
+        self.delivery_repository
+            .find_one(&delivery.id)
+            .await?
+            .map(WebhookDeliverySchema::from)
+            .ok_or(AppError::EntityNotFound {
+                entity: "WebhookDelivery",
+                id: delivery.id,
+            })
+    }
+
+    /// Queues a delivery of the event to every enabled webhook subscribed to
+    /// it that belongs to one of `user_ids` or receives the events of all users.
+    /// Events are emitted once their change is saved, so failing to queue them
+    /// is logged instead of failing the request.
+    pub async fn emit(&self, event: WebhookEvent, user_ids: &[Uuid], data: serde_json::Value) {
+        if let Err(e) = self.queue_deliveries(event, user_ids, data).await {
+            tracing::error!("Can't queue {} webhook deliveries: {}", event.as_str(), e);
+        }
+    }
+
+    async fn queue_deliveries(
+        &self,
+        event: WebhookEvent,
+        user_ids: &[Uuid],
+        data: serde_json::Value,
+    ) -> Result<()> {
+        let webhooks = self
+            .repository
+            .find_subscribed(event.as_str(), user_ids)
+            .await?;
+        for webhook in webhooks {
+            let delivery = self.create_delivery(&webhook, event, data.clone()).await?;
+            self.job_repository
+                .enqueue(
+                    &JobPayload::DeliverWebhook {
+                        delivery_id: delivery.id,
+                    },
+                    Utc::now().naive_utc(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Makes an attempt at a queued delivery. A failed attempt is returned as
+    /// an error so the job is retried, until the delivery runs out of attempts
+    /// or the webhook is disabled for failing too often.
+    pub async fn deliver(&self, id: &Uuid) -> Result<()> {
+        // Deleted along with its webhook
+        let Some(delivery) = self.delivery_repository.find_one(id).await? else {
+            return Ok(());
+        };
+        if !delivery.is_pending() {
+            return Ok(());
+        }
+        let Some(webhook) = self.repository.find_one(&delivery.webhook_id).await? else {
+            return Ok(());
+        };
+        if !webhook.enabled {
+            self.delivery_repository
+                .fail_pending(&webhook.id, "Webhook is disabled")
+                .await?;
+            return Ok(());
+        }
+
+        let (response_status, error) = self.attempt(&webhook, &delivery).await;
+        let Some(error) = error else {
+            self.record_attempt(&delivery, response_status, None, true)
+                .await?;
+            self.repository.record_success(&webhook.id).await?;
+            return Ok(());
+        };
+
+        let enabled = self
+            .repository
+            .record_failure(&webhook.id, self.max_failures, Utc::now().naive_utc())
+            .await?;
+        let last = !enabled || delivery.attempts + 1 >= self.max_attempts;
+        self.record_attempt(&delivery, response_status, Some(error.clone()), last)
+            .await?;
+        if !enabled {
+            tracing::warn!(
+                "Disabled webhook {} after {} failed attempts in a row",
+                webhook.id,
+                self.max_failures
+            );
+            self.delivery_repository
+                .fail_pending(&webhook.id, "Webhook was disabled after failing too often")
+                .await?;
+        }
+        if last {
+            return Ok(());
+        }
+        Err(AppError::WebhookDeliveryFailed(error))
+    }
+
+    /// Deletes finished deliveries older than the retention period.
+    pub async fn purge_deliveries(&self) -> Result<u64> {
+        Ok(self
+            .delivery_repository
+            .purge(Utc::now().naive_utc() - Duration::days(DELIVERY_RETENTION_DAYS))
+            .await?)
+    }
+
+    /// Finds a webhook of the user. Webhooks of other users are reported as missing.
+    async fn find_own_webhook(&self, id: &Uuid, user_id: &Uuid) -> Result<Webhook> {
+        self.repository
+            .find_one(id)
+            .await?
+            .filter(|webhook| webhook.owner_id == *user_id)
+            .ok_or(AppError::EntityNotFound {
+                entity: "Webhook",
+                id: *id,
+            })
+    }
+
+    async fn create_delivery(
+        &self,
+        webhook: &Webhook,
+        event: WebhookEvent,
+        data: serde_json::Value,
+    ) -> Result<WebhookDelivery> {
+        let id = Uuid::new_v4();
+        Ok(self
+            .delivery_repository
+            .create(CreateWebhookDeliveryDTO {
+                id,
+                webhook_id: webhook.id,
+                event: event.as_str().to_string(),
+                payload: json!({
+                    "id": id,
+                    "event": event.as_str(),
+                    "created_at": Utc::now().naive_utc(),
+                    "data": data,
+                }),
+            })
+            .await?)
+    }
+
+    /// Sends the delivery once. Returns the response status, if there was a
+    /// response, and why the attempt failed.
+    async fn attempt(
+        &self,
+        webhook: &Webhook,
+        delivery: &WebhookDelivery,
+    ) -> (Option<i32>, Option<String>) {
+        let url = match Url::parse(&webhook.url) {
+            Ok(url) => url,
+            Err(e) => return (None, Some(format!("Invalid url: {}", e))),
+        };
+        let body = match serde_json::to_vec(&delivery.payload) {
+            Ok(body) => body,
+            Err(e) => return (None, Some(format!("Can't encode the payload: {}", e))),
+        };
+        match self
+            .sender
+            .send(
+                &url,
+                &webhook.secret,
+                &delivery.id,
+                &delivery.event,
+                body,
+                Utc::now().timestamp(),
+            )
+            .await
+        {
+            Ok(status) if status.is_success() => (Some(status.as_u16() as i32), None),
+            Ok(status) => (
+                Some(status.as_u16() as i32),
+                Some(format!("Unexpected response status {}", status)),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        }
+    }
+
+    /// Records an attempt. The delivery stays pending unless it succeeded or
+    /// it was the `last` attempt.
+    async fn record_attempt(
+        &self,
+        delivery: &WebhookDelivery,
+        response_status: Option<i32>,
+        error: Option<String>,
+        last: bool,
+    ) -> Result<()> {
+        let status = match (&error, last) {
+            (None, _) => "delivered",
+            (Some(_), true) => "failed",
+            (Some(_), false) => "pending",
+        };
+        self.delivery_repository
+            .update(
+                &delivery.id,
+                WebhookAttemptDTO {
+                    status: status.to_string(),
+                    response_status,
+                    delivered_at: error.is_none().then(|| Utc::now().naive_utc()),
+                    error,
+                },
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Only http and https urls with a host can be registered. Whether the host
+/// is public is checked on every delivery, since it can resolve differently later.
+fn check_url(url: &str) -> Result<String> {
+    match Url::parse(url.trim()) {
+        Ok(url) if (url.scheme() == "http" || url.scheme() == "https") && url.has_host() => {
+            Ok(url.to_string())
+        }
+        _ => Err(AppError::InvalidWebhookUrl),
+    }
+}
+
+fn check_events(events: Vec<String>) -> Result<Vec<String>> {
+    let mut events = events
+        .iter()
+        .map(|event| event.parse::<WebhookEvent>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| AppError::InvalidWebhookEvents)?
+        .into_iter()
+        .map(|event| event.as_str().to_string())
+        .collect::<Vec<_>>();
+    events.sort();
+    events.dedup();
+    if events.is_empty() {
+        return Err(AppError::InvalidWebhookEvents);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration as StdDuration;
+
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+    use crate::utils::webhook::{sign, DELIVERY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+    const SECRET: &str = "whsec_test";
+
+    /// Answers with the queued statuses in order, then with 200, and keeps
+    /// every request it got.
+    #[derive(Clone, Default)]
+    struct Receiver {
+        statuses: Arc<Mutex<VecDeque<StatusCode>>>,
+        requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        receiver.requests.lock().unwrap().push((headers, body));
+        receiver
+            .statuses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(StatusCode::OK)
+    }
+
+    async fn start_receiver(statuses: &[StatusCode]) -> (Receiver, String) {
+        let receiver = Receiver::default();
+        receiver.statuses.lock().unwrap().extend(statuses);
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (receiver, url)
+    }
+
+    /// The repositories are never used by attempts, so the pool doesn't connect
+    fn service() -> WebhookService {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/twotty")
+            .unwrap();
+        WebhookService {
+            repository: WebhookRepository { pool: pool.clone() },
+            delivery_repository: WebhookDeliveryRepository { pool: pool.clone() },
+            user_repository: UserRepository { pool: pool.clone() },
+            job_repository: JobRepository { pool },
+            sender: WebhookSender {
+                user_agent: "twotty-test".to_string(),
+                timeout: StdDuration::from_secs(5),
+                allow_private_networks: true,
+            },
+            max_attempts: 3,
+            max_failures: 10,
+        }
+    }
+
+    fn webhook(url: &str) -> Webhook {
+        Webhook {
+            id: Uuid::new_v4(),
+            owner_id: Uuid::new_v4(),
+            url: url.to_string(),
+            secret: SECRET.to_string(),
+            events: vec!["post.created".to_string()],
+            all_users: false,
+            enabled: true,
+            failure_count: 0,
+            disabled_at: None,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    fn delivery(webhook: &Webhook) -> WebhookDelivery {
+        WebhookDelivery {
+            id: Uuid::new_v4(),
+            webhook_id: webhook.id,
+            event: "post.created".to_string(),
+            payload: json!({ "data": { "post": { "text": "hello" } } }),
+            status: "pending".to_string(),
+            attempts: 0,
+            response_status: None,
+            last_error: None,
+            created_at: Utc::now().naive_utc(),
+            delivered_at: None,
+        }
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+        headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn attempt_is_signed() {
+        let (receiver, url) = start_receiver(&[]).await;
+        let webhook = webhook(&url);
+        let delivery = delivery(&webhook);
+
+        let (status, error) = service().attempt(&webhook, &delivery).await;
+        assert_eq!((status, error), (Some(200), None));
+
+        let requests = receiver.requests.lock().unwrap();
+        let (headers, body) = &requests[0];
+        let timestamp: i64 = header(headers, TIMESTAMP_HEADER).parse().unwrap();
+        assert_eq!(
+            header(headers, SIGNATURE_HEADER),
+            sign(SECRET, timestamp, body)
+        );
+        assert_ne!(
+            header(headers, SIGNATURE_HEADER),
+            sign("whsec_other", timestamp, body)
+        );
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(body).unwrap(),
+            delivery.payload
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_attempt_is_retried_with_the_same_delivery() {
+        let (receiver, url) =
+            start_receiver(&[StatusCode::SERVICE_UNAVAILABLE, StatusCode::FOUND]).await;
+        let webhook = webhook(&url);
+        let delivery = delivery(&webhook);
+        let service = service();
+
+        // An error makes `deliver` fail the job, which queues it again
+        let (status, error) = service.attempt(&webhook, &delivery).await;
+        assert_eq!(status, Some(503));
+        assert!(error.is_some());
+        // Redirects aren't followed
+        let (status, error) = service.attempt(&webhook, &delivery).await;
+        assert_eq!(status, Some(302));
+        assert!(error.is_some());
+        let (status, error) = service.attempt(&webhook, &delivery).await;
+        assert_eq!((status, error), (Some(200), None));
+
+        let requests = receiver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        for (headers, body) in requests.iter() {
+            assert_eq!(header(headers, DELIVERY_HEADER), delivery.id.to_string());
+            let timestamp: i64 = header(headers, TIMESTAMP_HEADER).parse().unwrap();
+            assert_eq!(
+                header(headers, SIGNATURE_HEADER),
+                sign(SECRET, timestamp, body)
+            );
+            assert_eq!(body, &requests[0].1);
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_receiver_fails_the_attempt() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+        let webhook = webhook(&url);
+
+        let (status, error) = service().attempt(&webhook, &delivery(&webhook)).await;
+        assert_eq!(status, None);
+        assert!(error.is_some());
+    }
+}
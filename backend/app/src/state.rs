@@ -1,6 +1,18 @@
+use crate::services::admin::AdminService;
+use crate::services::bookmark::BookmarkService;
+use crate::services::data_export::DataExportService;
+use crate::services::explore::ExploreService;
 use crate::services::following::FollowingService;
+use crate::services::health::HealthService;
+use crate::services::import::ImportService;
+use crate::services::job::JobService;
+use crate::services::list::ListService;
+use crate::services::media::MediaService;
+use crate::services::message::MessageService;
 use crate::services::post::PostService;
+use crate::services::search::SearchService;
 use crate::services::user::UserService;
+use crate::services::webhook::WebhookService;
 use crate::Config;
 
 #[derive(Clone)]
@@ -8,5 +20,17 @@ pub struct AppState {
     pub user_service: UserService,
     pub post_service: PostService,
     pub following_service: FollowingService,
+    pub message_service: MessageService,
+    pub explore_service: ExploreService,
+    pub search_service: SearchService,
+    pub media_service: MediaService,
+    pub bookmark_service: BookmarkService,
+    pub list_service: ListService,
+    pub data_export_service: DataExportService,
+    pub import_service: ImportService,
+    pub job_service: JobService,
+    pub webhook_service: WebhookService,
+    pub admin_service: AdminService,
+    pub health_service: HealthService,
     pub config: Config,
 }
@@ -0,0 +1,53 @@
+pub mod local;
+pub mod s3;
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures_util::Stream;
+use tokio::io::AsyncRead;
+
+use crate::Config;
+
+#[derive(thiserror::Error, Debug)]
+pub enum StorageError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("S3 error: {0}")]
+    S3(String),
+    #[error("Invalid storage configuration: {0}")]
+    Config(String),
+}
+
+/// Chunks of a stored file, read as the stream is polled
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, StorageError>> + Send>>;
+
+/// Place where uploaded files are kept, addressed by a flat key such as `<uuid>.png`.
+#[async_trait::async_trait]
+pub trait MediaStore: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8], content_type: &str) -> Result<(), StorageError>;
+    /// Returns `None` if there is no file under this key
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+    /// Like `put`, for files too large to hold in memory
+    async fn put_stream(
+        &self,
+        key: &str,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        content_type: &str,
+    ) -> Result<(), StorageError>;
+    /// Like `get`, for files too large to hold in memory
+    async fn get_stream(&self, key: &str) -> Result<Option<ByteStream>, StorageError>;
+}
+
+/// Builds the store selected by `MEDIA_STORE` (`local` or `s3`).
+pub fn media_store(settings: &Config) -> Result<Arc<dyn MediaStore>, StorageError> {
+    match settings.media_store.as_str() {
+        "local" => Ok(Arc::new(local::LocalMediaStore::new(&settings.media_dir))),
+        "s3" => Ok(Arc::new(s3::S3MediaStore::new(settings)?)),
+        other => Err(StorageError::Config(format!(
+            "unknown media store `{other}`"
+        ))),
+    }
+}
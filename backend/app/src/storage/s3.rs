@@ -0,0 +1,105 @@
+use futures_util::TryStreamExt;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use tokio::io::AsyncRead;
+
+use super::{ByteStream, MediaStore, StorageError};
+use crate::Config;
+
+/// Keeps files in an S3-compatible bucket, such as AWS S3 or MinIO.
+pub struct S3MediaStore {
+    bucket: Bucket,
+}
+
+impl S3MediaStore {
+    pub fn new(settings: &Config) -> Result<Self, StorageError> {
+        let region = match &settings.s3_endpoint {
+            Some(endpoint) => Region::Custom {
+                region: settings.s3_region.clone(),
+                endpoint: endpoint.clone(),
+            },
+            None => settings
+                .s3_region
+                .parse()
+                .map_err(|e| StorageError::Config(format!("{e}")))?,
+        };
+        let credentials = Credentials {
+            access_key: settings.s3_access_key.clone(),
+            secret_key: settings.s3_secret_key.clone(),
+            security_token: None,
+            session_token: None,
+            expiration: None,
+        };
+        let bucket = Bucket::new(&settings.s3_bucket, region, credentials)
+            .map_err(|e| StorageError::Config(e.to_string()))?;
+
+        // MinIO and most self-hosted stores don't support virtual-hosted buckets
+        let bucket = match settings.s3_endpoint {
+            Some(_) => bucket.with_path_style(),
+            None => bucket,
+        };
+        Ok(Self { bucket })
+    }
+}
+
+fn s3_error(e: impl ToString) -> StorageError {
+    StorageError::S3(e.to_string())
+}
+
+#[async_trait::async_trait]
+impl MediaStore for S3MediaStore {
+    async fn put(&self, key: &str, data: &[u8], content_type: &str) -> Result<(), StorageError> {
+        let response = self
+            .bucket
+            .put_object_with_content_type(key, data, content_type)
+            .await
+            .map_err(s3_error)?;
+        match response.status_code() {
+            200..=299 => Ok(()),
+            code => Err(s3_error(format!("PUT {key} returned {code}"))),
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let response = self.bucket.get_object(key).await.map_err(s3_error)?;
+        match response.status_code() {
+            200..=299 => Ok(Some(response.to_vec())),
+            404 => Ok(None),
+            code => Err(s3_error(format!("GET {key} returned {code}"))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let response = self.bucket.delete_object(key).await.map_err(s3_error)?;
+        match response.status_code() {
+            200..=299 | 404 => Ok(()),
+            code => Err(s3_error(format!("DELETE {key} returned {code}"))),
+        }
+    }
+
+    async fn put_stream(
+        &self,
+        key: &str,
+        mut reader: &mut (dyn AsyncRead + Unpin + Send),
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        let response = self
+            .bucket
+            .put_object_stream_with_content_type(&mut reader, key, content_type)
+            .await
+            .map_err(s3_error)?;
+        match response.status_code() {
+            200..=299 => Ok(()),
+            code => Err(s3_error(format!("PUT {key} returned {code}"))),
+        }
+    }
+
+    async fn get_stream(&self, key: &str) -> Result<Option<ByteStream>, StorageError> {
+        let response = self.bucket.get_object_stream(key).await.map_err(s3_error)?;
+        match response.status_code {
+            200..=299 => Ok(Some(Box::pin(response.bytes.map_err(s3_error)))),
+            404 => Ok(None),
+            code => Err(s3_error(format!("GET {key} returned {code}"))),
+        }
+    }
+}
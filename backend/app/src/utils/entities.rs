@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Mention,
+    Hashtag,
+}
+
+/// `@mention` or `#hashtag` found in a post text.
+///
+/// Offsets span the whole token including the leading `@` or `#`.
+/// `start`/`end` are byte offsets, `char_start`/`char_end` count unicode scalar values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedEntity {
+    pub kind: EntityKind,
+    /// Username or tag without the leading symbol, as written
+    pub value: String,
+    pub start: usize,
+    pub end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+pub fn parse_entities(text: &str) -> Vec<ParsedEntity> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut entities = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let kind = match c {
+            '@' => EntityKind::Mention,
+            '#' => EntityKind::Hashtag,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut j = i + 1;
+        while j < chars.len() && is_word_char(chars[j].1) {
+            j += 1;
+        }
+
+        // `mail@example` and `issue#12` are not entities, neither is a lone symbol
+        let preceded_by_word = i > 0 && is_word_char(chars[i - 1].1);
+        if preceded_by_word || j == i + 1 {
+            i += 1;
+            continue;
+        }
+
+        let end = chars.get(j).map(|(b, _)| *b).unwrap_or(text.len());
+        let value = &text[start + c.len_utf8()..end];
+        if kind == EntityKind::Hashtag && value.chars().all(|c| c.is_ascii_digit()) {
+            i = j;
+            continue;
+        }
+
+        entities.push(ParsedEntity {
+            kind,
+            value: value.to_string(),
+            start,
+            end,
+            char_start: i,
+            char_end: j,
+        });
+        i = j;
+    }
+
+    entities
+}
+
+/// Normalized form used to store and look up hashtags.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.to_lowercase()
+}
@@ -1,3 +1,4 @@
+use axum::extract::multipart::MultipartError;
 use axum::extract::rejection::JsonRejection;
 use axum::response::Response;
 use axum::{
@@ -5,13 +6,36 @@ use axum::{
     response::IntoResponse,
     Json,
 };
+use image::ImageError;
 use serde_json::json;
+use tokio::task::JoinError;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::ValidationErrors;
+use zip::result::ZipError;
+
+use crate::config::ConfigError;
+use crate::repositories::RepoError;
+use crate::storage::StorageError;
+use crate::utils::import::ImportError;
 
 pub type Result<T> = std::result::Result<T, AppError>;
 
+/// Reasons the server or the admin CLI can't start
+#[derive(thiserror::Error, Debug)]
+pub enum StartupError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error("Can't connect to the database: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Can't migrate the database: {0}")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error("Can't set up the media store: {0}")]
+    Storage(#[from] StorageError),
+    #[error("Can't listen on {0}: {1}")]
+    Bind(String, std::io::Error),
+}
+
 #[derive(Debug, ToSchema)]
 pub struct APIError {
     pub message: String,
@@ -54,14 +78,16 @@ pub enum AuthError {
     UsernameAlreadyOccupied,
     #[error("User not found")]
     UserNotFound,
+    #[error("This account is suspended")]
+    AccountSuspended,
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum AppError {
     #[error("{entity} with id {id} not found")]
     EntityNotFound { entity: &'static str, id: Uuid },
-    #[error("Text too long (maximum 256 symbols)")]
-    TextTooLong,
+    #[error("Text too long (maximum {0} symbols)")]
+    TextTooLong(usize),
     #[error("You don't have permission to do this")]
     CantDoThis,
     #[error("You're already following this user")]
@@ -70,12 +96,112 @@ pub enum AppError {
     NotFollowed,
     #[error("Can't follow yourself")]
     CantFollowYourself,
+    #[error("Can't start a conversation with yourself")]
+    CantMessageYourself,
+    #[error("This user only accepts messages from their followers")]
+    MessagesRestricted,
+    #[error("Search query can't be empty")]
+    EmptySearchQuery,
+    #[error("This post can no longer be edited")]
+    EditWindowExpired,
+    #[error("File is too large")]
+    MediaTooLarge,
+    #[error("Unsupported file type")]
+    UnsupportedMediaType,
+    #[error("Media {0} can't be attached to this post")]
+    InvalidMedia(Uuid),
+    #[error("A `file` field is required")]
+    MissingFile,
+    #[error("Poll options must be between 1 and 50 characters long")]
+    InvalidPollOption,
+    #[error("These options can't be chosen in this poll")]
+    InvalidPollVote,
+    #[error("This poll is closed")]
+    PollClosed,
+    #[error("You already voted in this poll")]
+    AlreadyVoted,
+    #[error("Scheduled time must be in the future, and drafts can't be scheduled")]
+    InvalidSchedule,
+    #[error("This post is already published")]
+    AlreadyPublished,
+    #[error("Only published posts can be pinned")]
+    CantPinUnpublished,
+    #[error("You can pin at most {0} posts")]
+    TooManyPinnedPosts(i64),
+    #[error("List name can't be blank")]
+    InvalidListName,
+    #[error("This user is already on the list")]
+    AlreadyListMember,
+    #[error("This user isn't on the list")]
+    NotListMember,
+    #[error("A list can have at most {0} members")]
+    ListFull(i64),
+    #[error("You're already subscribed to this list")]
+    AlreadySubscribed,
+    #[error("You're not subscribed to this list")]
+    NotSubscribed,
+    #[error("Can't subscribe to your own list")]
+    CantSubscribeToOwnList,
+    #[error("Folder name can't be blank")]
+    InvalidFolderName,
+    #[error("You already have a folder with this name")]
+    FolderNameTaken,
+    #[error("This account is already scheduled for deletion")]
+    DeletionAlreadyScheduled,
+    #[error("This account isn't scheduled for deletion")]
+    DeletionNotScheduled,
+    #[error("An export of your data is already in progress")]
+    ExportInProgress,
+    #[error("This export isn't ready for download")]
+    ExportNotReady,
+    #[error("An import is already in progress")]
+    ImportInProgress,
+    #[error("Upload an archive or a follows CSV to import")]
+    NothingToImport,
+    #[error("The uploaded file is missing, upload it again")]
+    ImportFileMissing,
+    #[error("The placeholder account of deleted users can't be changed")]
+    PlaceholderAccount,
+    #[error("Only failed jobs can be retried")]
+    JobNotFailed,
+    #[error("Webhook url must be an http or https url")]
+    InvalidWebhookUrl,
+    #[error("Subscribe to at least one of post.created, post.deleted, user.followed, mention and poll.closed")]
+    InvalidWebhookEvents,
+    #[error("You can have at most {0} webhooks")]
+    TooManyWebhooks(i64),
+    #[error("Webhook delivery failed: {0}")]
+    WebhookDeliveryFailed(String),
+    #[error("This already exists")]
+    AlreadyExists,
+    #[error("Something this refers to no longer exists")]
+    ReferenceNotFound,
+    #[error("This conflicted with another change, try again")]
+    ConcurrentUpdate,
+    #[error("Service is temporarily unavailable, try again later")]
+    DatabaseUnavailable(sqlx::Error),
+    #[error("Internal server error")]
+    Database(RepoError),
+    #[error("Can't process image: {0}")]
+    InvalidImage(#[from] ImageError),
+    #[error("Image processing is unavailable, try again later")]
+    ImageWorkersClosed,
     #[error(transparent)]
     ValidationError(#[from] ValidationErrors),
     #[error(transparent)]
     AxumJsonRejection(#[from] JsonRejection),
     #[error(transparent)]
     AuthError(#[from] AuthError),
+    #[error(transparent)]
+    MultipartError(#[from] MultipartError),
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(transparent)]
+    ArchiveError(#[from] ZipError),
+    #[error(transparent)]
+    InvalidImport(#[from] ImportError),
+    #[error(transparent)]
+    TaskFailed(#[from] JoinError),
 }
 
 impl IntoResponse for AppError {
@@ -101,9 +227,128 @@ impl IntoResponse for AppError {
                 }
             }
             Self::CantDoThis => (StatusCode::FORBIDDEN, message),
+            Self::MessagesRestricted => (StatusCode::FORBIDDEN, message),
+            Self::EditWindowExpired => (StatusCode::FORBIDDEN, message),
+            Self::PollClosed => (StatusCode::FORBIDDEN, message),
+            Self::AlreadyVoted => (StatusCode::CONFLICT, message),
+            Self::AlreadyPublished => (StatusCode::CONFLICT, message),
+            Self::FolderNameTaken => (StatusCode::CONFLICT, message),
+            Self::TooManyPinnedPosts(_) => (StatusCode::CONFLICT, message),
+            Self::AlreadyListMember => (StatusCode::CONFLICT, message),
+            Self::ListFull(_) => (StatusCode::CONFLICT, message),
+            Self::MediaTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, message),
+            Self::UnsupportedMediaType => (StatusCode::UNSUPPORTED_MEDIA_TYPE, message),
+            Self::InvalidImage(ImageError::Limits(_)) => (StatusCode::PAYLOAD_TOO_LARGE, message),
+            Self::InvalidImage(_) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+            Self::ImageWorkersClosed => (StatusCode::SERVICE_UNAVAILABLE, message),
+            Self::MultipartError(e) => (e.status(), e.body_text()),
+            Self::DeletionAlreadyScheduled => (StatusCode::CONFLICT, message),
+            Self::ExportInProgress => (StatusCode::CONFLICT, message),
+            Self::ExportNotReady => (StatusCode::CONFLICT, message),
+            Self::ImportInProgress => (StatusCode::CONFLICT, message),
+            Self::PlaceholderAccount => (StatusCode::FORBIDDEN, message),
+            Self::JobNotFailed => (StatusCode::CONFLICT, message),
+            Self::TooManyWebhooks(_) => (StatusCode::CONFLICT, message),
+            Self::WebhookDeliveryFailed(_) => (StatusCode::BAD_GATEWAY, message),
+            Self::InvalidImport(_) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+            Self::AlreadyExists => (StatusCode::CONFLICT, message),
+            Self::ReferenceNotFound => (StatusCode::CONFLICT, message),
+            Self::ConcurrentUpdate => (StatusCode::CONFLICT, message),
+            Self::DatabaseUnavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, message),
+            Self::Database(e) => {
+                tracing::error!("Database error: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, message)
+            }
+            Self::StorageError(e) => {
+                tracing::error!("Storage error: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+            Self::ArchiveError(e) => {
+                tracing::error!("Archive error: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+            Self::TaskFailed(e) => {
+                tracing::error!("Background task failed: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
             _ => (StatusCode::BAD_REQUEST, message),
         };
 
         APIError::new(status_code, message).into_response()
     }
 }
+
+impl From<RepoError> for AppError {
+    fn from(value: RepoError) -> Self {
+        match value {
+            RepoError::UniqueViolation(constraint) => {
+                tracing::info!("Unique constraint `{}` violated", constraint);
+                Self::AlreadyExists
+            }
+            RepoError::ForeignKeyViolation(constraint) => {
+                tracing::info!("Foreign key constraint `{}` violated", constraint);
+                Self::ReferenceNotFound
+            }
+            RepoError::SerializationFailure(_) => Self::ConcurrentUpdate,
+            RepoError::Unavailable(e) => {
+                tracing::error!("Database unavailable: {}", e);
+                Self::DatabaseUnavailable(e)
+            }
+            e => Self::Database(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    fn status_of(error: sqlx::Error) -> StatusCode {
+        AppError::from(RepoError::from(error))
+            .into_response()
+            .status()
+    }
+
+    #[test]
+    fn pool_timeout_is_service_unavailable() {
+        assert!(matches!(
+            RepoError::from(sqlx::Error::PoolTimedOut),
+            RepoError::Unavailable(_)
+        ));
+        assert_eq!(
+            status_of(sqlx::Error::PoolTimedOut),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_pool_is_service_unavailable() {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/twotty")
+            .unwrap();
+        pool.close().await;
+
+        let error = sqlx::query("SELECT 1").execute(&pool).await.unwrap_err();
+        assert!(matches!(error, sqlx::Error::PoolClosed));
+        assert_eq!(status_of(error), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn other_errors_are_internal() {
+        assert_eq!(
+            status_of(sqlx::Error::RowNotFound),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt::Write as _;
+use std::io::{Seek, Write};
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use uuid::Uuid;
+use zip::result::ZipResult;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Everything a personal data archive holds besides the media files.
+#[derive(Debug, Serialize)]
+pub struct ArchiveContents {
+    pub profile: ArchiveProfile,
+    pub posts: Vec<ArchivePost>,
+    pub followers: Vec<ArchiveUser>,
+    pub following: Vec<ArchiveUser>,
+    pub bookmarks: Vec<ArchiveBookmark>,
+    pub media: Vec<ArchiveMedia>,
+    pub exported_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveProfile {
+    pub id: Uuid,
+    pub username: String,
+    pub about: String,
+    pub dms_from_followers_only: bool,
+    pub avatar: Option<Uuid>,
+    /// Linked profile picture from before uploads
+    pub avatar_url: Option<String>,
+    pub banner: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchivePost {
+    pub id: Uuid,
+    pub text: String,
+    pub created_at: NaiveDateTime,
+    pub edited_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub draft: bool,
+    pub scheduled_at: Option<NaiveDateTime>,
+    pub pinned: bool,
+    /// Earlier versions of the text, oldest first
+    pub revisions: Vec<ArchiveRevision>,
+    pub media_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveRevision {
+    pub text: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveUser {
+    pub id: Uuid,
+    pub username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveBookmark {
+    pub post_id: Uuid,
+    pub folder: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveMedia {
+    pub id: Uuid,
+    pub post_id: Option<Uuid>,
+    pub mime: String,
+    pub size: i64,
+    pub created_at: NaiveDateTime,
+    /// Path of the file inside the archive, `None` if it couldn't be read
+    pub file: Option<String>,
+}
+
+/// Writes the archive as a ZIP with one JSON file per section, the media
+/// files under `media/` and an `index.html` to browse it all. Entries go to
+/// the writer as they are added, so only one media file is held at a time.
+pub struct ArchiveWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            zip: ZipWriter::new(writer),
+        }
+    }
+
+    pub fn add_media(&mut self, path: &str, data: &[u8]) -> ZipResult<()> {
+        // Media is compressed already
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        self.zip.start_file(path, stored)?;
+        self.zip.write_all(data)?;
+        Ok(())
+    }
+
+    /// Adds the JSON files and the index, which list the media added before.
+    pub fn finish(mut self, contents: &ArchiveContents) -> ZipResult<W> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip.start_file("index.html", options)?;
+        self.zip.write_all(render_index(contents).as_bytes())?;
+        self.write_json("profile.json", &contents.profile)?;
+        self.write_json("posts.json", &contents.posts)?;
+        self.write_json("followers.json", &contents.followers)?;
+        self.write_json("following.json", &contents.following)?;
+        self.write_json("bookmarks.json", &contents.bookmarks)?;
+        self.write_json("media.json", &contents.media)?;
+        self.zip.finish()
+    }
+
+    fn write_json<T: Serialize>(&mut self, name: &str, value: &T) -> ZipResult<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip.start_file(name, options)?;
+        serde_json::to_writer_pretty(&mut self.zip, value).map_err(std::io::Error::from)?;
+        Ok(())
+    }
+}
+
+fn render_index(contents: &ArchiveContents) -> String {
+    let profile = &contents.profile;
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>twotty archive of @{username}</title>\n</head>\n<body>\n\
+         <h1>@{username}</h1>\n<p>{about}</p>\n<p>Exported at {exported_at} UTC</p>\n",
+        username = escape(&profile.username),
+        about = escape(&profile.about),
+        exported_at = contents.exported_at.format("%Y-%m-%d %H:%M:%S"),
+    );
+
+    let _ = write!(html, "<h2>Posts ({})</h2>\n<ul>\n", contents.posts.len());
+    for post in &contents.posts {
+        let state = if post.deleted_at.is_some() {
+            " (deleted)"
+        } else if post.draft {
+            " (draft)"
+        } else if post.scheduled_at.is_some() {
+            " (scheduled)"
+        } else {
+            ""
+        };
+        let _ = write!(
+            html,
+            "<li><time>{}</time>{}<p>{}</p>",
+            post.created_at.format("%Y-%m-%d %H:%M"),
+            state,
+            escape(&post.text)
+        );
+        for id in &post.media_ids {
+            if let Some(file) = contents
+                .media
+                .iter()
+                .find(|media| media.id == *id)
+                .and_then(|media| media.file.as_deref())
+            {
+                let file = escape(file);
+                let _ = write!(html, "<a href=\"{file}\">{file}</a> ");
+            }
+        }
+        if !post.revisions.is_empty() {
+            html.push_str("<details><summary>Earlier versions</summary><ul>");
+            for revision in &post.revisions {
+                let _ = write!(
+                    html,
+                    "<li><time>{}</time> {}</li>",
+                    revision.created_at.format("%Y-%m-%d %H:%M"),
+                    escape(&revision.text)
+                );
+            }
+            html.push_str("</ul></details>");
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+
+    for (title, users) in [
+        ("Followers", &contents.followers),
+        ("Following", &contents.following),
+    ] {
+        let _ = write!(html, "<h2>{} ({})</h2>\n<ul>\n", title, users.len());
+        for user in users {
+            let _ = writeln!(html, "<li>@{}</li>", escape(&user.username));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    let _ = write!(
+        html,
+        "<h2>Bookmarks ({})</h2>\n<ul>\n",
+        contents.bookmarks.len()
+    );
+    for bookmark in &contents.bookmarks {
+        let folder = bookmark
+            .folder
+            .as_deref()
+            .map(|name| format!(" in {}", escape(name)))
+            .unwrap_or_default();
+        let _ = writeln!(
+            html,
+            "<li><time>{}</time> post {}{}</li>",
+            bookmark.created_at.format("%Y-%m-%d %H:%M"),
+            bookmark.post_id,
+            folder
+        );
+    }
+    html.push_str("</ul>\n");
+
+    let _ = write!(html, "<h2>Media ({})</h2>\n<ul>\n", contents.media.len());
+    for media in &contents.media {
+        match &media.file {
+            Some(file) => {
+                let file = escape(file);
+                let _ = writeln!(
+                    html,
+                    "<li><a href=\"{file}\">{file}</a> {}</li>",
+                    media.mime
+                );
+            }
+            None => {
+                let _ = writeln!(html, "<li>{} {} (missing)</li>", media.id, media.mime);
+            }
+        }
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,32 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+/// How often running jobs, data exports and imports refresh their lock
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Runs `work` and calls `beat` every [`HEARTBEAT_INTERVAL`] until it's done,
+/// so other workers can tell it's still running. Failed heartbeats are only
+/// logged, the work carries on.
+pub async fn with_heartbeat<T, B, F, E>(work: impl Future<Output = T>, mut beat: B) -> T
+where
+    B: FnMut() -> F,
+    F: Future<Output = Result<(), E>>,
+    E: Display,
+{
+    tokio::pin!(work);
+    let mut interval = tokio::time::interval_at(
+        tokio::time::Instant::now() + HEARTBEAT_INTERVAL,
+        HEARTBEAT_INTERVAL,
+    );
+    loop {
+        tokio::select! {
+            output = &mut work => return output,
+            _ = interval.tick() => {
+                if let Err(e) = beat().await {
+                    tracing::error!("Can't refresh the lock of running work: {}", e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::error::{EncodingError, ImageFormatHint};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader, Limits};
+
+/// Longest accepted side of an uploaded image, in pixels
+pub const MAX_DIMENSION: u32 = 10_000;
+/// Largest accepted image, in pixels. Checked before decoding so small files
+/// that expand to huge bitmaps are rejected early.
+pub const MAX_PIXELS: u64 = 40_000_000;
+/// Upper bound for the memory a decoder may allocate
+const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+const WEBP_QUALITY: f32 = 80.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageVariant {
+    Original,
+    Small,
+    Thumbnail,
+}
+
+impl ImageVariant {
+    pub const ALL: [ImageVariant; 3] = [Self::Original, Self::Small, Self::Thumbnail];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Original => "original",
+            Self::Small => "small",
+            Self::Thumbnail => "thumbnail",
+        }
+    }
+
+    /// Longest side of the variant, `None` keeps the original size.
+    fn max_side(&self) -> Option<u32> {
+        match self {
+            Self::Original => None,
+            Self::Small => Some(640),
+            Self::Thumbnail => Some(160),
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|variant| variant.name() == value)
+    }
+}
+
+/// Images shown on a profile. They are cropped to a fixed size on upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileImage {
+    Avatar,
+    Banner,
+}
+
+impl ProfileImage {
+    /// Width and height in pixels
+    pub fn size(&self) -> (u32, u32) {
+        match self {
+            Self::Avatar => (400, 400),
+            Self::Banner => (1500, 500),
+        }
+    }
+}
+
+pub struct ProcessedImage {
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: String,
+    /// Re-encoded files, one per variant in the order of [`ImageVariant::ALL`].
+    /// Empty for GIFs, which are kept as uploaded so animations survive.
+    pub variants: Vec<(ImageVariant, Vec<u8>)>,
+}
+
+/// Decodes an image, applies its EXIF orientation and re-encodes it in every
+/// variant. Re-encoding drops all metadata, EXIF and GPS included.
+/// GIFs are only decoded to read their size and blurhash. `crop` scales and
+/// crops the image around its center to fill the given width and height.
+///
+/// This is CPU bound, call it from a blocking thread.
+pub fn process_image(
+    data: &[u8],
+    format: ImageFormat,
+    crop: Option<(u32, u32)>,
+) -> Result<ProcessedImage, ImageError> {
+    let mut image = decode(data, format)?;
+    if let Some((width, height)) = crop {
+        image = image.resize_to_fill(width, height, FilterType::Lanczos3);
+    }
+    let (width, height) = (image.width(), image.height());
+
+    let mut variants = Vec::with_capacity(ImageVariant::ALL.len());
+    for variant in ImageVariant::ALL
+        .into_iter()
+        .filter(|_| format != ImageFormat::Gif)
+    {
+        let resized = match variant.max_side() {
+            Some(side) if width > side || height > side => image.thumbnail(side, side),
+            _ => image.clone(),
+        };
+        variants.push((variant, encode(&resized, format)?));
+    }
+
+    // The hash only carries a few colors, a tiny copy is plenty
+    let preview = image.thumbnail(32, 32).to_rgba8();
+    let blurhash = blurhash::encode(4, 3, preview.width(), preview.height(), preview.as_raw())
+        .unwrap_or_default();
+
+    Ok(ProcessedImage {
+        width,
+        height,
+        blurhash,
+        variants,
+    })
+}
+
+pub fn decode(data: &[u8], format: ImageFormat) -> Result<DynamicImage, ImageError> {
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_ALLOC);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    if u64::from(width) * u64::from(height) > MAX_PIXELS {
+        return Err(ImageError::Limits(image::error::LimitError::from_kind(
+            image::error::LimitErrorKind::DimensionError,
+        )));
+    }
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Encodes in the upload format. Formats without an encoder fall back to PNG.
+pub fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    let mut out = Vec::new();
+    match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))?,
+        // The image crate only encodes lossless WebP, which is often larger than the upload
+        ImageFormat::WebP => {
+            let rgba = image.to_rgba8();
+            let encoded = webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height())
+                .encode_simple(false, WEBP_QUALITY)
+                .map_err(|e| {
+                    ImageError::Encoding(EncodingError::new(
+                        ImageFormatHint::Exact(ImageFormat::WebP),
+                        format!("{:?}", e),
+                    ))
+                })?;
+            out.extend_from_slice(&encoded);
+        }
+        _ => image.write_with_encoder(PngEncoder::new(&mut out))?,
+    }
+    Ok(out)
+}
@@ -0,0 +1,319 @@
+use std::io::{Cursor, Read};
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use scraper::{ElementRef, Html, Node};
+use serde::Deserialize;
+use thiserror::Error;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Largest file read from an uploaded ZIP, bigger ones are treated as broken
+const MAX_ENTRY_BYTES: u64 = 512 * 1024 * 1024;
+const ACTIVITY_STREAMS_PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("Not a Twitter archive or Mastodon outbox")]
+    Unrecognized,
+    #[error("Can't read the archive: {0}")]
+    Zip(#[from] ZipError),
+    #[error("Can't read `{file}`: {error}")]
+    Json {
+        file: &'static str,
+        error: serde_json::Error,
+    },
+    #[error("`{0}` is too large")]
+    TooLarge(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Twitter,
+    Mastodon,
+}
+
+impl ArchiveKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Twitter => "twitter",
+            Self::Mastodon => "mastodon",
+        }
+    }
+}
+
+/// Post found in an archive, with its text already converted to plain text
+#[derive(Clone, Debug)]
+pub struct ImportedPost {
+    /// `twitter:<id>` or `mastodon:<uri>`, unique per network
+    pub source_id: String,
+    pub text: String,
+    pub created_at: NaiveDateTime,
+    /// `source_id` of the replied to post, it may not be in the archive
+    pub reply_to: Option<String>,
+}
+
+/// Tells which network the upload comes from without reading all of it.
+/// Twitter archives are ZIPs with a `tweets.js`, Mastodon outboxes are
+/// either the `outbox.json` itself or the ZIP it comes in.
+pub fn detect(data: &[u8]) -> Result<ArchiveKind, ImportError> {
+    if data.starts_with(b"PK") {
+        let zip = ZipArchive::new(Cursor::new(data))?;
+        if zip.file_names().any(is_outbox) {
+            return Ok(ArchiveKind::Mastodon);
+        }
+        if zip.file_names().any(is_tweets) {
+            return Ok(ArchiveKind::Twitter);
+        }
+        return Err(ImportError::Unrecognized);
+    }
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    match data[start..].first() {
+        Some(b'{') => Ok(ArchiveKind::Mastodon),
+        _ => Err(ImportError::Unrecognized),
+    }
+}
+
+/// Reads the posts of an upload, oldest first so replies come after the
+/// posts they reply to. Retweets, boosts and posts that weren't public are left out.
+pub fn read_posts(data: &[u8]) -> Result<(ArchiveKind, Vec<ImportedPost>), ImportError> {
+    let kind = detect(data)?;
+    let mut posts = match kind {
+        ArchiveKind::Twitter => read_tweets(data)?,
+        ArchiveKind::Mastodon if data.starts_with(b"PK") => {
+            let mut zip = ZipArchive::new(Cursor::new(data))?;
+            let name = zip
+                .file_names()
+                .find(|name| is_outbox(name))
+                .map(str::to_string)
+                .ok_or(ImportError::Unrecognized)?;
+            read_outbox(&read_entry(&mut zip, &name)?)?
+        }
+        ArchiveKind::Mastodon => read_outbox(data)?,
+    };
+    posts.sort_by_key(|post| post.created_at);
+    Ok((kind, posts))
+}
+
+/// Reads the usernames of a follows CSV, one account per line in the first
+/// column. Mastodon `user@domain` addresses are matched by the local part.
+pub fn read_follows(data: &[u8]) -> Vec<String> {
+    let mut usernames: Vec<String> = String::from_utf8_lossy(data)
+        .lines()
+        .filter_map(|line| {
+            let account = line.split(',').next()?.trim().trim_matches('"');
+            let account = account.strip_prefix('@').unwrap_or(account);
+            let username = account.split('@').next()?.trim();
+            let header = username.eq_ignore_ascii_case("account address")
+                || username.eq_ignore_ascii_case("username");
+            (!username.is_empty() && !header).then(|| username.to_string())
+        })
+        .collect();
+    usernames.sort();
+    usernames.dedup();
+    usernames
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn is_outbox(path: &str) -> bool {
+    file_name(path) == "outbox.json"
+}
+
+/// Archives split large tweet lists into `tweets.js`, `tweets-part1.js` and so on
+fn is_tweets(path: &str) -> bool {
+    let name = file_name(path);
+    name == "tweets.js"
+        || name == "tweet.js"
+        || ((name.starts_with("tweets-part") || name.starts_with("tweet-part"))
+            && name.ends_with(".js"))
+}
+
+fn read_entry(zip: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>, ImportError> {
+    let entry = zip.by_name(name)?;
+    let mut data = Vec::new();
+    entry
+        .take(MAX_ENTRY_BYTES + 1)
+        .read_to_end(&mut data)
+        .map_err(ZipError::from)?;
+    if data.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(ImportError::TooLarge(name.to_string()));
+    }
+    Ok(data)
+}
+
+#[derive(Deserialize)]
+struct TweetItem {
+    tweet: Tweet,
+}
+
+#[derive(Deserialize)]
+struct Tweet {
+    id_str: String,
+    #[serde(alias = "text")]
+    full_text: String,
+    /// `Wed Oct 10 20:19:24 +0000 2018`
+    created_at: String,
+    in_reply_to_status_id_str: Option<String>,
+    #[serde(default)]
+    entities: TweetEntities,
+}
+
+#[derive(Default, Deserialize)]
+struct TweetEntities {
+    #[serde(default)]
+    urls: Vec<TweetUrl>,
+    #[serde(default)]
+    media: Vec<TweetUrl>,
+}
+
+#[derive(Deserialize)]
+struct TweetUrl {
+    url: String,
+    expanded_url: Option<String>,
+}
+
+fn read_tweets(data: &[u8]) -> Result<Vec<ImportedPost>, ImportError> {
+    let mut zip = ZipArchive::new(Cursor::new(data))?;
+    let names: Vec<String> = zip
+        .file_names()
+        .filter(|name| is_tweets(name))
+        .map(str::to_string)
+        .collect();
+
+    let mut posts = Vec::new();
+    for name in names {
+        let script = read_entry(&mut zip, &name)?;
+        // The JSON is assigned to a variable, `window.YTD.tweets.part0 = [...]`
+        let json = match script.iter().position(|b| *b == b'=') {
+            Some(i) if !script.starts_with(b"[") => &script[i + 1..],
+            _ => &script[..],
+        };
+        let items: Vec<TweetItem> =
+            serde_json::from_slice(json).map_err(|error| ImportError::Json {
+                file: "tweets.js",
+                error,
+            })?;
+        for TweetItem { tweet } in items {
+            if tweet.full_text.starts_with("RT @") {
+                continue;
+            }
+            let Ok(created_at) =
+                DateTime::parse_from_str(&tweet.created_at, "%a %b %d %H:%M:%S %z %Y")
+            else {
+                continue;
+            };
+            posts.push(ImportedPost {
+                text: tweet_text(&tweet),
+                source_id: format!("twitter:{}", tweet.id_str),
+                created_at: created_at.naive_utc(),
+                reply_to: tweet
+                    .in_reply_to_status_id_str
+                    .map(|id| format!("twitter:{}", id)),
+            });
+        }
+    }
+    Ok(posts)
+}
+
+/// Expands `t.co` links, drops links to attached media and undoes the HTML
+/// escaping of the archive.
+fn tweet_text(tweet: &Tweet) -> String {
+    let mut text = tweet.full_text.clone();
+    for media in &tweet.entities.media {
+        text = text.replace(&media.url, "");
+    }
+    for url in &tweet.entities.urls {
+        if let Some(expanded) = &url.expanded_url {
+            text = text.replace(&url.url, expanded);
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[derive(Deserialize)]
+struct Outbox {
+    #[serde(rename = "orderedItems", default)]
+    ordered_items: Vec<Activity>,
+}
+
+#[derive(Deserialize)]
+struct Activity {
+    #[serde(rename = "type")]
+    kind: String,
+    object: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Note {
+    id: String,
+    #[serde(default)]
+    content: String,
+    published: DateTime<FixedOffset>,
+    #[serde(rename = "inReplyTo")]
+    in_reply_to: Option<String>,
+    #[serde(default)]
+    to: Vec<String>,
+    #[serde(default)]
+    cc: Vec<String>,
+}
+
+fn read_outbox(data: &[u8]) -> Result<Vec<ImportedPost>, ImportError> {
+    let outbox: Outbox = serde_json::from_slice(data).map_err(|error| ImportError::Json {
+        file: "outbox.json",
+        error,
+    })?;
+    Ok(outbox
+        .ordered_items
+        .into_iter()
+        .filter(|activity| activity.kind == "Create")
+        .filter_map(|activity| serde_json::from_value::<Note>(activity.object).ok())
+        // Followers-only posts and direct messages stay private
+        .filter(|note| {
+            note.to
+                .iter()
+                .chain(&note.cc)
+                .any(|to| to == ACTIVITY_STREAMS_PUBLIC)
+        })
+        .map(|note| ImportedPost {
+            text: html_to_text(&note.content),
+            source_id: format!("mastodon:{}", note.id),
+            created_at: note.published.naive_utc(),
+            reply_to: note.in_reply_to.map(|uri| format!("mastodon:{}", uri)),
+        })
+        .collect())
+}
+
+/// Converts Mastodon post HTML to text, paragraphs are separated by an empty line.
+fn html_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut text = String::new();
+    push_text(fragment.root_element(), &mut text);
+    text.trim().to_string()
+}
+
+fn push_text(element: ElementRef, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(t) => text.push_str(t),
+            Node::Element(e) if e.name() == "br" => text.push('\n'),
+            Node::Element(e) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    push_text(child, text);
+                }
+                if e.name() == "p" {
+                    text.push_str("\n\n");
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use url::Url;
+
+/// Characters that end a sentence rather than the url before them
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '}', '\'', '"'];
+
+/// Finds `http` and `https` urls in a post text, in order of appearance.
+pub fn find_urls(text: &str) -> Vec<Url> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("http://").or_else(|| word.find("https://"))?;
+            let candidate = word[start..].trim_end_matches(TRAILING_PUNCTUATION);
+            let url = Url::parse(candidate).ok()?;
+            url.host_str()?;
+            Some(url)
+        })
+        .collect()
+}
+
+/// Normalizes a url so links to the same page share a cache entry. The
+/// fragment and tracking parameters are dropped, the host is already
+/// lowercased and default ports removed by the parser.
+pub fn normalize_url(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && key != "fbclid" && key != "gclid")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    url.to_string()
+}
+
+/// Normalized url of the first link in the text, the one shown as a card.
+pub fn first_link(text: &str) -> Option<String> {
+    find_urls(text).first().map(normalize_url)
+}
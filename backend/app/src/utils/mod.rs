@@ -1,3 +1,13 @@
 pub mod auth;
+pub mod clock;
+pub mod entities;
 pub mod errors;
+pub mod export;
+pub mod heartbeat;
+pub mod images;
+pub mod import;
+pub mod links;
+pub mod scoring;
+pub mod unfurl;
 pub mod validator;
+pub mod webhook;
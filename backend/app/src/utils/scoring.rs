@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::models::post::TagUse;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrendingTag {
+    pub tag: String,
+    pub score: f64,
+    /// Number of uses within the window
+    pub uses: i64,
+}
+
+/// Weight of a single use of a tag that happened `age` ago.
+///
+/// Weights halve every quarter of the window and uses outside of the window don't count.
+pub fn decayed_weight(age: Duration, window: Duration) -> f64 {
+    if age < Duration::zero() || age >= window {
+        return 0.0;
+    }
+    let half_life = window.num_seconds() as f64 / 4.0;
+    (-(age.num_seconds() as f64) * std::f64::consts::LN_2 / half_life).exp()
+}
+
+/// Scores tags by their time-decayed number of uses within `window` before `now`.
+/// Sorted by score, ties broken by tag name.
+pub fn trending_tags(uses: &[TagUse], window: Duration, now: NaiveDateTime) -> Vec<TrendingTag> {
+    let mut scores: HashMap<&str, (f64, i64)> = HashMap::new();
+    for tag_use in uses {
+        let weight = decayed_weight(now - tag_use.created_at, window);
+        if weight > 0.0 {
+            let entry = scores.entry(tag_use.tag.as_str()).or_default();
+            entry.0 += weight;
+            entry.1 += 1;
+        }
+    }
+
+    let mut tags: Vec<TrendingTag> = scores
+        .into_iter()
+        .map(|(tag, (score, uses))| TrendingTag {
+            tag: tag.to_string(),
+            score,
+            uses,
+        })
+        .collect();
+    tags.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.tag.cmp(&b.tag))
+    });
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::utils::clock::{Clock, FixedClock};
+
+    fn clock() -> FixedClock {
+        FixedClock(
+            NaiveDate::from_ymd_opt(2024, 8, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        )
+    }
+
+    fn tag_use(tag: &str, age: Duration) -> TagUse {
+        TagUse {
+            tag: tag.to_string(),
+            created_at: clock().now() - age,
+        }
+    }
+
+    #[test]
+    fn weight_halves_every_quarter_of_the_window() {
+        let window = Duration::hours(24);
+        assert_eq!(decayed_weight(Duration::zero(), window), 1.0);
+        assert!((decayed_weight(Duration::hours(6), window) - 0.5).abs() < 1e-9);
+        assert!((decayed_weight(Duration::hours(12), window) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uses_outside_of_the_window_weigh_nothing() {
+        let window = Duration::hours(1);
+        assert_eq!(decayed_weight(Duration::hours(1), window), 0.0);
+        assert_eq!(decayed_weight(Duration::hours(2), window), 0.0);
+        assert_eq!(decayed_weight(Duration::minutes(-1), window), 0.0);
+    }
+
+    #[test]
+    fn recent_uses_outrank_older_ones() {
+        let uses = vec![
+            tag_use("old", Duration::minutes(50)),
+            tag_use("old", Duration::minutes(55)),
+            tag_use("new", Duration::minutes(1)),
+            tag_use("new", Duration::minutes(2)),
+            tag_use("expired", Duration::hours(2)),
+        ];
+
+        let tags = trending_tags(&uses, Duration::hours(1), clock().now());
+
+        let names: Vec<&str> = tags.iter().map(|tag| tag.tag.as_str()).collect();
+        assert_eq!(names, ["new", "old"]);
+        assert_eq!(tags[0].uses, 2);
+        assert_eq!(tags[1].uses, 2);
+    }
+
+    #[test]
+    fn tied_tags_are_sorted_by_name() {
+        let uses = vec![
+            tag_use("b", Duration::minutes(5)),
+            tag_use("a", Duration::minutes(5)),
+        ];
+
+        let tags = trending_tags(&uses, Duration::hours(1), clock().now());
+
+        let names: Vec<&str> = tags.iter().map(|tag| tag.tag.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[test]
+    fn trending_is_deterministic_for_a_fixed_clock() {
+        let uses = vec![
+            tag_use("a", Duration::minutes(10)),
+            tag_use("b", Duration::minutes(20)),
+            tag_use("c", Duration::minutes(30)),
+        ];
+        let now = clock().now();
+
+        assert_eq!(
+            trending_tags(&uses, Duration::hours(1), now),
+            trending_tags(&uses, Duration::hours(1), now)
+        );
+    }
+}
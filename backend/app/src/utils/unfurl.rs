@@ -0,0 +1,387 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, LOCATION};
+use reqwest::redirect::Policy;
+use scraper::{Html, Selector};
+use thiserror::Error;
+use url::Url;
+
+const MAX_REDIRECTS: usize = 5;
+const MAX_TITLE_CHARS: usize = 300;
+const MAX_DESCRIPTION_CHARS: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum UnfurlError {
+    #[error("Only http and https urls can be fetched")]
+    UnsupportedScheme,
+    #[error("Host `{0}` resolves to a non-public address")]
+    BlockedAddress(String),
+    #[error("Can't resolve host `{0}`")]
+    Unresolvable(String),
+    #[error("Too many redirects")]
+    TooManyRedirects,
+    #[error("Unexpected response status {0}")]
+    Status(reqwest::StatusCode),
+    #[error("Response is not an html page")]
+    NotHtml,
+    #[error("Request timed out")]
+    Timeout,
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+/// OpenGraph or Twitter card metadata of a page
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+}
+
+/// Fetches pages for link previews. Every hop, redirects included, is
+/// resolved and checked before connecting, and the connection is pinned to
+/// the checked address so DNS can't be switched in between.
+#[derive(Clone, Debug)]
+pub struct LinkFetcher {
+    pub user_agent: String,
+    /// Limit for the whole fetch, redirects included
+    pub timeout: Duration,
+    /// Largest part of the page that is read, the rest is ignored
+    pub max_bytes: usize,
+    /// Allows fetching from loopback and private networks, for tests against
+    /// a local server only
+    pub allow_private_networks: bool,
+}
+
+impl LinkFetcher {
+    pub async fn fetch(&self, url: &Url) -> Result<PageMetadata, UnfurlError> {
+        match tokio::time::timeout(self.timeout, self.fetch_page(url.clone())).await {
+            Err(_) => Err(UnfurlError::Timeout),
+            Ok(page) => {
+                let (url, html) = page?;
+                Ok(parse_metadata(&html, &url))
+            }
+        }
+    }
+
+    async fn fetch_page(&self, mut url: Url) -> Result<(Url, String), UnfurlError> {
+        for _ in 0..=MAX_REDIRECTS {
+            let addr = resolve_public(&url, self.allow_private_networks).await?;
+            let host = url.host_str().unwrap_or_default().to_string();
+            let client = reqwest::Client::builder()
+                .user_agent(&self.user_agent)
+                .redirect(Policy::none())
+                .no_proxy()
+                .resolve(&host, addr)
+                .build()?;
+
+            let mut response = client
+                .get(url.clone())
+                .header(reqwest::header::ACCEPT, "text/html")
+                .send()
+                .await?;
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or(UnfurlError::Status(status))?;
+                url = url
+                    .join(location)
+                    .map_err(|_| UnfurlError::Status(status))?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(UnfurlError::Status(status));
+            }
+
+            let is_html = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.contains("text/html") || value.contains("xhtml"));
+            if !is_html {
+                return Err(UnfurlError::NotHtml);
+            }
+            let declared_length = response
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+
+            // Metadata lives in the head, a truncated page is still useful
+            let mut body = Vec::with_capacity(declared_length.unwrap_or(0).min(self.max_bytes));
+            while let Some(chunk) = response.chunk().await? {
+                let room = self.max_bytes - body.len();
+                body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+                if body.len() >= self.max_bytes {
+                    break;
+                }
+            }
+            return Ok((url, String::from_utf8_lossy(&body).into_owned()));
+        }
+        Err(UnfurlError::TooManyRedirects)
+    }
+}
+
+/// Resolves the url host and, unless `allow_private_networks` is set, makes
+/// sure every address it points to is public. The returned address is the
+/// one to connect to.
+pub async fn resolve_public(
+    url: &Url,
+    allow_private_networks: bool,
+) -> Result<SocketAddr, UnfurlError> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(UnfurlError::UnsupportedScheme);
+    }
+    let host = url.host_str().ok_or(UnfurlError::UnsupportedScheme)?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    // `host_str` keeps the brackets around IPv6 literals
+    let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+        .await
+        .map_err(|_| UnfurlError::Unresolvable(host.to_string()))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(UnfurlError::Unresolvable(host.to_string()));
+    }
+    if !allow_private_networks && addrs.iter().any(|addr| !is_public_ip(&addr.ip())) {
+        return Err(UnfurlError::BlockedAddress(host.to_string()));
+    }
+    Ok(addrs[0])
+}
+
+/// Whether the address is reachable on the public internet. Loopback,
+/// private, link-local, shared, reserved and multicast ranges are not.
+pub fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_ipv4(&mapped);
+            }
+            let segments = ip.segments();
+            // NAT64 addresses embed an IPv4 address in the last 32 bits
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_public_ipv4(&Ipv4Addr::new(a, b, c, d));
+            }
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local fc00::/7
+                || (segments[0] & 0xfe00) == 0xfc00
+                // Link-local fe80::/10
+                || (segments[0] & 0xffc0) == 0xfe80
+                // Documentation 2001:db8::/32
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+fn is_public_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // "This network" 0.0.0.0/8
+        || a == 0
+        // Shared address space 100.64.0.0/10
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking 198.18.0.0/15
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved 240.0.0.0/4
+        || a >= 240)
+}
+
+/// Extracts OpenGraph metadata, falling back to Twitter card tags and then
+/// to the plain html title and description. Relative image urls are resolved
+/// against the page url.
+pub fn parse_metadata(html: &str, page_url: &Url) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let meta = Selector::parse("meta").unwrap();
+    let title_selector = Selector::parse("title").unwrap();
+
+    let find = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            document.select(&meta).find_map(|element| {
+                let element = element.value();
+                let key = element.attr("property").or_else(|| element.attr("name"))?;
+                if !key.eq_ignore_ascii_case(name) {
+                    return None;
+                }
+                let content = element.attr("content")?.trim();
+                (!content.is_empty()).then(|| content.to_string())
+            })
+        })
+    };
+
+    let title = find(&["og:title", "twitter:title"]).or_else(|| {
+        document
+            .select(&title_selector)
+            .next()
+            .map(|element| element.text().collect::<String>().trim().to_string())
+            .filter(|title| !title.is_empty())
+    });
+    let description = find(&["og:description", "twitter:description", "description"]);
+    let image = find(&[
+        "og:image",
+        "og:image:url",
+        "twitter:image",
+        "twitter:image:src",
+    ])
+    .and_then(|image| page_url.join(&image).ok())
+    .filter(|image| image.scheme() == "http" || image.scheme() == "https")
+    .map(String::from);
+
+    PageMetadata {
+        title: title.map(|title| truncate(&title, MAX_TITLE_CHARS)),
+        description: description.map(|description| truncate(&description, MAX_DESCRIPTION_CHARS)),
+        image,
+        site_name: find(&["og:site_name"]).map(|name| truncate(&name, MAX_TITLE_CHARS)),
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Redirect};
+    use axum::routing::get;
+    use axum::Router;
+
+    use super::*;
+
+    const PAGE: &str = r#"<html><head>
+        <title>Fallback title</title>
+        <meta property="og:title" content="Stub page">
+        <meta name="description" content="A page served by the test stub">
+        <meta property="og:image" content="/cover.png">
+        <meta property="og:site_name" content="Stub">
+        </head><body></body></html>"#;
+
+    fn html(body: String) -> impl IntoResponse {
+        ([(header::CONTENT_TYPE, "text/html; charset=utf-8")], body)
+    }
+
+    /// Serves pages on a local port and returns its base url
+    async fn start_stub() -> Url {
+        let app = Router::new()
+            .route("/page", get(|| async { html(PAGE.to_string()) }))
+            .route("/redirect", get(|| async { Redirect::to("/page") }))
+            .route("/loop", get(|| async { Redirect::to("/loop") }))
+            .route(
+                "/agent",
+                get(|headers: HeaderMap| async move {
+                    let agent = headers[header::USER_AGENT].to_str().unwrap().to_string();
+                    html(format!("<title>{agent}</title>"))
+                }),
+            )
+            .route(
+                "/json",
+                get(|| async { ([(header::CONTENT_TYPE, "application/json")], "{}") }),
+            )
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    html(PAGE.to_string())
+                }),
+            )
+            .route(
+                "/long",
+                get(|| async { html(format!("<title>Long page</title>{}", "x".repeat(100_000))) }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Url::parse(&url).unwrap()
+    }
+
+    fn fetcher() -> LinkFetcher {
+        LinkFetcher {
+            user_agent: "twotty-test".to_string(),
+            timeout: Duration::from_secs(2),
+            max_bytes: 1024,
+            allow_private_networks: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn redirects_are_followed_and_metadata_parsed() {
+        let base = start_stub().await;
+
+        let metadata = fetcher()
+            .fetch(&base.join("redirect").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            metadata,
+            PageMetadata {
+                title: Some("Stub page".to_string()),
+                description: Some("A page served by the test stub".to_string()),
+                image: Some(base.join("cover.png").unwrap().to_string()),
+                site_name: Some("Stub".to_string()),
+            }
+        );
+
+        let metadata = fetcher().fetch(&base.join("agent").unwrap()).await.unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("twotty-test"));
+    }
+
+    #[tokio::test]
+    async fn pages_are_read_up_to_max_bytes() {
+        let base = start_stub().await;
+        let metadata = fetcher().fetch(&base.join("long").unwrap()).await.unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Long page"));
+    }
+
+    #[tokio::test]
+    async fn bad_responses_fail() {
+        let base = start_stub().await;
+        let fetch = |path: &str| {
+            let url = base.join(path).unwrap();
+            async move { fetcher().fetch(&url).await }
+        };
+
+        assert!(matches!(
+            fetch("loop").await,
+            Err(UnfurlError::TooManyRedirects)
+        ));
+        assert!(matches!(fetch("json").await, Err(UnfurlError::NotHtml)));
+        assert!(matches!(
+            fetch("missing").await,
+            Err(UnfurlError::Status(StatusCode::NOT_FOUND))
+        ));
+        assert!(matches!(fetch("slow").await, Err(UnfurlError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn private_addresses_are_blocked() {
+        let base = start_stub().await;
+        let fetcher = LinkFetcher {
+            allow_private_networks: false,
+            ..fetcher()
+        };
+        assert!(matches!(
+            fetcher.fetch(&base.join("page").unwrap()).await,
+            Err(UnfurlError::BlockedAddress(_))
+        ));
+    }
+}
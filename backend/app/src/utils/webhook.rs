@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use sha2::Sha256;
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+use crate::utils::unfurl::{resolve_public, UnfurlError};
+
+pub const EVENT_HEADER: &str = "X-Twotty-Event";
+/// Id of the delivery, the same for every attempt of it
+pub const DELIVERY_HEADER: &str = "X-Twotty-Delivery";
+/// Unix time of the attempt, in seconds
+pub const TIMESTAMP_HEADER: &str = "X-Twotty-Timestamp";
+/// `sha256=` and the hex HMAC-SHA256 of `{timestamp}.{body}`
+pub const SIGNATURE_HEADER: &str = "X-Twotty-Signature";
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error(transparent)]
+    Address(#[from] UnfurlError),
+    #[error("Request timed out")]
+    Timeout,
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+/// Makes a new signing secret.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+/// Signs a delivery body. The timestamp is signed along with it so an old
+/// request can't be replayed with a new timestamp.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Posts events to webhook urls. Like link previews, the host is resolved and
+/// checked before connecting and the connection is pinned to the checked
+/// address. Redirects aren't followed.
+#[derive(Clone, Debug)]
+pub struct WebhookSender {
+    pub user_agent: String,
+    pub timeout: Duration,
+    /// Allows sending to loopback and private networks, for tests against a
+    /// local receiver only
+    pub allow_private_networks: bool,
+}
+
+impl WebhookSender {
+    /// Sends a signed JSON body and returns the response status, whichever it is.
+    pub async fn send(
+        &self,
+        url: &Url,
+        secret: &str,
+        delivery_id: &Uuid,
+        event: &str,
+        body: Vec<u8>,
+        timestamp: i64,
+    ) -> Result<StatusCode, WebhookError> {
+        let addr = resolve_public(url, self.allow_private_networks).await?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .no_proxy()
+            .timeout(self.timeout)
+            .resolve(&host, addr)
+            .build()?;
+
+        let request = client
+            .post(url.clone())
+            .header(USER_AGENT, &self.user_agent)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event)
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign(secret, timestamp, &body))
+            .body(body);
+        match request.send().await {
+            Ok(response) => Ok(response.status()),
+            Err(e) if e.is_timeout() => Err(WebhookError::Timeout),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS "message";
+DROP TABLE IF EXISTS "conversation_member";
+DROP TABLE IF EXISTS "conversation";
+
+ALTER TABLE "user"
+DROP COLUMN dms_from_followers_only;
@@ -0,0 +1,37 @@
+ALTER TABLE "user"
+ADD dms_from_followers_only boolean NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS "conversation" (
+    id UUID NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    -- Members of a one-to-one conversation, smaller id first, NULL for groups
+    direct_first_id UUID,
+    direct_second_id UUID
+);
+
+-- Only one one-to-one conversation per pair of users
+CREATE UNIQUE INDEX IF NOT EXISTS conversation_direct_key ON "conversation" (direct_first_id, direct_second_id);
+
+CREATE TABLE IF NOT EXISTS "conversation_member" (
+    conversation_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    joined_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    last_read_at TIMESTAMP,
+    PRIMARY KEY (conversation_id, user_id),
+    CONSTRAINT conversation_member_conversation_id_fk FOREIGN KEY (conversation_id) REFERENCES "conversation" (id) ON DELETE CASCADE,
+    CONSTRAINT conversation_member_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id)
+);
+
+CREATE INDEX IF NOT EXISTS conversation_member_user_id_idx ON "conversation_member" (user_id);
+
+CREATE TABLE IF NOT EXISTS "message" (
+    id UUID NOT NULL PRIMARY KEY,
+    conversation_id UUID NOT NULL,
+    sender_id UUID NOT NULL,
+    text TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT message_conversation_id_fk FOREIGN KEY (conversation_id) REFERENCES "conversation" (id) ON DELETE CASCADE,
+    CONSTRAINT message_sender_id_fk FOREIGN KEY (sender_id) REFERENCES "user" (id)
+);
+
+CREATE INDEX IF NOT EXISTS message_conversation_id_created_at_idx ON "message" (conversation_id, created_at DESC);
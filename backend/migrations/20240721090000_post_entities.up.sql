@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS "post_mention" (
+    post_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    PRIMARY KEY (post_id, user_id),
+    CONSTRAINT post_mention_post_id_fk FOREIGN KEY (post_id) REFERENCES "post" (id) ON DELETE CASCADE,
+    CONSTRAINT post_mention_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS post_mention_user_id_idx ON "post_mention" (user_id);
+
+CREATE TABLE IF NOT EXISTS "post_tag" (
+    post_id UUID NOT NULL,
+    tag VARCHAR(255) NOT NULL,
+    PRIMARY KEY (post_id, tag),
+    CONSTRAINT post_tag_post_id_fk FOREIGN KEY (post_id) REFERENCES "post" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS post_tag_tag_idx ON "post_tag" (tag);
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS user_username_prefix_idx;
+DROP INDEX IF EXISTS user_search_idx;
+DROP INDEX IF EXISTS post_search_idx;
+
+ALTER TABLE "user" DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE "post" DROP COLUMN IF EXISTS search_vector;
@@ -0,0 +1,13 @@
+ALTER TABLE "post"
+ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+GENERATED ALWAYS AS (to_tsvector('simple', text)) STORED;
+
+CREATE INDEX IF NOT EXISTS post_search_idx ON "post" USING GIN (search_vector);
+
+ALTER TABLE "user"
+ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+GENERATED ALWAYS AS (to_tsvector('simple', username || ' ' || about)) STORED;
+
+CREATE INDEX IF NOT EXISTS user_search_idx ON "user" USING GIN (search_vector);
+
+CREATE INDEX IF NOT EXISTS user_username_prefix_idx ON "user" (lower(username) text_pattern_ops);
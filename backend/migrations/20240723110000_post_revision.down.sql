@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS "post_revision";
+
+ALTER TABLE "post"
+DROP COLUMN edited_at;
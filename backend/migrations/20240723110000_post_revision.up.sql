@@ -0,0 +1,12 @@
+ALTER TABLE "post"
+ADD edited_at TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS "post_revision" (
+    id UUID NOT NULL PRIMARY KEY,
+    post_id UUID NOT NULL,
+    text TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    CONSTRAINT post_revision_post_id_fk FOREIGN KEY (post_id) REFERENCES "post" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS post_revision_post_id_idx ON "post_revision" (post_id, created_at DESC);
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS post_reply_to_id_idx;
+DROP INDEX IF EXISTS post_deleted_at_idx;
+
+ALTER TABLE "post"
+DROP COLUMN reply_to_id,
+DROP COLUMN deleted_at;
@@ -0,0 +1,9 @@
+ALTER TABLE "post"
+ADD deleted_at TIMESTAMP,
+ADD reply_to_id UUID;
+
+CREATE INDEX IF NOT EXISTS post_deleted_at_idx ON "post" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- No foreign key, a reply keeps the id of its post after that is purged so
+-- it can show a tombstone in its place
+CREATE INDEX IF NOT EXISTS post_reply_to_id_idx ON "post" (reply_to_id);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS "media" (
+    id UUID NOT NULL PRIMARY KEY,
+    owner_id UUID NOT NULL,
+    post_id UUID,
+    position SMALLINT NOT NULL DEFAULT 0,
+    storage_key VARCHAR(255) NOT NULL,
+    mime VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT media_owner_id_fk FOREIGN KEY (owner_id) REFERENCES "user" (id) ON DELETE CASCADE,
+    CONSTRAINT media_post_id_fk FOREIGN KEY (post_id) REFERENCES "post" (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS media_post_id_idx ON "media" (post_id);
@@ -0,0 +1,5 @@
+ALTER TABLE "media" DROP COLUMN IF EXISTS thumbnail_key;
+ALTER TABLE "media" DROP COLUMN IF EXISTS small_key;
+ALTER TABLE "media" DROP COLUMN IF EXISTS blurhash;
+ALTER TABLE "media" DROP COLUMN IF EXISTS height;
+ALTER TABLE "media" DROP COLUMN IF EXISTS width;
@@ -0,0 +1,5 @@
+ALTER TABLE "media" ADD COLUMN IF NOT EXISTS width INTEGER;
+ALTER TABLE "media" ADD COLUMN IF NOT EXISTS height INTEGER;
+ALTER TABLE "media" ADD COLUMN IF NOT EXISTS blurhash VARCHAR(64);
+ALTER TABLE "media" ADD COLUMN IF NOT EXISTS small_key VARCHAR(255);
+ALTER TABLE "media" ADD COLUMN IF NOT EXISTS thumbnail_key VARCHAR(255);
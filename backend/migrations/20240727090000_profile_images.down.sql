@@ -0,0 +1,4 @@
+ALTER TABLE "user" DROP CONSTRAINT IF EXISTS user_banner_media_fk;
+ALTER TABLE "user" DROP CONSTRAINT IF EXISTS user_avatar_media_fk;
+ALTER TABLE "user" DROP COLUMN IF EXISTS banner_media_id;
+ALTER TABLE "user" DROP COLUMN IF EXISTS avatar_media_id;
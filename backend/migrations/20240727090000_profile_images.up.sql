@@ -0,0 +1,6 @@
+-- Uploaded profile images are kept next to the old hotlinked avatar URLs, which
+-- stay in use until the user uploads a picture or resets it
+ALTER TABLE "user" ADD COLUMN avatar_media_id UUID;
+ALTER TABLE "user" ADD COLUMN banner_media_id UUID;
+ALTER TABLE "user" ADD CONSTRAINT user_avatar_media_fk FOREIGN KEY (avatar_media_id) REFERENCES "media" (id) ON DELETE SET NULL;
+ALTER TABLE "user" ADD CONSTRAINT user_banner_media_fk FOREIGN KEY (banner_media_id) REFERENCES "media" (id) ON DELETE SET NULL;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS "link_preview" (
+    url VARCHAR(2048) NOT NULL PRIMARY KEY,
+    title TEXT,
+    description TEXT,
+    image TEXT,
+    site_name TEXT,
+    fetched_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
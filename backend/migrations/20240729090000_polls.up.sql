@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS "poll" (
+    post_id UUID NOT NULL PRIMARY KEY,
+    multiple BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at TIMESTAMP NOT NULL,
+    -- When the author was told the poll closed
+    closed_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT poll_post_id_fk FOREIGN KEY (post_id) REFERENCES "post" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS poll_open_expires_at_idx ON "poll" (expires_at) WHERE closed_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS "poll_option" (
+    post_id UUID NOT NULL,
+    position SMALLINT NOT NULL,
+    text VARCHAR(50) NOT NULL,
+    PRIMARY KEY (post_id, position),
+    CONSTRAINT poll_option_post_id_fk FOREIGN KEY (post_id) REFERENCES "poll" (post_id) ON DELETE CASCADE
+);
+
+-- One row per voter, multiple choice polls store every chosen option in it
+CREATE TABLE IF NOT EXISTS "poll_vote" (
+    post_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    options SMALLINT[] NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT poll_vote_post_id_user_id_key UNIQUE (post_id, user_id),
+    CONSTRAINT poll_vote_post_id_fk FOREIGN KEY (post_id) REFERENCES "poll" (post_id) ON DELETE CASCADE,
+    CONSTRAINT poll_vote_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS post_scheduled_at_idx;
+ALTER TABLE "post" DROP CONSTRAINT IF EXISTS post_draft_not_scheduled_check;
+ALTER TABLE "post" DROP COLUMN IF EXISTS scheduled_at;
+ALTER TABLE "post" DROP COLUMN IF EXISTS draft;
@@ -0,0 +1,6 @@
+ALTER TABLE "post" ADD COLUMN IF NOT EXISTS draft BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE "post" ADD COLUMN IF NOT EXISTS scheduled_at TIMESTAMP;
+ALTER TABLE "post" ADD CONSTRAINT post_draft_not_scheduled_check CHECK (NOT (draft AND scheduled_at IS NOT NULL));
+
+CREATE INDEX IF NOT EXISTS post_scheduled_at_idx ON "post" (scheduled_at)
+    WHERE scheduled_at IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS "bookmark";
+DROP TABLE IF EXISTS "bookmark_folder";
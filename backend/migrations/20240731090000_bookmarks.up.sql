@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS "bookmark_folder" (
+    id UUID NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT bookmark_folder_user_id_name_key UNIQUE (user_id, name),
+    CONSTRAINT bookmark_folder_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "bookmark" (
+    id UUID NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    post_id UUID NOT NULL,
+    folder_id UUID,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT bookmark_user_id_post_id_key UNIQUE (user_id, post_id),
+    CONSTRAINT bookmark_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE,
+    CONSTRAINT bookmark_post_id_fk FOREIGN KEY (post_id) REFERENCES "post" (id) ON DELETE CASCADE,
+    CONSTRAINT bookmark_folder_id_fk FOREIGN KEY (folder_id) REFERENCES "bookmark_folder" (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS bookmark_user_id_created_at_idx ON "bookmark" (user_id, created_at DESC);
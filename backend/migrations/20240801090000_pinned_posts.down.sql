@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS post_pinned_idx;
+ALTER TABLE "post" DROP COLUMN IF EXISTS pinned_at;
@@ -0,0 +1,4 @@
+ALTER TABLE "post" ADD COLUMN IF NOT EXISTS pinned_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS post_pinned_idx ON "post" (author_id, pinned_at)
+    WHERE pinned_at IS NOT NULL;
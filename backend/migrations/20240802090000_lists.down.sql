@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS "list_subscription";
+DROP TABLE IF EXISTS "list_member";
+DROP TABLE IF EXISTS "list";
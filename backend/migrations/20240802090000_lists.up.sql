@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS "list" (
+    id UUID NOT NULL PRIMARY KEY,
+    owner_id UUID NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    private BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    CONSTRAINT list_owner_id_fk FOREIGN KEY (owner_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS list_owner_id_idx ON "list" (owner_id);
+
+CREATE TABLE IF NOT EXISTS "list_member" (
+    list_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    PRIMARY KEY (list_id, user_id),
+    CONSTRAINT list_member_list_id_fk FOREIGN KEY (list_id) REFERENCES "list" (id) ON DELETE CASCADE,
+    CONSTRAINT list_member_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "list_subscription" (
+    list_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    PRIMARY KEY (list_id, user_id),
+    CONSTRAINT list_subscription_list_id_fk FOREIGN KEY (list_id) REFERENCES "list" (id) ON DELETE CASCADE,
+    CONSTRAINT list_subscription_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS list_subscription_user_id_idx ON "list_subscription" (user_id);
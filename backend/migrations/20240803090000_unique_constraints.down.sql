@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS user_username_lower_key;
+
+ALTER TABLE "following"
+DROP CONSTRAINT IF EXISTS following_from_id_to_id_key;
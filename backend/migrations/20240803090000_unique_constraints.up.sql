@@ -0,0 +1,17 @@
+-- Keep the oldest of any duplicated follow, the service used to check before inserting
+DELETE FROM "following" f
+USING "following" d
+WHERE f.from_id = d.from_id AND f.to_id = d.to_id AND f.id > d.id;
+
+ALTER TABLE "following"
+ADD CONSTRAINT following_from_id_to_id_key UNIQUE (from_id, to_id);
+
+-- Usernames are looked up case-insensitively, rename all but one of each clash
+UPDATE "user" u
+SET username = left(u.username, 246) || '_' || left(u.id::text, 8)
+WHERE EXISTS (
+    SELECT 1 FROM "user" d
+    WHERE lower(d.username) = lower(u.username) AND d.id < u.id
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS user_username_lower_key ON "user" (lower(username));
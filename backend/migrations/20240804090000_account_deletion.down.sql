@@ -0,0 +1,14 @@
+DELETE FROM "message" WHERE sender_id = '00000000-0000-0000-0000-000000000000';
+DELETE FROM "post" WHERE author_id = '00000000-0000-0000-0000-000000000000';
+DELETE FROM "media" WHERE owner_id = '00000000-0000-0000-0000-000000000000';
+DELETE FROM "following"
+WHERE from_id = '00000000-0000-0000-0000-000000000000'
+OR to_id = '00000000-0000-0000-0000-000000000000';
+DELETE FROM "conversation_member" WHERE user_id = '00000000-0000-0000-0000-000000000000';
+DELETE FROM "user" WHERE id = '00000000-0000-0000-0000-000000000000';
+
+DROP INDEX IF EXISTS user_deletion_scheduled_at_idx;
+
+ALTER TABLE "user"
+DROP COLUMN IF EXISTS tokens_valid_after,
+DROP COLUMN IF EXISTS deletion_scheduled_at;
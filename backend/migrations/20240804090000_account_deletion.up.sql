@@ -0,0 +1,16 @@
+ALTER TABLE "user"
+ADD COLUMN deletion_scheduled_at TIMESTAMP,
+ADD COLUMN tokens_valid_after TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc');
+
+CREATE INDEX IF NOT EXISTS user_deletion_scheduled_at_idx
+ON "user" (deletion_scheduled_at) WHERE deletion_scheduled_at IS NOT NULL;
+
+-- Anonymized posts and messages of deleted accounts are handed over to this user.
+-- Its empty password hash never verifies, so nobody can sign in as it.
+UPDATE "user"
+SET username = left(username, 246) || '_' || left(id::text, 8)
+WHERE lower(username) = '[deleted]';
+
+INSERT INTO "user" (id, username, password)
+VALUES ('00000000-0000-0000-0000-000000000000', '[deleted]', '')
+ON CONFLICT (id) DO NOTHING;
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS "data_export" (
+    id UUID NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    -- pending, running, ready, failed or expired
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    storage_key VARCHAR(255),
+    size BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    started_at TIMESTAMP,
+    -- Refreshed while the export is built, stale ones are taken over by another worker
+    locked_at TIMESTAMP,
+    finished_at TIMESTAMP,
+    expires_at TIMESTAMP,
+    CONSTRAINT data_export_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS data_export_user_id_idx ON "data_export" (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS data_export_status_idx
+ON "data_export" (status, created_at) WHERE status IN ('pending', 'running', 'ready');
+
+-- A user has at most one export pending or running at a time
+CREATE UNIQUE INDEX IF NOT EXISTS data_export_in_progress_key
+ON "data_export" (user_id) WHERE status IN ('pending', 'running');
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS "import";
+
+DROP INDEX IF EXISTS post_author_id_source_id_key;
+
+ALTER TABLE "post"
+DROP COLUMN IF EXISTS source_id;
@@ -0,0 +1,39 @@
+ALTER TABLE "post"
+ADD COLUMN IF NOT EXISTS source_id TEXT;
+
+-- Importing the same archive again skips the posts that are already there
+CREATE UNIQUE INDEX IF NOT EXISTS post_author_id_source_id_key
+ON "post" (author_id, source_id) WHERE source_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS "import" (
+    id UUID NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    -- twitter or mastodon, NULL when only follows are imported
+    source VARCHAR(16),
+    -- pending, running, done or failed
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    -- Uploaded files in the media store, deleted once the import has run
+    archive_key VARCHAR(255),
+    follows_key VARCHAR(255),
+    total INTEGER NOT NULL DEFAULT 0,
+    processed INTEGER NOT NULL DEFAULT 0,
+    posts_created INTEGER NOT NULL DEFAULT 0,
+    posts_skipped INTEGER NOT NULL DEFAULT 0,
+    follows_created INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    started_at TIMESTAMP,
+    -- Refreshed while the import runs, stale ones are taken over by another worker
+    locked_at TIMESTAMP,
+    finished_at TIMESTAMP,
+    CONSTRAINT import_user_id_fk FOREIGN KEY (user_id) REFERENCES "user" (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS import_user_id_idx ON "import" (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS import_status_idx
+ON "import" (status, created_at) WHERE status IN ('pending', 'running');
+
+-- A user has at most one import pending or running at a time
+CREATE UNIQUE INDEX IF NOT EXISTS import_in_progress_key
+ON "import" (user_id) WHERE status IN ('pending', 'running');
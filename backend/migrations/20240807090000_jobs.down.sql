@@ -0,0 +1,3 @@
+ALTER TABLE "user" DROP COLUMN IF EXISTS is_admin;
+
+DROP TABLE IF EXISTS "job";
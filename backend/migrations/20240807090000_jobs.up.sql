@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS "job" (
+    id UUID NOT NULL PRIMARY KEY,
+    -- Type of the payload, such as fetch_link_preview
+    kind VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    -- pending, running or failed, finished jobs are deleted
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- When the job can run, pushed back after a failed attempt
+    run_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    -- Refreshed while the job runs, stale ones are taken over by another worker
+    locked_at TIMESTAMP,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    failed_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS job_run_at_idx ON "job" (run_at) WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS job_status_idx ON "job" (status, created_at DESC);
+
+-- Enqueueing looks for the same job waiting to run
+CREATE INDEX IF NOT EXISTS job_kind_payload_idx ON "job" (kind, payload) WHERE status = 'pending';
+
+ALTER TABLE "user" ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,38 @@
+CREATE TABLE IF NOT EXISTS "webhook" (
+    id UUID NOT NULL PRIMARY KEY,
+    owner_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- Key of the HMAC-SHA256 signature sent with every delivery
+    secret TEXT NOT NULL,
+    -- post.created, post.deleted, user.followed, mention and poll.closed
+    events TEXT[] NOT NULL,
+    -- Receives the events of every user instead of only the owner's, set by admins
+    all_users BOOLEAN NOT NULL DEFAULT false,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    -- Failed attempts in a row, the webhook is disabled once there are too many
+    failure_count INTEGER NOT NULL DEFAULT 0,
+    -- When it was disabled for failing
+    disabled_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE INDEX IF NOT EXISTS webhook_owner_id_idx ON "webhook" (owner_id);
+
+CREATE TABLE IF NOT EXISTS "webhook_delivery" (
+    -- Sent along as the idempotency id, the same for every attempt
+    id UUID NOT NULL PRIMARY KEY,
+    webhook_id UUID NOT NULL REFERENCES "webhook" (id) ON DELETE CASCADE,
+    event VARCHAR(32) NOT NULL,
+    -- Body that is sent, the same for every attempt
+    payload JSONB NOT NULL,
+    -- pending, delivered or failed
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- Status code of the last response, NULL when no response came
+    response_status INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    delivered_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS webhook_delivery_webhook_id_idx ON "webhook_delivery" (webhook_id, created_at DESC);
@@ -0,0 +1,2 @@
+-- Suspended accounts can't sign in, set from the admin CLI
+ALTER TABLE "user" ADD COLUMN IF NOT EXISTS suspended_at TIMESTAMP;
@@ -0,0 +1 @@
+ALTER TABLE "job" DROP COLUMN IF EXISTS lease_id;
@@ -0,0 +1,3 @@
+-- Set on every claim, so a worker whose job was taken over as stale can't
+-- change the run that replaced it
+ALTER TABLE "job" ADD COLUMN IF NOT EXISTS lease_id UUID;
@@ -23,6 +23,7 @@ import {
   Sun,
 } from "lucide-react";
 import { Avatar, AvatarImage, AvatarFallback } from "@/components/ui/avatar";
+import { mediaUrl } from "@/lib/utils";
 import useUserContext from "./user-context";
 import { redirect } from "next/navigation";
 import User from "@/lib/models/user";
@@ -52,7 +53,7 @@ function HeaderDropdownMenu({ user }: { user: User }) {
       <DropdownMenuTrigger asChild>
         <Avatar className="h-9 w-9 cursor-pointer">
           <AvatarImage
-            src={user?.avatar ? user.avatar : "/placeholder-user.jpg"}
+            src={mediaUrl(user?.avatar, "/placeholder-user.jpg")}
           />
           {/* <AvatarFallback>USER</AvatarFallback> */}
         </Avatar>
@@ -12,6 +12,7 @@ import { Card, CardContent } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import Link from "next/link";
 import { Avatar, AvatarImage, AvatarFallback } from "@/components/ui/avatar";
+import { mediaUrl } from "@/lib/utils";
 import User from "@/lib/models/user";
 import { format } from "date-fns";
 import { useState } from "react";
@@ -72,7 +73,7 @@ export function Post({
         <div className="flex items-start gap-4">
           <Avatar className="w-10 h-10 border">
             <AvatarImage
-              src={author.avatar ? author.avatar : "/placeholder-user.jpg"}
+              src={mediaUrl(author.avatar, "/placeholder-user.jpg")}
             />
             <AvatarFallback>{author.username}</AvatarFallback>
           </Avatar>
@@ -21,7 +21,7 @@ import { Textarea } from "@/components/ui/textarea";
 import { Button } from "@/components/ui/button";
 import { Switch } from "@/components/ui/switch";
 import { useState } from "react";
-import { getMe, updateUser } from "@/lib/api";
+import { getMe, removeAvatar, updateUser, uploadAvatar } from "@/lib/api";
 import { profile } from "console";
 import useUserContext from "./user-context";
 import { redirect } from "next/navigation";
@@ -29,7 +29,6 @@ import { Alert, AlertTitle } from "./ui/alert";
 
 interface ProfileSettings {
   username?: string | null;
-  avatar?: string | null;
   about?: string | null;
 }
 
@@ -45,6 +44,7 @@ export function Settings() {
     security: "",
   } as ErrorMessages);
   const [profileSettings, setProfileSettings] = useState({} as ProfileSettings);
+  const [avatarFile, setAvatarFile] = useState(null as File | null);
   const [password, setPassword] = useState("");
 
   async function updateProfile() {
@@ -52,9 +52,6 @@ export function Settings() {
     if (profileSettings.about == "") {
       profileSettings.about = null;
     }
-    if (profileSettings.avatar == "") {
-      profileSettings.avatar = null;
-    }
     if (profileSettings.username == "") {
       profileSettings.username = null;
     }
@@ -66,10 +63,26 @@ export function Settings() {
         profile:
           typeof response === "string"
             ? response
-            : "Something went wrong. Please check that the Avatar url is a valid image link",
+            : "Something went wrong",
       });
       return;
     }
+    if (avatarFile) {
+      const data = new FormData();
+      data.append("file", avatarFile);
+      const uploaded = await uploadAvatar(data);
+      if (uploaded !== true) {
+        setErrorMessages({
+          ...errorMessages,
+          profile:
+            typeof uploaded === "string"
+              ? uploaded
+              : "Something went wrong while uploading the avatar",
+        });
+        return;
+      }
+      setAvatarFile(null);
+    }
     setProfileSettings({} as ProfileSettings);
     setErrorMessages({
       ...errorMessages,
@@ -81,7 +94,22 @@ export function Settings() {
       redirect("/login");
     }
 
-    setUser(user);
+    setUser(newUser);
+  }
+
+  async function resetAvatar() {
+    if (!(await removeAvatar())) {
+      setErrorMessages({
+        ...errorMessages,
+        profile: "Something went wrong while removing the avatar",
+      });
+      return;
+    }
+    const newUser = await getMe();
+    if (!newUser) {
+      redirect("/login");
+    }
+    setUser(newUser);
   }
 
   return (
@@ -128,16 +156,20 @@ export function Settings() {
                 />
               </div>
               <div className="grid gap-2">
-                <Label htmlFor="avatarUrl">Avatar url</Label>
-                <Input
-                  id="avatarUrl"
-                  onChange={(e) =>
-                    setProfileSettings({
-                      ...profileSettings,
-                      avatar: e.target.value,
-                    })
-                  }
-                />
+                <Label htmlFor="avatar">Avatar</Label>
+                <div className="flex items-center gap-2">
+                  <Input
+                    id="avatar"
+                    type="file"
+                    accept="image/png, image/jpeg, image/gif, image/webp"
+                    onChange={(e) => setAvatarFile(e.target.files?.[0] ?? null)}
+                  />
+                  {user?.avatar && (
+                    <Button variant="outline" onClick={resetAvatar}>
+                      Remove
+                    </Button>
+                  )}
+                </div>
               </div>
               <div className="grid gap-2">
                 <Label htmlFor="about">About</Label>
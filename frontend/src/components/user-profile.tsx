@@ -7,6 +7,7 @@
  */
 
 import { Avatar, AvatarImage, AvatarFallback } from "@/components/ui/avatar";
+import { mediaUrl } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
 import { Textarea } from "@/components/ui/textarea";
 import Post from "@/lib/models/post";
@@ -84,7 +85,7 @@ export function UserProfile({
       <div className="flex flex-col items-center gap-4">
         <Avatar className="w-24 h-24 dark:border-zinc-50">
           <AvatarImage
-            src={author.avatar ? author.avatar : "/placeholder-user.jpg"}
+            src={mediaUrl(author.avatar, "/placeholder-user.jpg")}
           />
           <AvatarFallback>JD</AvatarFallback>
         </Avatar>
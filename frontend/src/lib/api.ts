@@ -90,6 +90,39 @@ export async function updateUser(data: object) {
   return true;
 }
 
+export async function uploadAvatar(data: FormData) {
+  const token = cookies().get("access-token")?.value;
+  if (!token) {
+    return false;
+  }
+  const response = await request(`/auth/avatar`, {
+    method: "PUT",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+    body: data,
+  });
+  if (response.status != 200) {
+    const body = await response.json();
+    return body.message as string;
+  }
+  return true;
+}
+
+export async function removeAvatar() {
+  const token = cookies().get("access-token")?.value;
+  if (!token) {
+    return false;
+  }
+  const response = await request(`/auth/avatar`, {
+    method: "DELETE",
+    headers: {
+      Authorization: `Bearer ${token}`,
+    },
+  });
+  return response.status == 200;
+}
+
 export async function getUserPosts(username: string) {
   const token = cookies().get("access-token")?.value;
   if (!token) {
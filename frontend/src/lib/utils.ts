@@ -4,3 +4,14 @@ import { twMerge } from "tailwind-merge"
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+// Uploaded images come as paths on the backend, older avatars as full urls
+export function mediaUrl(path: string | null | undefined, fallback: string) {
+  if (!path) {
+    return fallback
+  }
+  if (path.startsWith("/")) {
+    return `${process.env.NEXT_PUBLIC_BACKEND_URL}${path}`
+  }
+  return path
+}